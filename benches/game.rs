@@ -0,0 +1,92 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use snake_2d::level::Level;
+use snake_2d::{Board, Direction, Game, Pos, Rules, Snake};
+use std::hint::black_box;
+
+const LENGTHS: [usize; 3] = [10, 100, 1000];
+const BOARD: Board = Board::new(128, 128);
+// The snake is laid out in rows this wide, away from the walls so the head
+// always has room to move on.
+const ROW: usize = 100;
+const MARGIN: i32 = 10;
+
+// A snake of `length` segments coiled back and forth from the top left,
+// heading on along its last row.
+fn coiled_snake(length: usize) -> Snake {
+    let cells: Vec<Pos> = (0..length)
+        .map(|i| {
+            let (row, column) = ((i / ROW) as i32, (i % ROW) as i32);
+            let x = if row % 2 == 0 {
+                column
+            } else {
+                ROW as i32 - 1 - column
+            };
+            Pos::new(MARGIN + x, MARGIN + row)
+        })
+        .collect();
+    let dir = if ((length - 1) / ROW).is_multiple_of(2) {
+        Direction::Right
+    } else {
+        Direction::Left
+    };
+    Snake::new(cells.into_iter().rev().collect(), dir)
+}
+
+fn game(length: usize) -> Game {
+    let rules = Rules {
+        board: BOARD,
+        seed: Some(1),
+        ..Rules::default()
+    };
+    let mut game = Game::new(rules, Level::empty());
+    game.restart();
+    game.players[0].snake = coiled_snake(length);
+    game.foods.clear();
+    game
+}
+
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    for &length in &LENGTHS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(length),
+            &length,
+            |b, &length| {
+                b.iter_batched(
+                    || game(length),
+                    |mut game| black_box(game.step(None)),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+    for &length in &LENGTHS {
+        let snake = coiled_snake(length);
+        group.bench_with_input(BenchmarkId::from_parameter(length), &snake, |b, snake| {
+            b.iter(|| black_box(snake.collision()))
+        });
+    }
+    group.finish();
+}
+
+fn place_food(c: &mut Criterion) {
+    let mut group = c.benchmark_group("place_food");
+    for &length in &LENGTHS {
+        let mut game = game(length);
+        group.bench_with_input(BenchmarkId::from_parameter(length), &length, |b, _| {
+            b.iter(|| {
+                game.foods.clear();
+                black_box(game.place_food())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step, collision, place_food);
+criterion_main!(benches);
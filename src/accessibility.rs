@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+// Effects move no faster than this, in cells per second, with reduced motion.
+const REDUCED_EFFECT_SPEED: f64 = 1.5;
+
+// Comfort options for players sensitive to motion or flashing. They never
+// change the simulation; they live on `Game` so every effect drawn from it
+// reads the same settings.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityOptions {
+    pub screen_shake: bool,
+    // The crash flash and the blinking of a respawned snake.
+    pub flashes: bool,
+    pub particles: bool,
+    // Caps the speed of particles and keeps score popups in place.
+    pub reduced_motion: bool,
+}
+
+impl AccessibilityOptions {
+    pub fn effect_speed(&self, speed: f64) -> f64 {
+        if self.reduced_motion {
+            speed.min(REDUCED_EFFECT_SPEED)
+        } else {
+            speed
+        }
+    }
+
+    // Shorthand for the settings screen: everything that moves on its own.
+    pub fn set_reduced_motion(&mut self, reduced: bool) {
+        self.reduced_motion = reduced;
+        self.screen_shake = !reduced;
+        self.particles = !reduced;
+    }
+}
+
+impl Default for AccessibilityOptions {
+    fn default() -> AccessibilityOptions {
+        AccessibilityOptions {
+            screen_shake: true,
+            flashes: true,
+            particles: true,
+            reduced_motion: false,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use log::error;
+use snake_2d::food::FoodKind;
+use snake_2d::{BoardMode, Game, GameEvent, Locale, TickResult};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const TARGET_LENGTH: usize = 20;
+const SURVIVE_SECONDS: f64 = 300.0;
+const GOLDEN_TARGET: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Achievement {
+    LongSnake,
+    Survivor,
+    WrapChampion,
+    GoldRush,
+}
+
+pub const ACHIEVEMENTS: [Achievement; 4] = [
+    Achievement::LongSnake,
+    Achievement::Survivor,
+    Achievement::WrapChampion,
+    Achievement::GoldRush,
+];
+
+impl Achievement {
+    pub fn name(self, locale: &Locale) -> String {
+        locale
+            .get(&format!("achievement.{}", self.id()))
+            .to_string()
+    }
+
+    pub fn description(self, locale: &Locale) -> String {
+        let key = format!("achievement.{}.goal", self.id());
+        match self {
+            Achievement::LongSnake => locale.format(&key, &[("length", &TARGET_LENGTH)]),
+            Achievement::Survivor => locale.format(&key, &[("minutes", &(SURVIVE_SECONDS / 60.0))]),
+            Achievement::WrapChampion => locale.format(&key, &[]),
+            Achievement::GoldRush => locale.format(&key, &[("count", &GOLDEN_TARGET)]),
+        }
+    }
+
+    fn id(self) -> &'static str {
+        match self {
+            Achievement::LongSnake => "long_snake",
+            Achievement::Survivor => "survivor",
+            Achievement::WrapChampion => "wrap_champion",
+            Achievement::GoldRush => "gold_rush",
+        }
+    }
+}
+
+pub struct Achievements {
+    unlocked: Vec<Achievement>,
+    golden: Vec<u32>,
+    path: Option<PathBuf>,
+}
+
+impl Achievements {
+    pub fn load() -> Achievements {
+        let path = dirs::config_dir().map(|dir| dir.join("snake_2d").join("achievements.txt"));
+        let unlocked = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|contents| {
+                ACHIEVEMENTS
+                    .iter()
+                    .copied()
+                    .filter(|a| contents.lines().any(|line| line.trim() == a.id()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Achievements {
+            unlocked,
+            golden: Vec::new(),
+            path,
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents: String = self
+            .unlocked
+            .iter()
+            .map(|a| format!("{}\n", a.id()))
+            .collect();
+        fs::write(path, contents)
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    pub fn new_game(&mut self) {
+        self.golden.clear();
+    }
+
+    // Checks the goals for the given players after a tick and returns the
+    // achievements unlocked by it.
+    pub fn update(
+        &mut self,
+        game: &Game,
+        players: &[usize],
+        result: TickResult,
+    ) -> Vec<Achievement> {
+        self.golden.resize(game.players.len(), 0);
+        for event in &game.events {
+            if let GameEvent::FoodEaten {
+                player,
+                kind: FoodKind::Golden,
+                ..
+            } = *event
+            {
+                self.golden[player] += 1;
+            }
+        }
+
+        let mut reached = Vec::new();
+        for &i in players {
+            let player = &game.players[i];
+            if player.snake.len() >= TARGET_LENGTH {
+                reached.push(Achievement::LongSnake);
+            }
+            if player.alive() && game.elapsed >= SURVIVE_SECONDS {
+                reached.push(Achievement::Survivor);
+            }
+            if game.rules.board_mode == BoardMode::Wrap && won(game, i, result) {
+                reached.push(Achievement::WrapChampion);
+            }
+            if self.golden[i] >= GOLDEN_TARGET {
+                reached.push(Achievement::GoldRush);
+            }
+        }
+
+        let mut unlocked = Vec::new();
+        for achievement in reached {
+            if !self.is_unlocked(achievement) {
+                self.unlocked.push(achievement);
+                unlocked.push(achievement);
+            }
+        }
+        if !unlocked.is_empty() {
+            if let Err(e) = self.save() {
+                error!("Could not save achievements: {}", e);
+            }
+        }
+        unlocked
+    }
+}
+
+// Filling the board wins outright; against others the last snake alive wins.
+fn won(game: &Game, player: usize, result: TickResult) -> bool {
+    match result {
+        TickResult::Won => true,
+        TickResult::Died(_) if game.players.len() > 1 => {
+            game.players[player].alive() && game.players.iter().filter(|p| p.alive()).count() == 1
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,252 @@
+use crate::level::Level;
+use crate::pos::Pos;
+use crate::rules::{BoardMode, Rules};
+use crate::snake::{Direction, Snake};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+const MAX_FLOOD_FILL: usize = 200;
+const MIN_SEARCH_DEPTH: usize = 2;
+// How much more often a weaker step makes the snake slip, up to
+// MAX_MISTAKES of its moves.
+const MISTAKE_STEP: f64 = 0.05;
+const MAX_MISTAKES: f64 = 0.3;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    // Starts out as Normal and plays stronger or weaker to keep the score
+    // close, see `Game::rubber_band`.
+    Adaptive,
+}
+
+// What an AI snake plays by: how many moves ahead it looks for food, and
+// the chance it takes a random safe turn instead on each move.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Tuning {
+    pub search_depth: usize,
+    pub mistakes: f64,
+}
+
+impl Tuning {
+    pub fn stronger(self, rules: &Rules) -> Tuning {
+        Tuning {
+            search_depth: (self.search_depth * 2).min(rules.board.cells()),
+            mistakes: (self.mistakes - MISTAKE_STEP).max(0.0),
+        }
+    }
+
+    pub fn weaker(self) -> Tuning {
+        Tuning {
+            search_depth: (self.search_depth / 2).max(MIN_SEARCH_DEPTH),
+            mistakes: (self.mistakes + MISTAKE_STEP).min(MAX_MISTAKES),
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Adaptive => "Adaptive",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "ai.easy",
+            Difficulty::Normal => "ai.normal",
+            Difficulty::Hard => "ai.hard",
+            Difficulty::Adaptive => "ai.adaptive",
+        }
+    }
+
+    pub fn next(self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Adaptive,
+            Difficulty::Adaptive => Difficulty::Easy,
+        }
+    }
+
+    // How the difficulty plays; where an adaptive AI starts from.
+    pub fn tuning(self, rules: &Rules) -> Tuning {
+        let search_depth = match self {
+            Difficulty::Easy => 4,
+            Difficulty::Normal | Difficulty::Adaptive => 12,
+            Difficulty::Hard => rules.board.cells(),
+        };
+        Tuning {
+            search_depth,
+            mistakes: 0.0,
+        }
+    }
+}
+
+// `rng` is only drawn from for a snake that makes mistakes, so games
+// without one play out the same as before they existed.
+pub fn next_direction<R: Rng>(
+    snake: &Snake,
+    blocked: &HashSet<(i32, i32)>,
+    food: &HashSet<(i32, i32)>,
+    tuning: Tuning,
+    rules: &Rules,
+    level: &Level,
+    rng: &mut R,
+) -> Direction {
+    let head = match snake.head() {
+        Ok(head) => (head.x, head.y),
+        Err(_) => return snake.dir,
+    };
+
+    if tuning.mistakes > 0.0 && rng.gen_bool(tuning.mistakes) {
+        let safe: Vec<Direction> = DIRECTIONS
+            .iter()
+            .copied()
+            .filter(|&dir| dir != snake.dir.opposite())
+            .filter(|&dir| step(head, dir, rules, level).is_some_and(|n| !blocked.contains(&n)))
+            .collect();
+        if !safe.is_empty() {
+            return safe[rng.gen_range(0, safe.len())];
+        }
+    }
+
+    find_path(head, blocked, food, tuning.search_depth, rules, level)
+        .or_else(|| most_open_direction(snake.dir, head, blocked, rules, level))
+        .unwrap_or(snake.dir)
+}
+
+fn find_path(
+    head: (i32, i32),
+    blocked: &HashSet<(i32, i32)>,
+    food: &HashSet<(i32, i32)>,
+    depth: usize,
+    rules: &Rules,
+    level: &Level,
+) -> Option<Direction> {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    for &dir in DIRECTIONS.iter() {
+        if let Some(next) = step(head, dir, rules, level) {
+            if !blocked.contains(&next) && visited.insert(next) {
+                queue.push_back((next, dir, 1));
+            }
+        }
+    }
+
+    while let Some((pos, first, distance)) = queue.pop_front() {
+        if food.contains(&pos) {
+            return Some(first);
+        }
+        if distance >= depth {
+            continue;
+        }
+        for &dir in DIRECTIONS.iter() {
+            if let Some(next) = step(pos, dir, rules, level) {
+                if !blocked.contains(&next) && visited.insert(next) {
+                    queue.push_back((next, first, distance + 1));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn most_open_direction(
+    current: Direction,
+    head: (i32, i32),
+    blocked: &HashSet<(i32, i32)>,
+    rules: &Rules,
+    level: &Level,
+) -> Option<Direction> {
+    DIRECTIONS
+        .iter()
+        .filter_map(|&dir| {
+            let next = step(head, dir, rules, level)?;
+            if blocked.contains(&next) {
+                return None;
+            }
+            let preference = if dir == current { 1 } else { 0 };
+            Some(((open_area(next, blocked, rules, level), preference), dir))
+        })
+        .max_by_key(|&(score, _)| score)
+        .map(|(_, dir)| dir)
+}
+
+fn open_area(
+    start: (i32, i32),
+    blocked: &HashSet<(i32, i32)>,
+    rules: &Rules,
+    level: &Level,
+) -> usize {
+    let mut visited = HashSet::new();
+    let mut stack = vec![start];
+    visited.insert(start);
+
+    while let Some(pos) = stack.pop() {
+        if visited.len() >= MAX_FLOOD_FILL {
+            break;
+        }
+        for &dir in DIRECTIONS.iter() {
+            if let Some(next) = step(pos, dir, rules, level) {
+                if !blocked.contains(&next) && visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    visited.len()
+}
+
+// The cell a move lands on, following portals like `Snake::update_direction`.
+fn step(pos: (i32, i32), dir: Direction, rules: &Rules, level: &Level) -> Option<(i32, i32)> {
+    let next = Pos::new(pos.0, pos.1) + dir;
+    let cell = if rules.board_mode == BoardMode::Wrap {
+        next.wrapped(rules.board)
+    } else if !next.on(rules.board) {
+        return None;
+    } else {
+        next
+    };
+    Some(
+        level
+            .portal_exit(cell.x, cell.y)
+            .unwrap_or((cell.x, cell.y)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn tuning_steps_stay_within_bounds() {
+        let rules = Rules {
+            board: Board::new(10, 10),
+            ..Rules::default()
+        };
+        let normal = Difficulty::Normal.tuning(&rules);
+
+        let weakest = (0..10).fold(normal, |tuning, _| tuning.weaker());
+        assert_eq!(weakest.search_depth, MIN_SEARCH_DEPTH);
+        assert_eq!(weakest.mistakes, MAX_MISTAKES);
+
+        let strongest = (0..10).fold(weakest, |tuning, _| tuning.stronger(&rules));
+        assert_eq!(strongest, Difficulty::Hard.tuning(&rules));
+    }
+}
@@ -0,0 +1,62 @@
+use graphics::{Context, Image, Transformed};
+use log::warn;
+use opengl_graphics::{GlGraphics, Texture, TextureSettings};
+use std::path::Path;
+
+pub const SPRITES_PATH: &str = "assets/sprites.png";
+const TILE_SIZE: f64 = 32.0;
+
+#[derive(Clone, Copy)]
+pub enum Sprite {
+    Head,
+    Body,
+    Tail,
+    Food,
+    Obstacle,
+}
+
+pub struct Sprites {
+    texture: Texture,
+}
+
+impl Sprites {
+    pub fn load(path: &str) -> Option<Sprites> {
+        if !Path::new(path).exists() {
+            return None;
+        }
+        match Texture::from_path(path, &TextureSettings::new()) {
+            Ok(texture) => Some(Sprites { texture }),
+            Err(e) => {
+                warn!("Could not load sprites, using plain shapes: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn draw(
+        &self,
+        sprite: Sprite,
+        color: [f32; 4],
+        rect: [f64; 4],
+        rotation: f64,
+        c: &Context,
+        gl: &mut GlGraphics,
+    ) {
+        let (half_width, half_height) = (rect[2] / 2.0, rect[3] / 2.0);
+        let transform = c
+            .transform
+            .trans(rect[0] + half_width, rect[1] + half_height)
+            .rot_deg(rotation)
+            .trans(-half_width, -half_height);
+
+        Image::new_color(color)
+            .src_rect([
+                sprite as usize as f64 * TILE_SIZE,
+                0.0,
+                TILE_SIZE,
+                TILE_SIZE,
+            ])
+            .rect([0.0, 0.0, rect[2], rect[3]])
+            .draw(&self.texture, &c.draw_state, transform, gl);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::config::Config;
+use snake_2d::level::Level;
+use snake_2d::replay::{self, Replay};
+use snake_2d::{Game, TickResult};
+
+// How long the main menu waits for input before the demo starts.
+const IDLE_SECONDS: f64 = 15.0;
+
+// Attract mode: a game playing behind an untouched main menu, either the
+// best classic run or the AI when none has been saved yet. Any input puts
+// the menu back on its own.
+pub struct Attract {
+    idle: f64,
+    tick_accumulator: f64,
+    pub demo: Option<Game>,
+}
+
+impl Attract {
+    pub fn new() -> Attract {
+        Attract {
+            idle: 0.0,
+            tick_accumulator: 0.0,
+            demo: None,
+        }
+    }
+
+    // Call on any input; returns whether a demo was stopped by it.
+    pub fn wake(&mut self) -> bool {
+        self.idle = 0.0;
+        self.demo.take().is_some()
+    }
+
+    // Call on every update while the main menu is shown.
+    pub fn update(&mut self, dt: f64, config: &Config) {
+        if self.demo.is_none() {
+            self.idle += dt;
+            if self.idle < IDLE_SECONDS {
+                return;
+            }
+            self.demo = Some(demo(config));
+            self.tick_accumulator = 0.0;
+        }
+        let game = match self.demo {
+            Some(ref mut game) => game,
+            None => return,
+        };
+        self.tick_accumulator += dt;
+        while self.tick_accumulator >= game.tick_interval() {
+            self.tick_accumulator -= game.tick_interval();
+            let ticks = game.playback.as_ref().map(|p| p.inputs[0].len());
+            let finished = ticks.is_some_and(|ticks| game.tick >= ticks)
+                || matches!(
+                    game.step(None),
+                    TickResult::Died(_)
+                        | TickResult::Won
+                        | TickResult::TimeUp
+                        | TickResult::Failed(_)
+                );
+            if finished {
+                *game = demo(config);
+                self.tick_accumulator = 0.0;
+                return;
+            }
+        }
+    }
+}
+
+fn demo(config: &Config) -> Game {
+    let mut game = best_run(config).unwrap_or_else(|| ai_game(config));
+    game.accessibility = config.accessibility;
+    game
+}
+
+fn best_run(config: &Config) -> Option<Game> {
+    let replay = replay::best_run_path("Classic").and_then(|path| Replay::load(&path).ok())?;
+    let mut rules = config.rules.clone();
+    replay.apply_to(&mut rules);
+    let level = match rules.level {
+        Some(ref spec) => Level::load(spec, rules.board).ok()?,
+        None => Level::empty(),
+    };
+    let mut game = Game::new(rules, level);
+    game.playback = Some(replay);
+    Some(game)
+}
+
+fn ai_game(config: &Config) -> Game {
+    let mut rules = config.rules.clone();
+    rules.board = config.zoom.grid(rules.board);
+    rules.seed = None;
+    rules.players = 1;
+    rules.coop = false;
+    rules.ai_opponent = false;
+    rules.endless = false;
+    rules.shrinking = false;
+    rules.time_limit = None;
+    rules.level = None;
+    let mut game = Game::new(rules, Level::empty());
+    game.players[0].ai = Some(game.rules.ai_difficulty);
+    game
+}
@@ -0,0 +1,185 @@
+use crate::user_assets::SoundPack;
+#[cfg(feature = "audio")]
+use log::warn;
+#[cfg(feature = "audio")]
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+#[cfg(feature = "audio")]
+use std::io::Cursor;
+use std::sync::Arc;
+
+#[cfg(feature = "audio")]
+const MUSIC: &[u8] = include_bytes!("../assets/sounds/music.wav");
+#[cfg(feature = "audio")]
+const MUSIC_VOLUME: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sound {
+    Eat,
+    PowerUp,
+    Death,
+    Menu,
+}
+
+pub const SOUNDS: [Sound; 4] = [Sound::Eat, Sound::PowerUp, Sound::Death, Sound::Menu];
+
+impl Sound {
+    // Its file name in a sound pack, without `.wav`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Sound::Eat => "eat",
+            Sound::PowerUp => "power_up",
+            Sound::Death => "death",
+            Sound::Menu => "menu",
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Sound {
+    fn bytes(self) -> &'static [u8] {
+        match self {
+            Sound::Eat => include_bytes!("../assets/sounds/eat.wav"),
+            Sound::PowerUp => include_bytes!("../assets/sounds/power_up.wav"),
+            Sound::Death => include_bytes!("../assets/sounds/death.wav"),
+            Sound::Menu => include_bytes!("../assets/sounds/menu.wav"),
+        }
+    }
+}
+
+pub struct Audio {
+    muted: bool,
+    volume: f32,
+    // Sounds from the chosen sound pack, played instead of the built-in ones.
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    custom: Vec<(Sound, Arc<[u8]>)>,
+    #[cfg(feature = "audio")]
+    output: Option<Output>,
+}
+
+#[cfg(feature = "audio")]
+struct Output {
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    music: Sink,
+}
+
+impl Audio {
+    pub fn init(muted: bool, volume: f32) -> Audio {
+        let audio = Audio {
+            muted,
+            volume,
+            custom: Vec::new(),
+            #[cfg(feature = "audio")]
+            output: Output::open(),
+        };
+        audio.update_music();
+        audio
+    }
+
+    pub fn play(&self, sound: Sound) {
+        #[cfg(feature = "audio")]
+        {
+            if let (false, Some(output)) = (self.muted, &self.output) {
+                match self.custom.iter().find(|&&(custom, _)| custom == sound) {
+                    Some((_, bytes)) => output.play(bytes.clone(), self.volume),
+                    None => output.play(sound.bytes(), self.volume),
+                }
+            }
+        }
+        #[cfg(not(feature = "audio"))]
+        let _ = sound;
+    }
+
+    // Switches to a sound pack, or back to the built-in sounds with None.
+    pub fn set_pack(&mut self, pack: Option<&SoundPack>) {
+        self.custom = pack.map_or_else(Vec::new, |pack| pack.effects.clone());
+        #[cfg(feature = "audio")]
+        {
+            let music = pack.and_then(|pack| pack.music.clone());
+            if let Some(ref mut output) = self.output {
+                match music {
+                    Some(music) => output.start_music(music),
+                    None => output.start_music(MUSIC),
+                }
+            }
+        }
+        self.update_music();
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.update_music();
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        self.update_music();
+    }
+
+    fn update_music(&self) {
+        #[cfg(feature = "audio")]
+        {
+            if let Some(ref output) = self.output {
+                output.music.set_volume(MUSIC_VOLUME * self.volume);
+                if self.muted {
+                    output.music.pause();
+                } else {
+                    output.music.play();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Output {
+    fn open() -> Option<Output> {
+        let (stream, handle) = match OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("Could not open audio output: {}", e);
+                return None;
+            }
+        };
+        let music = Sink::try_new(&handle).ok()?;
+        let mut output = Output {
+            _stream: stream,
+            handle,
+            music,
+        };
+        output.start_music(MUSIC);
+        Some(output)
+    }
+
+    // Replaces the music, paused until `update_music` says otherwise.
+    fn start_music<B>(&mut self, bytes: B)
+    where
+        B: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        let music = match Sink::try_new(&self.handle) {
+            Ok(music) => music,
+            Err(e) => return warn!("Could not play music: {}", e),
+        };
+        match Decoder::new_looped(Cursor::new(bytes)) {
+            Ok(source) => music.append(source),
+            Err(e) => warn!("Could not decode music: {}", e),
+        }
+        music.pause();
+        self.music = music;
+    }
+
+    fn play<B>(&self, bytes: B, volume: f32)
+    where
+        B: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        match Decoder::new(Cursor::new(bytes)) {
+            Ok(source) => {
+                let source = source.amplify(volume).convert_samples();
+                if let Err(e) = self.handle.play_raw(source) {
+                    warn!("Could not play sound: {}", e);
+                }
+            }
+            Err(e) => warn!("Could not decode sound: {}", e),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+pub const MIN_SIZE: i32 = 5;
+
+// The size of the playing field in cells. Saved as its preset name or as
+// "WxH", so `board = "large"` and `board = "30x20"` both work in the config.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Board {
+    pub columns: i32,
+    pub rows: i32,
+}
+
+pub const BOARD_PRESETS: [(&str, Board); 3] = [
+    ("small", Board::new(15, 15)),
+    ("classic", Board::new(20, 20)),
+    ("large", Board::new(40, 30)),
+];
+
+impl Board {
+    pub const fn new(columns: i32, rows: i32) -> Board {
+        Board { columns, rows }
+    }
+
+    // A preset name or a custom size such as "30x20".
+    pub fn parse(text: &str) -> Option<Board> {
+        if let Some(&(_, board)) = BOARD_PRESETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(text))
+        {
+            return Some(board);
+        }
+        let (columns, rows) = text.split_once(['x', 'X'])?;
+        let board = Board::new(columns.trim().parse().ok()?, rows.trim().parse().ok()?);
+        if board.columns < 1 || board.rows < 1 {
+            return None;
+        }
+        Some(board)
+    }
+
+    pub fn name(self) -> String {
+        match BOARD_PRESETS.iter().find(|&&(_, board)| board == self) {
+            Some((name, _)) => name.to_string(),
+            None => format!("{}x{}", self.columns, self.rows),
+        }
+    }
+
+    pub fn validated(self) -> Board {
+        Board::new(self.columns.max(MIN_SIZE), self.rows.max(MIN_SIZE))
+    }
+
+    pub fn contains(self, x: i32, y: i32) -> bool {
+        self.inside(x, y, 0)
+    }
+
+    // On the board and at least `margin` cells in from every edge.
+    pub fn inside(self, x: i32, y: i32, margin: i32) -> bool {
+        x >= margin && y >= margin && x < self.columns - margin && y < self.rows - margin
+    }
+
+    // Where a cell off the edge comes back in on a wrapping board.
+    pub fn wrap(self, x: i32, y: i32) -> (i32, i32) {
+        (x.rem_euclid(self.columns), y.rem_euclid(self.rows))
+    }
+
+    pub fn cells(self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    pub fn center(self) -> (i32, i32) {
+        (self.columns / 2, self.rows / 2)
+    }
+
+    // Every cell, column by column.
+    pub fn positions(self) -> impl Iterator<Item = (i32, i32)> {
+        (0..self.columns).flat_map(move |x| (0..self.rows).map(move |y| (x, y)))
+    }
+}
+
+impl Default for Board {
+    fn default() -> Board {
+        BOARD_PRESETS[1].1
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Board::parse(&text).ok_or_else(|| {
+            de::Error::custom(format!(
+                "invalid board '{}', expected small, classic, large or WxH",
+                text
+            ))
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use crate::board::Board;
+use crate::food::FoodKind;
+use crate::game::Game;
+use crate::pos::Pos;
+use crate::rules::BoardMode;
+use crate::snake::Direction;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+// Something outside the game that picks a snake's moves.
+pub trait Controller {
+    fn next_move(&mut self, view: &BoardView) -> Direction;
+}
+
+pub struct SnakeView {
+    pub dir: Direction,
+    // Head first.
+    pub body: Vec<Pos>,
+    pub alive: bool,
+}
+
+// What a controller gets to see before each tick.
+pub struct BoardView {
+    pub board: Board,
+    pub wrap: bool,
+    pub tick: usize,
+    // The index in `snakes` of the snake being steered.
+    pub you: usize,
+    pub snakes: Vec<SnakeView>,
+    pub food: Vec<Pos>,
+    pub poison: Vec<Pos>,
+    pub walls: Vec<Pos>,
+    // Outer rings of the board already closed in shrinking games.
+    pub closed: i32,
+}
+
+impl BoardView {
+    pub fn new(game: &Game, you: usize) -> BoardView {
+        let snakes = game
+            .players
+            .iter()
+            .map(|p| SnakeView {
+                dir: p.snake.dir,
+                body: p.snake.segments().collect(),
+                alive: p.alive(),
+            })
+            .collect();
+        let (poison, food) = game
+            .foods
+            .iter()
+            .partition::<Vec<_>, _>(|f| f.kind == FoodKind::Poison);
+        BoardView {
+            board: game.rules.board,
+            wrap: game.rules.board_mode == BoardMode::Wrap,
+            tick: game.tick,
+            you,
+            snakes,
+            food: food.iter().map(|f| f.part).collect(),
+            poison: poison.iter().map(|f| f.part).collect(),
+            walls: game
+                .level
+                .obstacles
+                .iter()
+                .map(|o| Pos::new(o.x, o.y))
+                .collect(),
+            closed: game.closed_rings(),
+        }
+    }
+
+    // Writes the view in the line protocol spoken to bot processes, ending
+    // with "end".
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let edges = if self.wrap { "wrap" } else { "walls" };
+        writeln!(
+            out,
+            "board {} {} {}",
+            self.board.columns, self.board.rows, edges
+        )?;
+        writeln!(out, "tick {}", self.tick)?;
+        writeln!(out, "you {}", self.you)?;
+        for (i, snake) in self.snakes.iter().enumerate() {
+            let state = if snake.alive { "alive" } else { "dead" };
+            write!(out, "snake {} {} {}", i, state, name(snake.dir))?;
+            for part in &snake.body {
+                write!(out, " {},{}", part.x, part.y)?;
+            }
+            writeln!(out)?;
+        }
+        for (word, cells) in &[
+            ("food", &self.food),
+            ("poison", &self.poison),
+            ("wall", &self.walls),
+        ] {
+            for cell in cells.iter() {
+                writeln!(out, "{} {} {}", word, cell.x, cell.y)?;
+            }
+        }
+        if self.closed > 0 {
+            writeln!(out, "closed {}", self.closed)?;
+        }
+        writeln!(out, "end")
+    }
+
+    pub fn heading(&self) -> Direction {
+        self.snakes
+            .get(self.you)
+            .map_or(Direction::Right, |snake| snake.dir)
+    }
+}
+
+fn name(dir: Direction) -> &'static str {
+    match dir {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+        Direction::UpLeft => "up-left",
+        Direction::UpRight => "up-right",
+        Direction::DownLeft => "down-left",
+        Direction::DownRight => "down-right",
+    }
+}
+
+// A move as a bot writes it; anything else keeps the current heading.
+pub fn parse_move(reply: &str) -> Option<Direction> {
+    match reply.trim().to_lowercase().as_str() {
+        "up" | "u" => Some(Direction::Up),
+        "down" | "d" => Some(Direction::Down),
+        "left" | "l" => Some(Direction::Left),
+        "right" | "r" => Some(Direction::Right),
+        "up-left" | "ul" => Some(Direction::UpLeft),
+        "up-right" | "ur" => Some(Direction::UpRight),
+        "down-left" | "dl" => Some(Direction::DownLeft),
+        "down-right" | "dr" => Some(Direction::DownRight),
+        _ => None,
+    }
+}
+
+// A bot program started as a child process. Each tick it is sent the view
+// on stdin and answers with one line on stdout.
+pub struct Process {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    // Why the bot stopped answering; it keeps its heading from then on.
+    pub error: Option<String>,
+}
+
+impl Process {
+    pub fn spawn(path: &str) -> Result<Process, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Could not start bot '{}': {}", path, e))?;
+        let input = child.stdin.take().ok_or("The bot has no stdin")?;
+        let output = child.stdout.take().ok_or("The bot has no stdout")?;
+        Ok(Process {
+            child,
+            input,
+            output: BufReader::new(output),
+            error: None,
+        })
+    }
+
+    fn ask(&mut self, view: &BoardView) -> Result<Option<Direction>, String> {
+        view.write_to(&mut self.input)
+            .and_then(|_| self.input.flush())
+            .map_err(|e| format!("Could not write to the bot: {}", e))?;
+        let mut reply = String::new();
+        match self.output.read_line(&mut reply) {
+            Ok(0) => Err(String::from("The bot exited")),
+            Ok(_) => Ok(parse_move(&reply)),
+            Err(e) => Err(format!("Could not read from the bot: {}", e)),
+        }
+    }
+}
+
+impl Controller for Process {
+    fn next_move(&mut self, view: &BoardView) -> Direction {
+        if self.error.is_some() {
+            return view.heading();
+        }
+        match self.ask(view) {
+            Ok(dir) => dir.unwrap_or_else(|| view.heading()),
+            Err(e) => {
+                self.error = Some(e);
+                view.heading()
+            }
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,175 @@
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use snake_2d::{BoardMode, Game, Locale, Rules};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy)]
+pub enum Goal {
+    Length(usize),
+    Food(u32),
+}
+
+impl Goal {
+    // How far the game is towards the goal, as (current, target).
+    pub fn progress(self, game: &Game) -> (usize, usize) {
+        match self {
+            Goal::Length(target) => (game.players[0].snake.len(), target),
+            Goal::Food(target) => (game.foods_eaten as usize, target as usize),
+        }
+    }
+
+    pub fn reached(self, game: &Game) -> bool {
+        let (current, target) = self.progress(game);
+        current >= target
+    }
+
+    pub fn describe(self, locale: &Locale) -> String {
+        match self {
+            Goal::Length(target) => locale.format("goal.length", &[("target", &target)]),
+            Goal::Food(target) => locale.format("goal.food", &[("target", &target)]),
+        }
+    }
+}
+
+pub struct Stage {
+    // A built-in level, 1-based as with --level.
+    pub level: usize,
+    pub speed: u64,
+    pub goal: Goal,
+}
+
+// Ordered by the number of walls, each one a little faster.
+pub const STAGES: [Stage; 5] = [
+    Stage {
+        level: 1,
+        speed: 5,
+        goal: Goal::Length(8),
+    },
+    Stage {
+        level: 2,
+        speed: 6,
+        goal: Goal::Food(10),
+    },
+    Stage {
+        level: 4,
+        speed: 7,
+        goal: Goal::Length(14),
+    },
+    Stage {
+        level: 3,
+        speed: 8,
+        goal: Goal::Food(16),
+    },
+    Stage {
+        level: 5,
+        speed: 9,
+        goal: Goal::Length(20),
+    },
+];
+
+// How far the player got, kept in `campaign.ron` so a campaign can be
+// continued in a later session.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Campaign {
+    pub stage: usize,
+    // The player's own rules, put back when leaving the campaign.
+    #[serde(skip)]
+    rules: Option<Rules>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Campaign {
+    pub fn load() -> Campaign {
+        let path = dirs::config_dir().map(|dir| dir.join("snake_2d").join("campaign.ron"));
+        let mut campaign: Campaign = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default();
+        campaign.path = path;
+        campaign
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        let contents =
+            ron::ser::to_string_pretty(self, PrettyConfig::default()).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    pub fn current(&self) -> &Stage {
+        &STAGES[self.stage.min(STAGES.len() - 1)]
+    }
+
+    pub fn finished(&self) -> bool {
+        self.stage >= STAGES.len()
+    }
+
+    pub fn advance(&mut self) {
+        self.stage = (self.stage + 1).min(STAGES.len());
+    }
+
+    pub fn reset(&mut self) {
+        self.stage = 0;
+    }
+
+    // A single snake on the current stage's level at its speed.
+    pub fn apply_to(&mut self, rules: &mut Rules) {
+        if self.rules.is_none() {
+            self.rules = Some(rules.clone());
+        }
+        let stage = self.current();
+        rules.board_mode = BoardMode::Walls;
+        rules.players = 1;
+        rules.ai_opponent = false;
+        rules.time_limit = None;
+        rules.endless = false;
+        rules.shrinking = false;
+        rules.coop = false;
+        rules.level = Some(stage.level.to_string());
+        rules.update_speed = stage.speed;
+        rules.max_speed = rules.max_speed.max(stage.speed);
+    }
+
+    pub fn leave(&mut self, rules: &mut Rules) {
+        if let Some(own) = self.rules.take() {
+            *rules = own;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaving_the_campaign_puts_the_players_rules_back() {
+        let mut campaign = Campaign::default();
+        let mut rules = Rules {
+            update_speed: 12,
+            max_speed: 14,
+            board_mode: BoardMode::Wrap,
+            ..Rules::default()
+        };
+        campaign.apply_to(&mut rules);
+        campaign.advance();
+        campaign.apply_to(&mut rules);
+        assert_eq!(rules.update_speed, STAGES[1].speed);
+        assert!(rules.board_mode == BoardMode::Walls);
+
+        campaign.leave(&mut rules);
+        assert_eq!(rules.update_speed, 12);
+        assert_eq!(rules.max_speed, 14);
+        assert!(rules.board_mode == BoardMode::Wrap);
+        assert_eq!(rules.level, None);
+    }
+}
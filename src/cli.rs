@@ -0,0 +1,187 @@
+use crate::config::{self, Config, Renderer};
+use crate::logger;
+use crate::net::DEFAULT_PORT;
+use snake_2d::ai::Difficulty;
+use snake_2d::simulate::Policy;
+use snake_2d::{Board, BoardMode, Spawn, SpeedPreset};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: snake_2d [OPTIONS]
+
+Options:
+  --board SIZE    small (15x15), classic (20x20), large (40x30) or WxH, e.g. 30x20
+  --speed SPEED   slow, normal, fast, insane, or moves per second
+  --wrap          Wrap around the board edges instead of dying
+  --seed N        Seed for food placement
+  --level LEVEL   Built-in level 1-5 (0 for none) or a level file
+  --replay FILE   Play back a recorded replay
+  --players N     Number of local players, 1 or 2
+  --ai LEVEL      Play against an AI opponent: easy, normal, hard or adaptive
+  --food N        Food pellets on the board at once, 1-5
+  --lives N       Lives per player
+  --time-limit N  End the game after N seconds
+  --endless       Grow a new wall every 10 foods
+  --shrinking     Close the outer ring of the board every 20 seconds
+  --coop          Two players on one team with a shared score and lives
+  --food-distance N
+                  Spawn food at least N moves from every head
+  --no-tail-chase Die moving onto the cell the tail is leaving
+  --spawn WHERE   center (default), facing the most open way, or corner
+  --diagonals     Also move diagonally by holding two direction keys
+  --fullscreen    Start in fullscreen
+  --log-level LVL off, error, warn (default), info, debug or trace
+  --renderer NAME opengl (default) or terminal, which needs the tui feature
+  --gl VERSION    Force an OpenGL version, e.g. 2.1, instead of trying 3.2 then 2.1
+  --headless N    Simulate N games without a window and print statistics
+  --policy NAME   Who steers in headless games: ai (default) or random
+  --bot PATH      Race a bot program that reads the board on stdin and
+                  writes moves on stdout; with --headless it plays alone
+  --host          Host a two-player network game and wait for a player
+  --port N        Port to host on, 7878 by default
+  --connect ADDR  Join a network game at HOST or HOST:PORT
+  --stats-report  Print input latency and frame times from the telemetry file
+  --check-assets  List the levels, themes and sound packs in the data folder
+                  and what is wrong with any that can't be used
+  -h, --help      Print this help";
+
+pub enum Command {
+    Play,
+    Replay(PathBuf),
+    Headless(usize, Policy),
+    Host(u16),
+    Connect(String),
+    Bot(String),
+    StatsReport,
+    CheckAssets,
+    Help,
+}
+
+pub fn parse_args<I>(config: &mut Config, args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut command = Command::Play;
+    let mut headless = None;
+    let mut random = false;
+    let mut bot = None;
+    let mut host = false;
+    let mut port = DEFAULT_PORT;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--stats-report" => return Ok(Command::StatsReport),
+            "--check-assets" => return Ok(Command::CheckAssets),
+            "--wrap" => config.rules.board_mode = BoardMode::Wrap,
+            "--fullscreen" => config.fullscreen = true,
+            "--gl" => {
+                let version = value(&arg, args.next())?;
+                if config::gl_version(&version).is_none() {
+                    return Err(format!("Unknown OpenGL version '{}'", version));
+                }
+                config.gl = Some(version);
+            }
+            "--renderer" => {
+                config.renderer = match value(&arg, args.next())?.as_str() {
+                    "opengl" => Renderer::OpenGl,
+                    "terminal" => Renderer::Terminal,
+                    other => return Err(format!("Unknown renderer '{}'", other)),
+                }
+            }
+            "--board" | "--grid" => config.rules.board = parse_board(&value(&arg, args.next())?)?,
+            "--speed" => {
+                let speed = value(&arg, args.next())?;
+                match SpeedPreset::parse(&speed) {
+                    Some(preset) => config.speed = Some(preset),
+                    None => {
+                        config.speed = None;
+                        config.rules.update_speed = parse_number(&arg, Some(speed))?;
+                    }
+                }
+            }
+            "--seed" => config.rules.seed = Some(parse_number(&arg, args.next())?),
+            "--level" => config.rules.level = Some(value(&arg, args.next())?),
+            "--replay" => command = Command::Replay(PathBuf::from(value(&arg, args.next())?)),
+            "--players" => config.rules.players = parse_number(&arg, args.next())?,
+            "--food" => config.rules.food_count = parse_number(&arg, args.next())?,
+            "--lives" => config.rules.lives = parse_number(&arg, args.next())?,
+            "--time-limit" => config.rules.time_limit = Some(parse_number(&arg, args.next())?),
+            "--endless" => config.rules.endless = true,
+            "--shrinking" => config.rules.shrinking = true,
+            "--coop" => config.rules.coop = true,
+            "--no-tail-chase" => config.rules.tail_chase = false,
+            "--diagonals" => config.rules.diagonals = true,
+            "--spawn" => {
+                let name = value(&arg, args.next())?;
+                config.rules.spawn =
+                    Spawn::parse(&name).ok_or_else(|| format!("Unknown spawn '{}'", name))?;
+            }
+            "--food-distance" => config.rules.food_distance = parse_number(&arg, args.next())?,
+            "--log-level" => {
+                let level = value(&arg, args.next())?;
+                config.log_level = logger::parse_level(&level)
+                    .ok_or_else(|| format!("Unknown log level '{}'", level))?;
+            }
+            "--headless" => headless = Some(parse_number(&arg, args.next())?),
+            "--policy" => {
+                random = match value(&arg, args.next())?.as_str() {
+                    "ai" => false,
+                    "random" => true,
+                    other => return Err(format!("Unknown policy '{}'", other)),
+                }
+            }
+            "--bot" => bot = Some(value(&arg, args.next())?),
+            "--host" => host = true,
+            "--port" => port = parse_number(&arg, args.next())?,
+            "--connect" => command = Command::Connect(value(&arg, args.next())?),
+            "--ai" => {
+                config.rules.ai_difficulty = parse_difficulty(&value(&arg, args.next())?)?;
+                config.rules.ai_opponent = true;
+            }
+            _ => return Err(format!("Unknown option '{}'", arg)),
+        }
+    }
+    if let Some(games) = headless {
+        let policy = match bot {
+            Some(path) => Policy::Bot(path),
+            None if random => Policy::Random,
+            None => Policy::Ai(config.rules.ai_difficulty),
+        };
+        command = Command::Headless(games, policy);
+    } else if let Some(path) = bot {
+        command = Command::Bot(path);
+    }
+    if host {
+        command = Command::Host(port);
+    }
+    Ok(command)
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("Missing value for '{}'", flag))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, arg: Option<String>) -> Result<T, String> {
+    let arg = value(flag, arg)?;
+    arg.parse()
+        .map_err(|_| format!("Invalid value '{}' for '{}'", arg, flag))
+}
+
+fn parse_difficulty(arg: &str) -> Result<Difficulty, String> {
+    match arg {
+        "easy" => Ok(Difficulty::Easy),
+        "normal" => Ok(Difficulty::Normal),
+        "hard" => Ok(Difficulty::Hard),
+        "adaptive" => Ok(Difficulty::Adaptive),
+        _ => Err(format!("Unknown AI difficulty '{}'", arg)),
+    }
+}
+
+fn parse_board(arg: &str) -> Result<Board, String> {
+    Board::parse(arg).ok_or_else(|| {
+        format!(
+            "Invalid board '{}', expected small, classic, large or WxH",
+            arg
+        )
+    })
+}
@@ -0,0 +1,93 @@
+use crate::screenshot;
+use image::gif::Encoder;
+use image::imageops::{self, FilterType};
+use image::{Delay, Frame, RgbaImage};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CLIP_FPS: u32 = 10;
+const CLIP_SECONDS: u32 = 10;
+// Frames are scaled down to at most this width to keep the buffer small.
+const CLIP_WIDTH: u32 = 240;
+
+// The last CLIP_SECONDS of frames, downscaled, ready to be written out as
+// an animated GIF. Encoding runs on a background thread.
+#[derive(Default)]
+pub struct Clip {
+    frames: VecDeque<RgbaImage>,
+    last: Option<Instant>,
+    saving: Option<Receiver<Result<PathBuf, String>>>,
+}
+
+impl Clip {
+    // Called after every frame; keeps one in 1 / CLIP_FPS seconds.
+    pub fn record(&mut self, size: [u32; 2]) {
+        let interval = Duration::from_secs(1) / CLIP_FPS;
+        if self.last.is_some_and(|last| last.elapsed() < interval) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        let frame = match screenshot::read_frame(size) {
+            Some(frame) => frame,
+            None => return,
+        };
+        let width = CLIP_WIDTH.min(size[0]);
+        let height = (u64::from(size[1]) * u64::from(width) / u64::from(size[0].max(1))) as u32;
+        self.frames.push_back(imageops::resize(
+            &frame,
+            width,
+            height.max(1),
+            FilterType::Triangle,
+        ));
+        while self.frames.len() > (CLIP_FPS * CLIP_SECONDS) as usize {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn save(&mut self) {
+        if self.saving.is_some() || self.frames.is_empty() {
+            return;
+        }
+        let frames: Vec<RgbaImage> = self.frames.iter().cloned().collect();
+        let (sender, saving) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(encode(frames));
+        });
+        self.saving = Some(saving);
+    }
+
+    pub fn saving(&self) -> bool {
+        self.saving.is_some()
+    }
+
+    // The result of a finished save, once.
+    pub fn poll(&mut self) -> Option<Result<PathBuf, String>> {
+        let result = match self.saving.as_ref().map(Receiver::try_recv) {
+            Some(Ok(result)) => result,
+            Some(Err(TryRecvError::Disconnected)) => Err(String::from("encoder stopped")),
+            Some(Err(TryRecvError::Empty)) | None => return None,
+        };
+        self.saving = None;
+        Some(result)
+    }
+}
+
+fn encode(frames: Vec<RgbaImage>) -> Result<PathBuf, String> {
+    let path = screenshot::output_path("clips", "gif")?;
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    let delay = Delay::from_numer_denom_ms(1000, CLIP_FPS);
+    let mut encoder = Encoder::new(BufWriter::new(file));
+    encoder
+        .encode_frames(
+            frames
+                .into_iter()
+                .map(|frame| Frame::from_parts(frame, 0, 0, delay)),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(path)
+}
@@ -0,0 +1,452 @@
+use crate::logger;
+use crate::render::{BoardStyle, Zoom};
+use crate::theme::{Theme, THEMES};
+use log::{info, warn, LevelFilter};
+use opengl_graphics::OpenGL;
+use piston::input::Key;
+use serde::{Deserialize, Serialize};
+use snake_2d::accessibility::AccessibilityOptions;
+use snake_2d::i18n::{self, Locale};
+use snake_2d::{Direction, Rules, SpeedPreset};
+use std::fs;
+use std::io::ErrorKind;
+use std::time::SystemTime;
+use toml_edit::{value, DocumentMut};
+
+pub const CONFIG_FILE: &str = "snake.toml";
+// How often a running game checks whether the config file was saved.
+const RELOAD_CHECK_SECONDS: f64 = 1.0;
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(flatten)]
+    pub rules: Rules,
+    // Overrides `update_speed` when set.
+    pub speed: Option<SpeedPreset>,
+    pub cell_size: i32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    // Frames drawn per second, 0 for as many as possible.
+    pub max_fps: u64,
+    // Logic updates per second. The snake's speed doesn't depend on it, but
+    // a higher rate moves it closer to the exact time of each step.
+    pub tick_rate: u64,
+    pub renderer: Renderer,
+    pub gl: Option<String>,
+    pub muted: bool,
+    // Sound and music loudness from 0 to 1.
+    pub volume: f32,
+    // Race a translucent replay of your best run in the same mode.
+    pub ghost: bool,
+    pub leaderboard_url: Option<String>,
+    pub leaderboard_name: String,
+    // The Discord application whose rich presence shows the game being played.
+    pub discord_client_id: Option<String>,
+    // Keep input latency and frame times in `telemetry.ron`.
+    pub telemetry: bool,
+    // Record a checksum of the game every tick in replays and compare it in
+    // network games, to catch the two runs of a game drifting apart.
+    pub checksums: bool,
+    pub theme: String,
+    // A folder of sounds under `sounds` in the data directory, see
+    // `UserAssets`; the built-in sounds when unset.
+    pub sound_pack: Option<String>,
+    // A language tag such as "de" or "es-MX"; the system locale when unset.
+    pub language: Option<String>,
+    pub board_style: BoardStyle,
+    pub zoom: Zoom,
+    // Marks food and obstacles with shapes as well as colors.
+    pub patterns: bool,
+    // Flashes the edge of the board a snake is about to run into.
+    pub edge_warning: bool,
+    pub accessibility: AccessibilityOptions,
+    // off, error, warn, info, debug or trace; --log-level overrides it.
+    pub log_level: LevelFilter,
+    pub colors: Colors,
+    pub keys: KeyMap,
+    pub keys_player_two: DirectionKeys,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Renderer {
+    OpenGl,
+    Terminal,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Colors {
+    pub background: Option<[f32; 4]>,
+    pub snake: Option<[f32; 4]>,
+    pub snake_player_two: Option<[f32; 4]>,
+    pub head: Option<[f32; 4]>,
+    pub head_player_two: Option<[f32; 4]>,
+    pub food: Option<[f32; 4]>,
+    pub obstacle: Option<[f32; 4]>,
+    pub grid_line: Option<[f32; 4]>,
+    pub text: Option<[f32; 4]>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    TurnUp,
+    TurnDown,
+    TurnLeft,
+    TurnRight,
+    Pause,
+    Restart,
+    Mute,
+}
+
+pub const ACTIONS: [Action; 7] = [
+    Action::TurnUp,
+    Action::TurnDown,
+    Action::TurnLeft,
+    Action::TurnRight,
+    Action::Pause,
+    Action::Restart,
+    Action::Mute,
+];
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct KeyMap {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key,
+    pub pause: Key,
+    pub restart: Key,
+    pub mute: Key,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct DirectionKeys {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key,
+}
+
+impl Action {
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::TurnUp => "action.up",
+            Action::TurnDown => "action.down",
+            Action::TurnLeft => "action.left",
+            Action::TurnRight => "action.right",
+            Action::Pause => "action.pause",
+            Action::Restart => "action.restart",
+            Action::Mute => "action.mute",
+        }
+    }
+
+    // Its name under [keys] in the config file.
+    pub fn field(self) -> &'static str {
+        match self {
+            Action::TurnUp => "up",
+            Action::TurnDown => "down",
+            Action::TurnLeft => "left",
+            Action::TurnRight => "right",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Mute => "mute",
+        }
+    }
+
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Action::TurnUp => Some(Direction::Up),
+            Action::TurnDown => Some(Direction::Down),
+            Action::TurnLeft => Some(Direction::Left),
+            Action::TurnRight => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+impl KeyMap {
+    pub fn action(&self, key: Key) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .copied()
+            .find(|&action| self.key(action) == key)
+    }
+
+    pub fn key(&self, action: Action) -> Key {
+        match action {
+            Action::TurnUp => self.up,
+            Action::TurnDown => self.down,
+            Action::TurnLeft => self.left,
+            Action::TurnRight => self.right,
+            Action::Pause => self.pause,
+            Action::Restart => self.restart,
+            Action::Mute => self.mute,
+        }
+    }
+
+    pub fn bind(&mut self, action: Action, key: Key) {
+        let binding = match action {
+            Action::TurnUp => &mut self.up,
+            Action::TurnDown => &mut self.down,
+            Action::TurnLeft => &mut self.left,
+            Action::TurnRight => &mut self.right,
+            Action::Pause => &mut self.pause,
+            Action::Restart => &mut self.restart,
+            Action::Mute => &mut self.mute,
+        };
+        *binding = key;
+    }
+}
+
+impl DirectionKeys {
+    pub fn direction(&self, key: Key) -> Option<Direction> {
+        match key {
+            k if k == self.up => Some(Direction::Up),
+            k if k == self.down => Some(Direction::Down),
+            k if k == self.left => Some(Direction::Left),
+            k if k == self.right => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+pub fn gl_version(name: &str) -> Option<OpenGL> {
+    match name {
+        "2.0" => Some(OpenGL::V2_0),
+        "2.1" => Some(OpenGL::V2_1),
+        "3.0" => Some(OpenGL::V3_0),
+        "3.1" => Some(OpenGL::V3_1),
+        "3.2" => Some(OpenGL::V3_2),
+        "3.3" => Some(OpenGL::V3_3),
+        "4.0" => Some(OpenGL::V4_0),
+        "4.1" => Some(OpenGL::V4_1),
+        "4.2" => Some(OpenGL::V4_2),
+        "4.3" => Some(OpenGL::V4_3),
+        "4.4" => Some(OpenGL::V4_4),
+        "4.5" => Some(OpenGL::V4_5),
+        _ => None,
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> Config {
+        match Config::read(path) {
+            Ok(config) => config.unwrap_or_default(),
+            Err(e) => {
+                warn!("{}, using defaults", e);
+                Config::default()
+            }
+        }
+    }
+
+    // None when there is no config file.
+    fn read(path: &str) -> Result<Option<Config>, String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Could not read {}: {}", path, e)),
+        };
+
+        let config =
+            toml::from_str::<Config>(&contents).map_err(|e| format!("Invalid {}: {}", path, e))?;
+        logger::set_level(config.log_level);
+        info!("Loaded {}", path);
+        Ok(Some(config.validated()))
+    }
+
+    pub fn validated(mut self) -> Config {
+        if let Some(preset) = self.speed {
+            self.rules.set_speed_preset(preset);
+        }
+        self.rules = self.rules.validated();
+        self.cell_size = self.cell_size.max(4);
+        self.tick_rate = self.tick_rate.max(1);
+        self.volume = self.volume.clamp(0.0, 1.0);
+        self
+    }
+
+    // Writes what the settings screen changes into the config file, keeping
+    // the rest of the file and its comments as they are.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Could not read {}: {}", path, e)),
+        };
+        let mut doc = contents
+            .parse::<DocumentMut>()
+            .map_err(|e| format!("Invalid {}: {}", path, e))?;
+
+        if let Some(preset) = self.speed {
+            doc["speed"] = value(name(preset));
+        }
+        doc["ai_difficulty"] = value(name(self.rules.ai_difficulty));
+        doc["food_count"] = value(self.rules.food_count as i64);
+        doc["board_mode"] = value(name(self.rules.board_mode));
+        doc["max_fps"] = value(self.max_fps as i64);
+        doc["tick_rate"] = value(self.tick_rate as i64);
+        doc["volume"] = value(f64::from(self.volume));
+        doc["theme"] = value(self.theme.as_str());
+        match self.sound_pack {
+            Some(ref pack) => doc["sound_pack"] = value(pack.as_str()),
+            None => {
+                doc.remove("sound_pack");
+            }
+        }
+        if let Some(ref level) = self.rules.level {
+            doc["level"] = value(level.as_str());
+        }
+        doc["board_style"] = value(name(self.board_style));
+        doc["zoom"] = value(name(self.zoom));
+        doc["patterns"] = value(self.patterns);
+        doc["edge_warning"] = value(self.edge_warning);
+        let options = &self.accessibility;
+        doc["accessibility"]["screen_shake"] = value(options.screen_shake);
+        doc["accessibility"]["flashes"] = value(options.flashes);
+        doc["accessibility"]["particles"] = value(options.particles);
+        doc["accessibility"]["reduced_motion"] = value(options.reduced_motion);
+        for &action in ACTIONS.iter() {
+            let key = format!("{:?}", self.keys.key(action));
+            doc["keys"][action.field()] = value(key);
+        }
+
+        fs::write(path, doc.to_string()).map_err(|e| format!("Could not write {}: {}", path, e))
+    }
+
+    pub fn theme(&self, themes: &[Theme]) -> Theme {
+        let theme = Theme::named(&self.theme, themes).unwrap_or_else(|| {
+            warn!("Unknown theme '{}', using classic", self.theme);
+            THEMES[0]
+        });
+        theme.with_overrides(&self.colors)
+    }
+
+    pub fn locale(&self) -> Locale {
+        let mut preferred: Vec<String> = self.language.iter().cloned().collect();
+        preferred.extend(i18n::system_languages());
+        Locale::new(&preferred)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            rules: Rules::default(),
+            speed: None,
+            cell_size: 25,
+            fullscreen: false,
+            vsync: false,
+            max_fps: 60,
+            tick_rate: 60,
+            renderer: Renderer::OpenGl,
+            gl: None,
+            muted: false,
+            volume: 1.0,
+            ghost: true,
+            leaderboard_url: None,
+            leaderboard_name: String::from("Anonymous"),
+            discord_client_id: None,
+            telemetry: false,
+            checksums: false,
+            theme: String::from("classic"),
+            sound_pack: None,
+            language: None,
+            board_style: BoardStyle::Plain,
+            zoom: Zoom::Normal,
+            patterns: false,
+            edge_warning: true,
+            accessibility: AccessibilityOptions::default(),
+            log_level: logger::DEFAULT_LEVEL,
+            colors: Colors::default(),
+            keys: KeyMap::default(),
+            keys_player_two: DirectionKeys::default(),
+        }
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap {
+            up: Key::Up,
+            down: Key::Down,
+            left: Key::Left,
+            right: Key::Right,
+            pause: Key::P,
+            restart: Key::Space,
+            mute: Key::M,
+        }
+    }
+}
+
+impl Default for DirectionKeys {
+    fn default() -> DirectionKeys {
+        DirectionKeys {
+            up: Key::W,
+            down: Key::S,
+            left: Key::A,
+            right: Key::D,
+        }
+    }
+}
+
+// Notices when the config file is saved while the game runs by polling its
+// modification time, so it works the same on every platform.
+pub struct ConfigWatcher {
+    path: String,
+    modified: Option<SystemTime>,
+    since_check: f64,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &str) -> ConfigWatcher {
+        ConfigWatcher {
+            path: path.to_string(),
+            modified: modified(path),
+            since_check: 0.0,
+        }
+    }
+
+    // The new config once the file has changed. A file that doesn't parse
+    // is reported and skipped until it is saved again.
+    pub fn poll(&mut self, dt: f64) -> Option<Config> {
+        self.since_check += dt;
+        if self.since_check < RELOAD_CHECK_SECONDS {
+            return None;
+        }
+        self.since_check = 0.0;
+
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        match Config::read(&self.path) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("{}, keeping the current settings", e);
+                None
+            }
+        }
+    }
+
+    // Called after the game wrote the file itself, so it isn't reloaded.
+    pub fn saved(&mut self) {
+        self.modified = modified(&self.path);
+    }
+}
+
+// A setting's spelling in the config file, e.g. "checkerboard".
+fn name<T: Serialize>(setting: T) -> String {
+    match toml::Value::try_from(setting) {
+        Ok(toml::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
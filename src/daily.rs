@@ -0,0 +1,82 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use snake_2d::rules::{MAX_FOOD, SPEED_PRESETS};
+use snake_2d::{BoardMode, Locale, Rules};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+// Built-in levels the daily board can use besides an empty one.
+const LEVELS: usize = 5;
+
+// Today's challenge. The UTC date picks the seed and the modifiers, so
+// everyone playing on the same day gets the same board.
+pub struct Daily {
+    // As YYYY-MM-DD, also the name of its high score table.
+    pub date: String,
+    day: u64,
+    // The player's own rules, put back when leaving the challenge.
+    pub rules: Rules,
+}
+
+impl Daily {
+    pub fn today(rules: Rules) -> Daily {
+        let day = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() / SECONDS_PER_DAY)
+            .unwrap_or(0);
+        Daily {
+            date: date(day),
+            day,
+            rules,
+        }
+    }
+
+    // Default rules apart from the seed, speed, walls, food count and edges,
+    // which are drawn from the date. Only the AI difficulty is kept, as no
+    // AI plays.
+    pub fn apply_to(&self, rules: &mut Rules) {
+        let mut rng = Pcg32::seed_from_u64(self.day);
+        *rules = Rules {
+            seed: Some(rng.gen()),
+            ai_difficulty: rules.ai_difficulty,
+            ..Rules::default()
+        };
+        rules.set_speed_preset(SPEED_PRESETS[rng.gen_range(0, SPEED_PRESETS.len())]);
+        rules.level = match rng.gen_range(0, LEVELS + 1) {
+            0 => None,
+            level => Some(level.to_string()),
+        };
+        rules.food_count = rng.gen_range(1, MAX_FOOD + 1);
+        if rng.gen_range(0, 4) == 0 {
+            rules.board_mode = BoardMode::Wrap;
+        }
+    }
+}
+
+// The modifiers `Daily::apply_to` picked, e.g. "Fast, level 3, 2 food".
+pub fn describe(rules: &Rules, locale: &Locale) -> String {
+    let mut parts = vec![locale.get(rules.speed_preset().key()).to_string()];
+    parts.push(match rules.level {
+        Some(ref level) => locale.format("daily.level", &[("level", level)]),
+        None => locale.format("daily.no_walls", &[]),
+    });
+    parts.push(locale.format("daily.food", &[("count", &rules.food_count)]));
+    if rules.board_mode == BoardMode::Wrap {
+        parts.push(locale.format("daily.wrap", &[]));
+    }
+    parts.join(", ")
+}
+
+// The civil date `days` after 1970-01-01.
+fn date(days: u64) -> String {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
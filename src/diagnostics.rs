@@ -0,0 +1,94 @@
+use snake_2d::{Game, Locale};
+use std::time::{Duration, Instant};
+
+const SAMPLE_SECONDS: f64 = 1.0;
+
+// Frame, update and tick timings for the F3 overlay. Rates are counted over
+// the last second of wall-clock time rather than from event timestamps.
+pub struct Diagnostics {
+    pub visible: bool,
+    started: Instant,
+    frames: u32,
+    updates: u32,
+    fps: f64,
+    ups: f64,
+    tick: Duration,
+    slowest_tick: Duration,
+}
+
+impl Diagnostics {
+    pub fn init() -> Diagnostics {
+        Diagnostics {
+            visible: false,
+            started: Instant::now(),
+            frames: 0,
+            updates: 0,
+            fps: 0.0,
+            ups: 0.0,
+            tick: Duration::default(),
+            slowest_tick: Duration::default(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn frame(&mut self) {
+        self.frames += 1;
+        self.sample();
+    }
+
+    pub fn update(&mut self) {
+        self.updates += 1;
+        self.sample();
+    }
+
+    pub fn tick(&mut self, duration: Duration) {
+        self.tick = duration;
+        self.slowest_tick = self.slowest_tick.max(duration);
+    }
+
+    fn sample(&mut self) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed < SAMPLE_SECONDS {
+            return;
+        }
+        self.fps = f64::from(self.frames) / elapsed;
+        self.ups = f64::from(self.updates) / elapsed;
+        self.frames = 0;
+        self.updates = 0;
+        self.slowest_tick = self.tick;
+        self.started = Instant::now();
+    }
+
+    pub fn lines(&self, game: &Game, particles: usize, locale: &Locale) -> Vec<String> {
+        let segments: usize = game.players.iter().map(|p| p.snake.len()).sum();
+        vec![
+            locale.format("diagnostics.fps", &[("fps", &format!("{:.0}", self.fps))]),
+            locale.format("diagnostics.ups", &[("ups", &format!("{:.0}", self.ups))]),
+            locale.format(
+                "diagnostics.tick",
+                &[
+                    ("time", &format!("{:.2}", millis(self.tick))),
+                    ("max", &format!("{:.2}", millis(self.slowest_tick))),
+                ],
+            ),
+            locale.format(
+                "diagnostics.snakes",
+                &[("count", &game.players.len()), ("segments", &segments)],
+            ),
+            locale.format("diagnostics.food", &[("count", &game.foods.len())]),
+            locale.format(
+                "diagnostics.obstacles",
+                &[("count", &game.level.obstacles.len())],
+            ),
+            locale.format("diagnostics.particles", &[("count", &particles)]),
+            locale.format("seed", &[("seed", &game.seed)]),
+        ]
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,193 @@
+#[cfg(feature = "discord")]
+use log::{debug, info};
+#[cfg(feature = "discord")]
+use serde_json::{json, Value};
+#[cfg(feature = "discord")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "discord")]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(feature = "discord")]
+use std::thread;
+#[cfg(feature = "discord")]
+use std::time::Duration;
+
+// Discord allows a handful of updates every 20 seconds; newer ones sent in
+// the meantime replace older ones.
+#[cfg(feature = "discord")]
+const UPDATE_INTERVAL: Duration = Duration::from_secs(4);
+#[cfg(feature = "discord")]
+const OP_HANDSHAKE: u32 = 0;
+#[cfg(feature = "discord")]
+const OP_FRAME: u32 = 1;
+
+// What the player's Discord profile shows while a game runs.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(not(feature = "discord"), allow(dead_code))]
+pub struct Activity {
+    // The mode being played.
+    pub details: String,
+    // The score.
+    pub state: String,
+    // When the game started, in seconds since the Unix epoch, so Discord
+    // can count the elapsed time itself.
+    pub start: u64,
+}
+
+// Rich presence through the Discord client running on the same machine.
+// The socket is talked to on a background thread that connects again
+// whenever there's something new to show, so the game runs the same whether
+// Discord is open or not; without the `discord` feature or an application ID
+// every call is a no-op.
+pub struct Discord {
+    #[cfg(feature = "discord")]
+    updates: Option<Sender<Option<Activity>>>,
+    #[cfg(feature = "discord")]
+    last: Option<Activity>,
+}
+
+impl Discord {
+    pub fn init(client_id: Option<String>) -> Discord {
+        #[cfg(not(feature = "discord"))]
+        let _ = client_id;
+        Discord {
+            #[cfg(feature = "discord")]
+            updates: client_id.map(|id| {
+                let (sender, updates) = mpsc::channel();
+                thread::spawn(move || publish(&id, &updates));
+                sender
+            }),
+            #[cfg(feature = "discord")]
+            last: None,
+        }
+    }
+
+    // Shows the activity, or clears it with None.
+    pub fn set(&mut self, activity: Option<Activity>) {
+        #[cfg(feature = "discord")]
+        {
+            if activity == self.last {
+                return;
+            }
+            self.last = activity.clone();
+            if let Some(ref updates) = self.updates {
+                let _ = updates.send(activity);
+            }
+        }
+        #[cfg(not(feature = "discord"))]
+        let _ = activity;
+    }
+}
+
+#[cfg(feature = "discord")]
+trait Pipe: Read + Write + Send {}
+
+#[cfg(feature = "discord")]
+impl<T: Read + Write + Send> Pipe for T {}
+
+// Runs until the game drops its sender.
+#[cfg(feature = "discord")]
+fn publish(client_id: &str, updates: &Receiver<Option<Activity>>) {
+    let mut pipe: Option<Box<dyn Pipe>> = None;
+    let mut nonce = 0;
+    while let Ok(mut activity) = updates.recv() {
+        while let Ok(newer) = updates.try_recv() {
+            activity = newer;
+        }
+        if pipe.is_none() {
+            pipe = connect(client_id);
+        }
+        if let Some(ref mut connection) = pipe {
+            nonce += 1;
+            if let Err(e) = set_activity(connection, activity.as_ref(), nonce) {
+                debug!("Lost the connection to Discord: {}", e);
+                pipe = None;
+            }
+        }
+        thread::sleep(UPDATE_INTERVAL);
+    }
+}
+
+#[cfg(feature = "discord")]
+fn connect(client_id: &str) -> Option<Box<dyn Pipe>> {
+    let mut pipe = (0..10).find_map(open)?;
+    let handshake = json!({ "v": 1, "client_id": client_id });
+    match send(&mut pipe, OP_HANDSHAKE, &handshake).and_then(|_| receive(&mut pipe)) {
+        Ok(_) => {
+            info!("Connected to Discord");
+            Some(pipe)
+        }
+        Err(e) => {
+            debug!("Discord refused the handshake: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(all(feature = "discord", unix))]
+fn open(index: u32) -> Option<Box<dyn Pipe>> {
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(std::env::var_os)
+        .unwrap_or_else(|| "/tmp".into());
+    let path = std::path::Path::new(&dir).join(format!("discord-ipc-{}", index));
+    let stream = std::os::unix::net::UnixStream::connect(path).ok()?;
+    stream.set_read_timeout(Some(UPDATE_INTERVAL)).ok()?;
+    Some(Box::new(stream))
+}
+
+#[cfg(all(feature = "discord", windows))]
+fn open(index: u32) -> Option<Box<dyn Pipe>> {
+    let path = format!(r"\\?\pipe\discord-ipc-{}", index);
+    let pipe = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .ok()?;
+    Some(Box::new(pipe))
+}
+
+#[cfg(all(feature = "discord", not(any(unix, windows))))]
+fn open(_index: u32) -> Option<Box<dyn Pipe>> {
+    None
+}
+
+#[cfg(feature = "discord")]
+fn set_activity(pipe: &mut dyn Pipe, activity: Option<&Activity>, nonce: u64) -> io::Result<()> {
+    let activity = activity.map(|activity| {
+        json!({
+            "details": activity.details,
+            "state": activity.state,
+            "timestamps": { "start": activity.start },
+        })
+    });
+    let command = json!({
+        "cmd": "SET_ACTIVITY",
+        "args": { "pid": std::process::id(), "activity": activity },
+        "nonce": nonce.to_string(),
+    });
+    send(pipe, OP_FRAME, &command)?;
+    receive(pipe).map(|_| ())
+}
+
+// A frame is the opcode and the payload's length, both little-endian u32s,
+// then the JSON payload.
+#[cfg(feature = "discord")]
+fn send(pipe: &mut dyn Pipe, op: u32, payload: &Value) -> io::Result<()> {
+    let payload = payload.to_string();
+    let mut frame = Vec::with_capacity(8 + payload.len());
+    frame.extend_from_slice(&op.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload.as_bytes());
+    pipe.write_all(&frame)?;
+    pipe.flush()
+}
+
+#[cfg(feature = "discord")]
+fn receive(pipe: &mut dyn Pipe) -> io::Result<Value> {
+    let mut header = [0; 8];
+    pipe.read_exact(&mut header)?;
+    let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let mut payload = vec![0; length as usize];
+    pipe.read_exact(&mut payload)?;
+    serde_json::from_slice(&payload).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::fmt;
+
+// A game state the simulation can't continue from. `Game::step` reports it
+// as `TickResult::Failed` so the front end can show it and restart instead
+// of the whole process panicking.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SnakeError {
+    // A snake lost every segment, e.g. from a damaged save.
+    EmptySnake,
+    // No free cell was left to put food on.
+    BoardFull,
+}
+
+impl SnakeError {
+    // The message looked up in a `Locale`, for showing to the player.
+    pub fn key(self) -> &'static str {
+        match self {
+            SnakeError::EmptySnake => "error.empty_snake",
+            SnakeError::BoardFull => "error.board_full",
+        }
+    }
+}
+
+impl fmt::Display for SnakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnakeError::EmptySnake => write!(f, "a snake has no segments"),
+            SnakeError::BoardFull => write!(f, "there is no free cell left on the board"),
+        }
+    }
+}
+
+impl Error for SnakeError {}
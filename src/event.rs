@@ -0,0 +1,52 @@
+use crate::food::FoodKind;
+use crate::game::DeathCause;
+use crate::pos::Pos;
+use crate::snake::Direction;
+
+// Something that happened during a tick. `Game::step` collects them in
+// `Game::events` so sound, effects, achievements and stats can react without
+// being wired into the simulation.
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    FoodEaten {
+        player: usize,
+        part: Pos,
+        kind: FoodKind,
+        points: u32,
+    },
+    // Poison was eaten; `penalty` points were taken off the score.
+    Poisoned {
+        player: usize,
+        part: Pos,
+        penalty: u32,
+    },
+    // A food with a lasting or special effect: slow-mo, ghost or shrink.
+    PowerUpActivated {
+        player: usize,
+        kind: FoodKind,
+    },
+    // Sent for every crash; `lives_left` is 0 once the snake is out.
+    SnakeDied {
+        player: usize,
+        part: Pos,
+        cause: DeathCause,
+        lives_left: u32,
+    },
+    // The snakes filled the whole board.
+    LevelCompleted,
+    // Another ring closed around a shrinking arena; `closed` in all.
+    ArenaShrank {
+        closed: i32,
+    },
+    // Also sent on a snake's first tick and after it respawns facing a new
+    // way, so the direction can always be followed from events alone.
+    DirectionChanged {
+        player: usize,
+        dir: Direction,
+    },
+    // The game no longer matches the replay it plays back: its checksum for
+    // `tick` differs from the recorded one. Sent once per game.
+    Diverged {
+        tick: usize,
+    },
+}
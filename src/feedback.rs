@@ -0,0 +1,68 @@
+use rand::Rng;
+use snake_2d::accessibility::AccessibilityOptions;
+
+const SHAKE_SECONDS: f64 = 0.4;
+const SHAKE_CELLS: f64 = 0.4;
+const FLASH_SECONDS: f64 = 0.15;
+const FLASH_COLOR: [f32; 4] = [1.0, 0.85, 0.85, 1.0];
+
+// Screen shake and background flash after a crash, timed per frame rather
+// than per game tick.
+#[derive(Default)]
+pub struct Feedback {
+    shake: f64,
+    flash: f64,
+}
+
+impl Feedback {
+    pub fn crash(&mut self, options: &AccessibilityOptions) {
+        if options.screen_shake {
+            self.shake = SHAKE_SECONDS;
+        }
+        if options.flashes {
+            self.flash = FLASH_SECONDS;
+        }
+    }
+
+    // A shake without the flash, for the arena closing in.
+    pub fn rumble(&mut self, options: &AccessibilityOptions) {
+        if options.screen_shake {
+            self.shake = SHAKE_SECONDS;
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.shake = (self.shake - dt).max(0.0);
+        self.flash = (self.flash - dt).max(0.0);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Feedback::default();
+    }
+
+    pub fn active(&self) -> bool {
+        self.shake > 0.0
+    }
+
+    // A random board offset in cells that decays as the shake wears off.
+    pub fn offset(&self) -> [f64; 2] {
+        if self.shake <= 0.0 {
+            return [0.0, 0.0];
+        }
+        let amplitude = SHAKE_CELLS * self.shake / SHAKE_SECONDS;
+        let mut rng = rand::thread_rng();
+        [
+            rng.gen_range(-amplitude, amplitude),
+            rng.gen_range(-amplitude, amplitude),
+        ]
+    }
+
+    pub fn background(&self, color: [f32; 4]) -> [f32; 4] {
+        let t = (self.flash / FLASH_SECONDS) as f32;
+        let mut flashed = color;
+        for (channel, &flash) in flashed.iter_mut().zip(FLASH_COLOR.iter()) {
+            *channel += (flash - *channel) * t;
+        }
+        flashed
+    }
+}
@@ -0,0 +1,129 @@
+use crate::pos::Pos;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const SPAWN_WEIGHTS: [(FoodKind, u32); 5] = [
+    (FoodKind::Normal, 80),
+    (FoodKind::Golden, 8),
+    (FoodKind::Shrink, 5),
+    (FoodKind::SlowMo, 4),
+    (FoodKind::Ghost, 3),
+];
+const BEHAVIOR_WEIGHTS: [(Behavior, u32); 3] = [
+    (Behavior::Still, 85),
+    (Behavior::Timed, 10),
+    (Behavior::Fleeing, 5),
+];
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum FoodKind {
+    Normal,
+    Golden,
+    Shrink,
+    SlowMo,
+    Ghost,
+    // Costs segments and points instead of giving them. Spawned next to the
+    // other kinds rather than in their place, see `Game::fill_food`.
+    Poison,
+}
+
+impl FoodKind {
+    pub fn random<R: Rng>(rng: &mut R) -> FoodKind {
+        let total: u32 = SPAWN_WEIGHTS.iter().map(|&(_, weight)| weight).sum();
+        let mut roll = rng.gen_range(0, total);
+        for &(kind, weight) in SPAWN_WEIGHTS.iter() {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        FoodKind::Normal
+    }
+
+    pub fn color(self, normal: [f32; 4]) -> [f32; 4] {
+        match self {
+            FoodKind::Normal => normal,
+            FoodKind::Golden => [1.0, 0.84, 0.0, 1.0],
+            FoodKind::Shrink => [0.6, 0.2, 0.8, 1.0],
+            FoodKind::SlowMo => [0.3, 0.7, 1.0, 1.0],
+            FoodKind::Ghost => [0.9, 0.9, 0.9, 0.6],
+            FoodKind::Poison => [0.55, 1.0, 0.0, 1.0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum Behavior {
+    #[default]
+    Still,
+    // Teleports to another free cell when its timer runs out.
+    Timed,
+    // Steps away from the nearest snake head whenever its timer runs out.
+    Fleeing,
+}
+
+impl Behavior {
+    pub fn random<R: Rng>(rng: &mut R) -> Behavior {
+        let total: u32 = BEHAVIOR_WEIGHTS.iter().map(|&(_, weight)| weight).sum();
+        let mut roll = rng.gen_range(0, total);
+        for &(behavior, weight) in BEHAVIOR_WEIGHTS.iter() {
+            if roll < weight {
+                return behavior;
+            }
+            roll -= weight;
+        }
+        Behavior::Still
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Food {
+    pub part: Pos,
+    pub kind: FoodKind,
+    #[serde(default)]
+    pub behavior: Behavior,
+    #[serde(default)]
+    pub timer: u32,
+    #[serde(default)]
+    pub lifetime: u32,
+}
+
+impl Food {
+    // Fraction of a timed pellet's lifetime that is left, 1.0 for other pellets.
+    pub fn remaining(&self) -> f64 {
+        match self.behavior {
+            Behavior::Timed if self.lifetime > 0 => {
+                f64::from(self.timer) / f64::from(self.lifetime)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Effects {
+    pub slow_mo: u32,
+    pub ghost: u32,
+    #[serde(default)]
+    pub invulnerable: u32,
+}
+
+impl Effects {
+    pub fn tick(&mut self) {
+        self.slow_mo = self.slow_mo.saturating_sub(1);
+        self.ghost = self.ghost.saturating_sub(1);
+        self.invulnerable = self.invulnerable.saturating_sub(1);
+    }
+
+    pub fn slow_mo_active(&self) -> bool {
+        self.slow_mo > 0
+    }
+
+    pub fn ghost_active(&self) -> bool {
+        self.ghost > 0
+    }
+
+    pub fn invulnerable_active(&self) -> bool {
+        self.invulnerable > 0
+    }
+}
@@ -0,0 +1,1902 @@
+use crate::accessibility::AccessibilityOptions;
+use crate::ai;
+use crate::error::SnakeError;
+use crate::event::GameEvent;
+use crate::food::{Behavior, Food, FoodKind};
+use crate::level::{Level, Obstacle};
+use crate::player::Player;
+use crate::pos::Pos;
+use crate::replay::Replay;
+use crate::rules::{BoardMode, Contact, Rules, Spawn};
+use crate::snake::{Direction, Snake};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const EFFECT_DURATION: f64 = 5.0;
+const SHRINK_SEGMENTS: usize = 2;
+const POISON_SEGMENTS: usize = 3;
+// One in POISON_CHANCE new pellets brings a poison pellet along.
+const POISON_CHANCE: u32 = 6;
+const MAX_POISON: usize = 2;
+const GOLDEN_VALUE: u32 = 3;
+const TIMED_FOOD_DURATION: f64 = 10.0;
+const FLEE_EVERY: u32 = 3;
+const INVULNERABLE_DURATION: f64 = 2.0;
+pub const ENDLESS_WALL_EVERY: u32 = 10;
+const WALL_LENGTH: i32 = 3;
+const WALL_ATTEMPTS: u32 = 50;
+// Manhattan distance a new wall keeps from every snake head.
+const WALL_CLEARANCE: i32 = 3;
+pub const SHRINK_EVERY: f64 = 20.0;
+// A shrinking arena stops closing in at this many cells across.
+const MIN_ARENA: i32 = 6;
+pub const COOP_LENGTH: usize = 40;
+// A head this close to the edge it is heading for gets a warning.
+pub const EDGE_WARNING_CELLS: i32 = 2;
+// Manhattan distance between the heads of two snakes starting a game,
+// wherever the board is big enough.
+pub const MIN_SPAWN_DISTANCE: i32 = 6;
+// How many foods' worth of points an adaptive AI may trail or lead the best
+// human by before it plays stronger or weaker.
+pub const RUBBER_BAND_FOODS: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TickResult {
+    Moved,
+    Ate(FoodKind),
+    Died(DeathCause),
+    LostLife(DeathCause),
+    Won,
+    TimeUp,
+    // The game can't go on; see `SnakeError`.
+    Failed(SnakeError),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum DeathCause {
+    Wall,
+    SelfCollision,
+    Obstacle,
+    OtherSnake,
+    Poison,
+    Hazard,
+}
+
+impl DeathCause {
+    pub fn message(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "Hit a wall",
+            DeathCause::SelfCollision => "Ran into itself",
+            DeathCause::Obstacle => "Hit an obstacle",
+            DeathCause::Hazard => "Hit a hazard",
+            DeathCause::OtherSnake => "Ran into the other snake",
+            DeathCause::Poison => "Ate poison",
+        }
+    }
+
+    // The same message looked up in a `Locale`.
+    pub fn key(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "death.wall",
+            DeathCause::SelfCollision => "death.self",
+            DeathCause::Obstacle => "death.obstacle",
+            DeathCause::Hazard => "death.hazard",
+            DeathCause::OtherSnake => "death.other_snake",
+            DeathCause::Poison => "death.poison",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub rules: Rules,
+    pub level: Level,
+    pub players: Vec<Player>,
+    pub foods: Vec<Food>,
+    pub recording: Replay,
+    pub playback: Option<Replay>,
+    pub tick: usize,
+    pub seed: u64,
+    pub foods_eaten: u32,
+    #[serde(default)]
+    pub won: bool,
+    // Won by leaving no room for more food, rather than co-op's length.
+    #[serde(default)]
+    pub filled: bool,
+    #[serde(default)]
+    pub elapsed: f64,
+    // Everything that happened during the last step.
+    #[serde(skip)]
+    pub events: Vec<GameEvent>,
+    // Obstacles at the end of `level.obstacles` grown in endless mode,
+    // dropped again on restart.
+    #[serde(default)]
+    added_walls: usize,
+    #[serde(skip)]
+    pub accessibility: AccessibilityOptions,
+    // Keep a checksum of every tick in the recording, to find where a
+    // replay or a network game stops matching.
+    #[serde(skip)]
+    pub record_checksums: bool,
+    // The first tick that didn't match the replay played back.
+    #[serde(skip)]
+    diverged: Option<usize>,
+    rng: Pcg32,
+}
+
+// The parts of a game that change from tick to tick, enough to put it back
+// where it was. The rules, level layout and seed stay the same during a game
+// so they aren't copied.
+#[derive(Clone)]
+pub struct Snapshot {
+    players: Vec<Player>,
+    foods: Vec<Food>,
+    walls: Vec<Obstacle>,
+    tick: usize,
+    foods_eaten: u32,
+    won: bool,
+    filled: bool,
+    elapsed: f64,
+    recorded: Vec<usize>,
+    rng: Pcg32,
+}
+
+impl Game {
+    pub fn new(rules: Rules, level: Level) -> Game {
+        let seed = rules.seed.unwrap_or_else(rand::random);
+
+        let mut game = Game {
+            players: Vec::new(),
+            foods: Vec::new(),
+            level,
+            recording: Replay::new(seed, &rules),
+            playback: None,
+            tick: 0,
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+            foods_eaten: 0,
+            won: false,
+            filled: false,
+            elapsed: 0.0,
+            events: Vec::new(),
+            added_walls: 0,
+            accessibility: AccessibilityOptions::default(),
+            record_checksums: false,
+            diverged: None,
+            rules,
+        };
+        game.players = game.new_players(1);
+        game.fill_food();
+        game
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents =
+            ron::ser::to_string_pretty(self, PrettyConfig::default()).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        // Written aside and moved over the old save, so a crash halfway
+        // through leaves the old one whole.
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, contents)
+            .and_then(|_| fs::rename(&temp, path))
+            .map_err(|e| format!("Could not write save {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Game, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read save {}: {}", path.display(), e))?;
+        let game: Game = ron::from_str(&contents)
+            .map_err(|e| format!("Invalid save {}: {}", path.display(), e))?;
+        if game.players.iter().any(|p| p.snake.is_empty()) {
+            let e = SnakeError::EmptySnake;
+            return Err(format!("Invalid save {}: {}", path.display(), e));
+        }
+        Ok(game)
+    }
+
+    pub fn speed(&self) -> u64 {
+        let steps = match self.rules.speed_up_every {
+            0 => 0,
+            every => u64::from(self.foods_eaten / every),
+        };
+        (self.rules.update_speed + steps).min(self.rules.max_speed)
+    }
+
+    pub fn tick_interval(&self) -> f64 {
+        let interval = 1.0 / self.speed() as f64;
+        if self.players.iter().any(|p| p.effects.slow_mo_active()) {
+            interval * 2.0
+        } else {
+            interval
+        }
+    }
+
+    // Seconds of play left in a timed game. Summed tick intervals fall a
+    // rounding error short of whole seconds, which counts as no time left.
+    pub fn time_left(&self) -> Option<f64> {
+        self.rules
+            .time_limit
+            .map(|limit| match limit as f64 - self.elapsed {
+                left if left < 1e-6 => 0.0,
+                left => left,
+            })
+    }
+
+    pub fn time_up(&self) -> bool {
+        self.time_left() == Some(0.0)
+    }
+
+    // The outer rings of the board closed so far in a shrinking game.
+    pub fn closed_rings(&self) -> i32 {
+        if !self.rules.shrinking {
+            return 0;
+        }
+        ((self.elapsed / SHRINK_EVERY) as i32).min(self.max_rings())
+    }
+
+    // Seconds until the next ring closes, if another one will.
+    pub fn next_ring_in(&self) -> Option<f64> {
+        let closed = self.closed_rings();
+        if !self.rules.shrinking || closed >= self.max_rings() {
+            return None;
+        }
+        Some(f64::from(closed + 1) * SHRINK_EVERY - self.elapsed)
+    }
+
+    fn max_rings(&self) -> i32 {
+        let board = self.rules.board;
+        ((board.columns.min(board.rows) - MIN_ARENA) / 2).max(0)
+    }
+
+    // The edge of the arena a snake is heading for, if it is at most
+    // EDGE_WARNING_CELLS moves from crashing into it. Wrapping edges only
+    // count once rings have closed.
+    pub fn edge_ahead(&self, index: usize) -> Option<Direction> {
+        let closed = self.closed_rings();
+        if self.rules.board_mode == BoardMode::Wrap && closed == 0 {
+            return None;
+        }
+        let player = self.players.get(index).filter(|p| p.alive())?;
+        let head = player.snake.head().ok()?;
+        let board = self.rules.board;
+        // A diagonal heads for two edges at once; the nearer one counts.
+        let (dx, dy) = player.snake.dir.offset();
+        let edges = [
+            (dx > 0, Direction::Right, board.columns - closed - head.x),
+            (dx < 0, Direction::Left, head.x - closed + 1),
+            (dy > 0, Direction::Down, board.rows - closed - head.y),
+            (dy < 0, Direction::Up, head.y - closed + 1),
+        ];
+        edges
+            .iter()
+            .filter(|&&(ahead, _, moves)| ahead && moves <= EDGE_WARNING_CELLS)
+            .min_by_key(|&&(_, _, moves)| moves)
+            .map(|&(_, side, _)| side)
+    }
+
+    // Where the level's hazards are this tick.
+    pub fn hazards(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.hazards_at(self.tick)
+    }
+
+    fn hazards_at(&self, tick: usize) -> impl Iterator<Item = Pos> + '_ {
+        self.level.patrols.iter().map(move |patrol| {
+            let (x, y) = patrol.at(tick);
+            Pos { x, y }
+        })
+    }
+
+    pub fn in_arena(&self, x: i32, y: i32) -> bool {
+        self.rules.board.inside(x, y, self.closed_rings())
+    }
+
+    pub fn step(&mut self, input: Option<Direction>) -> TickResult {
+        let checksum = if self.record_checksums || self.playback_checksums() {
+            Some(self.checksum())
+        } else {
+            None
+        };
+        // Each tick stands for tick_interval() seconds of real time, so the
+        // clock stays right while the speed changes.
+        let closed = self.closed_rings();
+        self.elapsed += self.tick_interval();
+        self.events.clear();
+        if let Some(checksum) = checksum {
+            self.verify(checksum);
+        }
+        if self.closed_rings() > closed {
+            self.shrink_arena();
+        }
+        if let Some(dir) = input {
+            self.players[0].steer(dir);
+        }
+        for player in &mut self.players {
+            player.next_input();
+        }
+        self.steer_ai();
+        if let Some(ref playback) = self.playback {
+            for (i, player) in self.players.iter_mut().enumerate() {
+                if let Some(dir) = playback.direction(i, self.tick) {
+                    player.snake.dir = dir;
+                }
+            }
+        }
+        for (i, player) in self.players.iter_mut().enumerate() {
+            let dir = player.snake.dir;
+            if self.recording.last_direction(i) != Some(dir) {
+                self.events
+                    .push(GameEvent::DirectionChanged { player: i, dir });
+            }
+            player.effects.tick();
+        }
+        self.recording.observe(&self.events);
+        self.tick += 1;
+        for player in &mut self.players {
+            player.score.expire(self.tick);
+        }
+
+        let mut eaten = None;
+        let mut poisoned = Vec::new();
+        for i in 0..self.players.len() {
+            let snake = &self.players[i].snake;
+            if let Some(food) = self.foods.iter().position(|f| snake.check_eat(&f.part)) {
+                let kind = self.foods[food].kind;
+                eaten = Some(kind);
+                if kind == FoodKind::Poison {
+                    if self.poison(i, food) {
+                        poisoned.push(i);
+                    }
+                } else {
+                    self.eat(i, food);
+                }
+            }
+        }
+        if eaten.is_some() {
+            self.rubber_band();
+        }
+        if self.won {
+            return TickResult::Won;
+        }
+        self.tick_food();
+        let stalled: Vec<bool> = (0..self.players.len()).map(|i| self.stalled(i)).collect();
+        for (player, stalled) in self.players.iter_mut().zip(stalled) {
+            if stalled {
+                continue;
+            }
+            if let Err(e) = player.snake.update_direction(&self.rules, &self.level) {
+                return TickResult::Failed(e);
+            }
+        }
+
+        let deaths: Vec<Option<DeathCause>> = (0..self.players.len())
+            .map(|i| {
+                if poisoned.contains(&i) {
+                    Some(DeathCause::Poison)
+                } else {
+                    self.death_cause(i)
+                }
+            })
+            .collect();
+        let mut died = None;
+        let mut lost_life = None;
+        for (i, death) in deaths.into_iter().enumerate() {
+            let cause = match death {
+                Some(cause) => cause,
+                None => continue,
+            };
+            let part = match self.players[i].snake.head() {
+                Ok(part) => part,
+                Err(e) => return TickResult::Failed(e),
+            };
+            if self.players[i].lives > 1 {
+                let snake = self.spawn_snake(i);
+                let ticks = (INVULNERABLE_DURATION * self.speed() as f64) as u32;
+                self.players[i].respawn(snake, ticks);
+                if self.rules.coop {
+                    // The team shares its lives.
+                    let lives = self.players[i].lives;
+                    for player in &mut self.players {
+                        player.lives = lives;
+                    }
+                }
+                lost_life = lost_life.or(Some(cause));
+            } else {
+                self.players[i].death = Some(cause);
+                died = died.or(Some(cause));
+            }
+            self.events.push(GameEvent::SnakeDied {
+                player: i,
+                part,
+                cause,
+                lives_left: if self.players[i].alive() {
+                    self.players[i].lives
+                } else {
+                    0
+                },
+            });
+        }
+
+        if died.is_none() && self.rules.coop && self.team_length() >= COOP_LENGTH {
+            self.won = true;
+            self.events.push(GameEvent::LevelCompleted);
+            return TickResult::Won;
+        }
+        match (died, lost_life) {
+            (Some(cause), _) => TickResult::Died(cause),
+            (None, _) if self.time_up() => TickResult::TimeUp,
+            (None, Some(cause)) => TickResult::LostLife(cause),
+            (None, None) => match eaten {
+                Some(kind) => TickResult::Ate(kind),
+                None => TickResult::Moved,
+            },
+        }
+    }
+
+    // FNV-1a over the state two runs of the same game have to agree on:
+    // every snake, score and life, the food, the walls grown so far and the
+    // clock. Cheap enough to take every tick.
+    pub fn checksum(&self) -> u32 {
+        let mut hash: u32 = 0x811c_9dc5;
+        let mut feed = |value: i64| {
+            for byte in value.to_le_bytes().iter() {
+                hash = (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193);
+            }
+        };
+        feed(self.tick as i64);
+        feed(i64::from(self.foods_eaten));
+        feed(self.elapsed.to_bits() as i64);
+        for player in &self.players {
+            let (dx, dy) = player.snake.dir.offset();
+            feed(i64::from(dx));
+            feed(i64::from(dy));
+            feed(i64::from(player.score.points));
+            feed(i64::from(player.lives));
+            feed(i64::from(player.alive()));
+            for part in player.snake.segments() {
+                feed(i64::from(part.x));
+                feed(i64::from(part.y));
+            }
+        }
+        for food in &self.foods {
+            feed(i64::from(food.part.x));
+            feed(i64::from(food.part.y));
+            feed(food.kind as i64);
+        }
+        let kept = self.level.obstacles.len() - self.added_walls;
+        for wall in &self.level.obstacles[kept..] {
+            feed(i64::from(wall.x));
+            feed(i64::from(wall.y));
+        }
+        hash
+    }
+
+    fn playback_checksums(&self) -> bool {
+        self.playback
+            .as_ref()
+            .is_some_and(|playback| !playback.checksums.is_empty())
+    }
+
+    // Keeps the checksum taken at the start of this tick, and holds it
+    // against the one the replay being played back kept.
+    fn verify(&mut self, checksum: u32) {
+        if self.record_checksums {
+            self.recording.checksums.push(checksum);
+        }
+        let recorded = self
+            .playback
+            .as_ref()
+            .and_then(|playback| playback.checksums.get(self.tick).copied());
+        if self.diverged.is_none() && recorded.is_some_and(|recorded| recorded != checksum) {
+            self.diverged = Some(self.tick);
+            self.events.push(GameEvent::Diverged { tick: self.tick });
+        }
+    }
+
+    // Whether the snake has to wait this tick because its way is blocked by
+    // another snake and the rules let it.
+    fn stalled(&self, index: usize) -> bool {
+        if self.rules.contact() != Contact::Stall {
+            return false;
+        }
+        let next = match self.players[index]
+            .snake
+            .next_head(&self.rules, &self.level)
+        {
+            Ok(next) => next,
+            Err(_) => return false,
+        };
+        self.players
+            .iter()
+            .enumerate()
+            .any(|(i, other)| i != index && other.snake.contains(next))
+    }
+
+    // Points scored between all the players, the team's score in co-op.
+    pub fn team_score(&self) -> u32 {
+        self.players.iter().map(|p| p.score.points).sum()
+    }
+
+    pub fn team_length(&self) -> usize {
+        self.players.iter().map(|p| p.snake.len()).sum()
+    }
+
+    fn steer_ai(&mut self) {
+        if self.players.iter().all(|p| p.ai.is_none()) {
+            return;
+        }
+
+        let mut blocked: HashSet<(i32, i32)> =
+            self.level.obstacles.iter().map(|o| (o.x, o.y)).collect();
+        let board = self.rules.board;
+        blocked.extend(board.positions().filter(|&(x, y)| !self.in_arena(x, y)));
+        for player in &self.players {
+            blocked.extend(player.snake.segments().map(|p| (p.x, p.y)));
+        }
+        // Where the hazards are now and where they'll be after this move.
+        for tick in self.tick..=self.tick + 1 {
+            blocked.extend(self.hazards_at(tick).map(|p| (p.x, p.y)));
+        }
+
+        let mut food = HashSet::new();
+        for f in &self.foods {
+            if f.kind == FoodKind::Poison {
+                blocked.insert((f.part.x, f.part.y));
+            } else {
+                food.insert((f.part.x, f.part.y));
+            }
+        }
+        let rules = &self.rules;
+        for player in &mut self.players {
+            if let Some(difficulty) = player.ai {
+                let tuning = player.tuning.unwrap_or_else(|| difficulty.tuning(rules));
+                player.snake.dir = ai::next_direction(
+                    &player.snake,
+                    &blocked,
+                    &food,
+                    tuning,
+                    rules,
+                    &self.level,
+                    &mut self.rng,
+                );
+            }
+        }
+    }
+
+    // Each time a snake scores, an adaptive AI that has fallen more than
+    // RUBBER_BAND_FOODS behind the best human plays a step stronger, and
+    // one that has pulled as far ahead a step weaker.
+    fn rubber_band(&mut self) {
+        let best = self
+            .players
+            .iter()
+            .filter(|p| p.ai.is_none())
+            .map(|p| p.score.points)
+            .max();
+        let best = match best {
+            Some(best) => best,
+            None => return,
+        };
+        let gap = self.rules.speed_preset().food_points() * RUBBER_BAND_FOODS;
+        let rules = &self.rules;
+        for player in &mut self.players {
+            if player.ai != Some(ai::Difficulty::Adaptive) {
+                continue;
+            }
+            let tuning = player
+                .tuning
+                .unwrap_or_else(|| ai::Difficulty::Adaptive.tuning(rules));
+            let points = player.score.points;
+            if best > points + gap {
+                player.tuning = Some(tuning.stronger(rules));
+            } else if points > best + gap {
+                player.tuning = Some(tuning.weaker());
+            }
+        }
+    }
+
+    fn eat(&mut self, index: usize, food: usize) {
+        let Food { part, kind, .. } = self.foods.remove(food);
+        let effect_ticks = (EFFECT_DURATION * self.speed() as f64) as u32;
+        let player = &mut self.players[index];
+        self.foods_eaten += 1;
+
+        if kind == FoodKind::Shrink {
+            player.snake.shrink(SHRINK_SEGMENTS);
+        } else {
+            player.snake.grow();
+        }
+        let value = if kind == FoodKind::Golden {
+            GOLDEN_VALUE
+        } else {
+            1
+        };
+        let base = self.rules.speed_preset().food_points();
+        let points = player.score.food_eaten(value * base, self.tick);
+        self.events.push(GameEvent::FoodEaten {
+            player: index,
+            part,
+            kind,
+            points,
+        });
+
+        match kind {
+            FoodKind::SlowMo => player.effects.slow_mo = effect_ticks / 2,
+            FoodKind::Ghost => player.effects.ghost = effect_ticks,
+            _ => {}
+        }
+        if let FoodKind::SlowMo | FoodKind::Ghost | FoodKind::Shrink = kind {
+            self.events.push(GameEvent::PowerUpActivated {
+                player: index,
+                kind,
+            });
+        }
+        if self.rules.endless && self.foods_eaten.is_multiple_of(ENDLESS_WALL_EVERY) {
+            self.grow_wall();
+        }
+        self.fill_food();
+        if self.edible_food() == 0 {
+            self.won = true;
+            self.filled = true;
+            self.events.push(GameEvent::LevelCompleted);
+        }
+    }
+
+    // Returns true when the poison is fatal: a snake too short to lose
+    // POISON_SEGMENTS and keep its head and tail dies.
+    fn poison(&mut self, index: usize, food: usize) -> bool {
+        let Food { part, .. } = self.foods.remove(food);
+        let player = &mut self.players[index];
+        let fatal = player.snake.len() < POISON_SEGMENTS + 2;
+        if !fatal {
+            player.snake.shrink(POISON_SEGMENTS);
+        }
+        let penalty = player.score.poisoned();
+        self.events.push(GameEvent::Poisoned {
+            player: index,
+            part,
+            penalty,
+        });
+        self.fill_food();
+        fatal
+    }
+
+    // Food caught on the ring that just closed is moved inside.
+    fn shrink_arena(&mut self) {
+        let closed = self.closed_rings();
+        let board = self.rules.board;
+        self.foods
+            .retain(|food| board.inside(food.part.x, food.part.y, closed));
+        self.fill_food();
+        self.events.push(GameEvent::ArenaShrank { closed });
+    }
+
+    // Tries random spots until a wall fits on free cells away from the heads
+    // without cutting the board in two; gives up quietly on a crowded board.
+    fn grow_wall(&mut self) {
+        let free: HashSet<(i32, i32)> = self.free_cells().iter().map(|p| (p.x, p.y)).collect();
+        let heads: Vec<Pos> = self
+            .players
+            .iter()
+            .filter_map(|player| player.snake.head().ok())
+            .collect();
+        for _ in 0..WALL_ATTEMPTS {
+            let x = self.rng.gen_range(0, self.rules.board.columns);
+            let y = self.rng.gen_range(0, self.rules.board.rows);
+            let (dx, dy) = if self.rng.gen_range(0, 2) == 0 {
+                (1, 0)
+            } else {
+                (0, 1)
+            };
+            let wall: Vec<(i32, i32)> =
+                (0..WALL_LENGTH).map(|i| (x + dx * i, y + dy * i)).collect();
+            let clear = wall.iter().all(|&(x, y)| {
+                free.contains(&(x, y))
+                    && heads
+                        .iter()
+                        .all(|head| head.distance(Pos::new(x, y)) >= WALL_CLEARANCE)
+            });
+            if clear && self.stays_connected(&wall) {
+                let obstacles = wall.iter().map(|&(x, y)| Obstacle { x, y });
+                self.level.obstacles.extend(obstacles);
+                self.added_walls += wall.len();
+                return;
+            }
+        }
+    }
+
+    // Flood fills the cells left open with `wall` added and checks that it
+    // reaches all of them, so no part of the board gets sealed off.
+    fn stays_connected(&self, wall: &[(i32, i32)]) -> bool {
+        let board = self.rules.board;
+        let blocked: HashSet<(i32, i32)> = self
+            .level
+            .obstacles
+            .iter()
+            .map(|o| (o.x, o.y))
+            .chain(wall.iter().copied())
+            .collect();
+        let open: Vec<(i32, i32)> = board
+            .positions()
+            .filter(|cell| !blocked.contains(cell))
+            .collect();
+        let start = match open.first() {
+            Some(&start) => start,
+            None => return false,
+        };
+
+        let wrap = self.rules.board_mode == BoardMode::Wrap;
+        let mut seen = HashSet::new();
+        seen.insert(start);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            for next in Pos::new(x, y).neighbours().iter() {
+                let next = if wrap {
+                    next.wrapped(board)
+                } else if next.on(board) {
+                    *next
+                } else {
+                    continue;
+                };
+                let cell = (next.x, next.y);
+                if !blocked.contains(&cell) && seen.insert(cell) {
+                    queue.push_back(cell);
+                }
+            }
+        }
+        seen.len() == open.len()
+    }
+
+    fn free_cells(&self) -> Vec<Pos> {
+        let foods: HashSet<(i32, i32)> = self.foods.iter().map(|f| (f.part.x, f.part.y)).collect();
+        let level = &self.level;
+        self.rules
+            .board
+            .positions()
+            .filter(|&(x, y)| {
+                self.in_arena(x, y)
+                    && !foods.contains(&(x, y))
+                    && !self.players.iter().any(|p| p.snake.contains(Pos { x, y }))
+                    && !level.contains(x, y)
+                    && !level.is_portal(x, y)
+                    && !level.is_patrolled(x, y)
+            })
+            .map(|(x, y)| Pos { x, y })
+            .collect()
+    }
+
+    // Free cells where new food is fair with `rules.food_distance`: far
+    // enough from every head and not just ahead of one. Any free cell when
+    // that leaves none.
+    fn spawn_cells(&self) -> Vec<Pos> {
+        let free = self.free_cells();
+        let distance = self.rules.food_distance as i32;
+        if distance == 0 {
+            return free;
+        }
+        let board = self.rules.board;
+        let wrap = |cell: Pos| match self.rules.board_mode {
+            BoardMode::Wrap => cell.wrapped(board),
+            BoardMode::Walls => cell,
+        };
+        let mut near = Vec::new();
+        for player in self.players.iter().filter(|p| p.alive()) {
+            if let Ok(head) = player.snake.head() {
+                let dir = player.snake.dir;
+                near.push((head, wrap(head + dir), wrap(head + dir + dir)));
+            }
+        }
+        let fair: Vec<Pos> = free
+            .iter()
+            .copied()
+            .filter(|&cell| {
+                near.iter().all(|&(head, one, two)| {
+                    head.distance(cell) >= distance && cell != one && cell != two
+                })
+            })
+            .collect();
+        if fair.is_empty() {
+            free
+        } else {
+            fair
+        }
+    }
+
+    // Adds one random pellet on a free cell.
+    pub fn place_food(&mut self) -> Result<(), SnakeError> {
+        self.place(None)
+    }
+
+    // Poison always fades and jumps elsewhere, so it never walls off a cell
+    // for good.
+    fn place(&mut self, kind: Option<FoodKind>) -> Result<(), SnakeError> {
+        let free = self.spawn_cells();
+        if free.is_empty() {
+            return Err(SnakeError::BoardFull);
+        }
+        let part = free[self.rng.gen_range(0, free.len())];
+        let (kind, behavior) = match kind {
+            Some(FoodKind::Poison) => (FoodKind::Poison, Behavior::Timed),
+            _ => {
+                let kind = kind.unwrap_or_else(|| FoodKind::random(&mut self.rng));
+                (kind, Behavior::random(&mut self.rng))
+            }
+        };
+        let lifetime = match behavior {
+            Behavior::Still => 0,
+            Behavior::Timed => (TIMED_FOOD_DURATION * self.speed() as f64) as u32,
+            Behavior::Fleeing => FLEE_EVERY,
+        };
+        self.foods.push(Food {
+            part,
+            kind,
+            behavior,
+            timer: lifetime,
+            lifetime,
+        });
+        Ok(())
+    }
+
+    fn tick_food(&mut self) {
+        for i in 0..self.foods.len() {
+            let food = &mut self.foods[i];
+            if food.behavior == Behavior::Still {
+                continue;
+            }
+            food.timer = food.timer.saturating_sub(1);
+            if food.timer > 0 {
+                continue;
+            }
+            food.timer = food.lifetime;
+
+            let target = match self.foods[i].behavior {
+                Behavior::Timed => {
+                    let free = self.spawn_cells();
+                    if free.is_empty() {
+                        None
+                    } else {
+                        Some(free[self.rng.gen_range(0, free.len())])
+                    }
+                }
+                Behavior::Fleeing => self.flee_cell(self.foods[i].part, &self.free_cells()),
+                _ => None,
+            };
+            if let Some(part) = target {
+                self.foods[i].part = part;
+            }
+        }
+    }
+
+    // The free neighbour furthest from the nearest snake head, if it is
+    // further away than the current cell.
+    fn flee_cell(&self, from: Pos, free: &[Pos]) -> Option<Pos> {
+        let heads: Vec<Pos> = self
+            .players
+            .iter()
+            .filter_map(|player| player.snake.head().ok())
+            .collect();
+        let distance = |cell: Pos| {
+            heads
+                .iter()
+                .map(|head| head.distance(cell))
+                .min()
+                .unwrap_or(0)
+        };
+
+        free.iter()
+            .copied()
+            .filter(|cell| cell.distance(from) == 1)
+            .filter(|&cell| distance(cell) > distance(from))
+            .max_by_key(|&cell| distance(cell))
+    }
+
+    fn edible_food(&self) -> usize {
+        self.foods
+            .iter()
+            .filter(|f| f.kind != FoodKind::Poison)
+            .count()
+    }
+
+    fn fill_food(&mut self) {
+        while self.edible_food() < self.rules.food_count && self.place_food().is_ok() {
+            let poison = self.foods.len() - self.edible_food();
+            if poison < MAX_POISON && self.rng.gen_range(0, POISON_CHANCE) == 0 {
+                // A full board only means one less poison.
+                let _ = self.place(Some(FoodKind::Poison));
+            }
+        }
+    }
+
+    fn death_cause(&self, index: usize) -> Option<DeathCause> {
+        let player = &self.players[index];
+        let head = player.snake.head().ok()?;
+        let hit_other = self.rules.contact() == Contact::Crash
+            && self
+                .players
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != index)
+                .any(|(_, other)| other.snake.contains(head));
+
+        let invulnerable = player.effects.invulnerable_active();
+
+        if player.snake.out_of_bounds(&self.rules, self.closed_rings()) {
+            Some(DeathCause::Wall)
+        } else if self.level.contains(head.x, head.y) {
+            Some(DeathCause::Obstacle)
+        } else if !invulnerable && self.hazards().any(|cell| player.snake.contains(cell)) {
+            Some(DeathCause::Hazard)
+        } else if let Some(cause) = self
+            .squeezed(index)
+            .filter(|&cause| cause == DeathCause::Obstacle || !invulnerable)
+        {
+            Some(cause)
+        } else if (player.snake.collision()
+            || (!self.rules.tail_chase && player.snake.chased_tail()))
+            && !player.effects.ghost_active()
+            && !invulnerable
+        {
+            Some(DeathCause::SelfCollision)
+        } else if hit_other && !invulnerable {
+            Some(DeathCause::OtherSnake)
+        } else {
+            None
+        }
+    }
+
+    // A diagonal step between two blocked cells, through the corner of a
+    // wall or across another diagonal, crashes into the first of them.
+    fn squeezed(&self, index: usize) -> Option<DeathCause> {
+        let player = &self.players[index];
+        if !player.snake.dir.is_diagonal() {
+            return None;
+        }
+        let (dx, dy) = player.snake.dir.offset();
+        let neck = player.snake.segments().nth(1)?;
+        let blocked = |cell: Pos| {
+            let cell = match self.rules.board_mode {
+                BoardMode::Wrap => cell.wrapped(self.rules.board),
+                BoardMode::Walls => cell,
+            };
+            if self.level.contains(cell.x, cell.y) {
+                Some(DeathCause::Obstacle)
+            } else if player.snake.contains(cell) && !player.effects.ghost_active() {
+                Some(DeathCause::SelfCollision)
+            } else if self.rules.contact() == Contact::Crash
+                && self
+                    .players
+                    .iter()
+                    .enumerate()
+                    .any(|(i, other)| i != index && other.snake.contains(cell))
+            {
+                Some(DeathCause::OtherSnake)
+            } else {
+                None
+            }
+        };
+        match (
+            blocked(Pos::new(neck.x + dx, neck.y)),
+            blocked(Pos::new(neck.x, neck.y + dy)),
+        ) {
+            (Some(cause), Some(_)) => Some(cause),
+            _ => None,
+        }
+    }
+
+    // A two-cell snake in the free corner of the arena furthest from the
+    // other snakes.
+    fn spawn_snake(&self, index: usize) -> Snake {
+        let edge = self.closed_rings();
+        let (right, bottom) = (
+            self.rules.board.columns - 1 - edge,
+            self.rules.board.rows - 1 - edge,
+        );
+        let corners = [
+            (edge, edge, edge + 1, Direction::Right),
+            (right, edge, edge + 1, Direction::Left),
+            (edge, bottom, bottom - 1, Direction::Right),
+            (right, bottom, bottom - 1, Direction::Left),
+        ];
+
+        let others: Vec<&Player> = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != index)
+            .map(|(_, player)| player)
+            .collect();
+        let free = |x: i32, y: i32| {
+            !self.level.contains(x, y)
+                && !self.level.is_portal(x, y)
+                && !self.level.is_patrolled(x, y)
+                && !others
+                    .iter()
+                    .any(|other| other.snake.contains(Pos { x, y }))
+        };
+        let distance = |x: i32, y: i32| {
+            others
+                .iter()
+                .filter_map(|other| other.snake.head().ok())
+                .map(|head| head.distance(Pos { x, y }))
+                .min()
+                .unwrap_or(0)
+        };
+
+        let (x, y, tail_y, dir) = corners
+            .iter()
+            .copied()
+            .filter(|&(x, y, tail_y, dir)| {
+                let ahead = if dir == Direction::Right {
+                    x + 1
+                } else {
+                    x - 1
+                };
+                free(x, y) && free(x, tail_y) && free(ahead, y)
+            })
+            .max_by_key(|&(x, y, _, _)| distance(x, y))
+            .unwrap_or(corners[index % corners.len()]);
+        Snake::new(vec![Pos { x, y }, Pos { x, y: tail_y }], dir)
+    }
+
+    fn new_players(&self, count: usize) -> Vec<Player> {
+        let snakes = match self.rules.spawn {
+            Spawn::Center => self.start_snakes(count),
+            Spawn::Corner => Vec::new(),
+        };
+        (0..count)
+            .map(|i| {
+                let snake = match snakes.get(i) {
+                    Some(snake) => snake.clone(),
+                    None if i == 0 => Snake::init(),
+                    None => Snake::init_player_two(&self.rules),
+                };
+                Player::new(i, snake, &self.rules)
+            })
+            .collect()
+    }
+
+    // One snake as close to the middle as there's room, or two at mirrored
+    // cells either side of it. Empty if the level leaves no room at all.
+    fn start_snakes(&self, count: usize) -> Vec<Snake> {
+        let board = self.rules.board;
+        let mut cells: Vec<Pos> = (0..board.rows)
+            .flat_map(|y| (0..board.columns).map(move |x| Pos::new(x, y)))
+            .collect();
+        if count < 2 {
+            let middle = Pos::new(board.columns / 2, board.rows / 2);
+            cells.sort_by_key(|&cell| cell.distance(middle));
+            return cells
+                .into_iter()
+                .find_map(|cell| self.start_at(cell, &[]))
+                .into_iter()
+                .collect();
+        }
+
+        let preferred = Pos::new(board.columns / 4, board.rows / 2);
+        cells.sort_by_key(|&cell| cell.distance(preferred));
+        let mirror = |cell: Pos| Pos::new(board.columns - 1 - cell.x, board.rows - 1 - cell.y);
+        let pair = |min_distance: i32| {
+            cells.iter().find_map(|&cell| {
+                if cell.distance(mirror(cell)) < min_distance {
+                    return None;
+                }
+                let first = self.start_at(cell, &[])?;
+                let second = self.start_at(mirror(cell), &[&first])?;
+                Some(vec![first, second])
+            })
+        };
+        // Boards too small to keep the distance still get a fair start.
+        pair(MIN_SPAWN_DISTANCE)
+            .or_else(|| pair(1))
+            .unwrap_or_default()
+    }
+
+    // A two-cell snake with its head on `head`, facing the way with the
+    // longest free run that doesn't lead straight into another snake. The
+    // cells behind and ahead of the head have to be free too.
+    fn start_at(&self, head: Pos, others: &[&Snake]) -> Option<Snake> {
+        let board = self.rules.board;
+        let free = |cell: Pos| {
+            board.contains(cell.x, cell.y)
+                && !self.level.contains(cell.x, cell.y)
+                && !self.level.is_portal(cell.x, cell.y)
+                && !self.level.is_patrolled(cell.x, cell.y)
+                && !others.iter().any(|other| other.contains(cell))
+        };
+        if !free(head) {
+            return None;
+        }
+        let wrap = self.rules.board_mode == BoardMode::Wrap;
+        let run = |dir: Direction| {
+            let mut cell = head;
+            for length in 0..board.columns.max(board.rows) {
+                cell = cell + dir;
+                if wrap {
+                    let (x, y) = board.wrap(cell.x, cell.y);
+                    cell = Pos::new(x, y);
+                }
+                if others.iter().any(|other| other.contains(cell)) {
+                    return 0;
+                }
+                if !free(cell) {
+                    return length;
+                }
+            }
+            board.columns.max(board.rows)
+        };
+        let mut best: Option<(Direction, i32)> = None;
+        for &dir in &[
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+            Direction::Up,
+        ] {
+            let length = run(dir);
+            let tail = head + dir.opposite();
+            if length > 0 && free(tail) && best.is_none_or(|(_, most)| length > most) {
+                best = Some((dir, length));
+            }
+        }
+        let (dir, _) = best?;
+        Some(Snake::new(vec![head, head + dir.opposite()], dir))
+    }
+
+    pub fn restart(&mut self) {
+        let kept = self.level.obstacles.len() - self.added_walls;
+        self.level.obstacles.truncate(kept);
+        self.added_walls = 0;
+        self.players = self.new_players(self.rules.players);
+        self.foods_eaten = 0;
+        self.won = false;
+        self.filled = false;
+        self.elapsed = 0.0;
+        self.events.clear();
+        self.seed = self.rules.seed.unwrap_or_else(rand::random);
+        self.rng = Pcg32::seed_from_u64(self.seed);
+        self.recording = Replay::new(self.seed, &self.rules);
+        self.diverged = None;
+        self.tick = 0;
+        self.foods.clear();
+        self.fill_food();
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let kept = self.level.obstacles.len() - self.added_walls;
+        Snapshot {
+            players: self.players.clone(),
+            foods: self.foods.clone(),
+            walls: self.level.obstacles[kept..].to_vec(),
+            tick: self.tick,
+            foods_eaten: self.foods_eaten,
+            won: self.won,
+            filled: self.filled,
+            elapsed: self.elapsed,
+            recorded: self.recording.inputs.iter().map(Vec::len).collect(),
+            rng: self.rng.clone(),
+        }
+    }
+
+    // Puts the game back to `snapshot`, dropping the inputs recorded since.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let kept = self.level.obstacles.len() - self.added_walls;
+        self.level.obstacles.truncate(kept);
+        self.level.obstacles.extend_from_slice(&snapshot.walls);
+        self.added_walls = snapshot.walls.len();
+        self.players = snapshot.players.clone();
+        self.foods = snapshot.foods.clone();
+        self.tick = snapshot.tick;
+        self.foods_eaten = snapshot.foods_eaten;
+        self.won = snapshot.won;
+        self.filled = snapshot.filled;
+        self.elapsed = snapshot.elapsed;
+        for (inputs, &len) in self.recording.inputs.iter_mut().zip(&snapshot.recorded) {
+            inputs.truncate(len);
+        }
+        self.recording.checksums.truncate(snapshot.tick);
+        self.rng = snapshot.rng.clone();
+        self.events.clear();
+    }
+}
+
+pub fn save_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("snake_2d").join("save.ron"))
+}
+
+// The run being played, kept every few seconds; only left behind when the
+// game didn't shut down cleanly.
+pub fn autosave_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("snake_2d").join("autosave.ron"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::rewind::Rewind;
+
+    fn rules(seed: u64) -> Rules {
+        Rules {
+            board: Board::new(5, 5),
+            seed: Some(seed),
+            ..Rules::default()
+        }
+    }
+
+    // Every cell of a 5x5 board in a single serpentine path, starting at (0, 0).
+    fn serpentine() -> Vec<Pos> {
+        let mut cells = Vec::new();
+        for y in 0..5 {
+            for i in 0..5 {
+                let x = if y % 2 == 0 { i } else { 4 - i };
+                cells.push(Pos { x, y });
+            }
+        }
+        cells
+    }
+
+    fn set_body(game: &mut Game, body: Vec<Pos>) {
+        game.players[0].snake = Snake::new(body, Direction::Right);
+    }
+
+    fn respawn(game: &mut Game) -> Pos {
+        game.foods.clear();
+        assert!(game.place_food().is_ok());
+        game.foods[0].part
+    }
+
+    #[test]
+    fn food_never_spawns_on_the_snake_or_obstacles() {
+        let mut game = Game::new(rules(1), Level::load("1", rules(1).board).unwrap());
+        game.rules.board = Board::new(20, 20);
+        for _ in 0..500 {
+            let food = respawn(&mut game);
+            assert!(!game.level.contains(food.x, food.y));
+            assert!(!game.players[0].snake.contains(food));
+        }
+    }
+
+    #[test]
+    fn wide_boards_wrap_and_place_food_within_their_own_bounds() {
+        let mut game = Game::new(rules(3), Level::empty());
+        game.rules.board = Board::parse("large").unwrap();
+        game.rules.board_mode = BoardMode::Wrap;
+        for _ in 0..500 {
+            let food = respawn(&mut game);
+            assert!(game.rules.board.contains(food.x, food.y));
+        }
+
+        set_body(&mut game, vec![Pos { x: 39, y: 29 }, Pos { x: 38, y: 29 }]);
+        game.players[0]
+            .snake
+            .update_direction(&game.rules, &game.level)
+            .unwrap();
+        let head = game.players[0].snake.head().unwrap();
+        assert_eq!((head.x, head.y), (0, 29));
+    }
+
+    #[test]
+    fn a_snake_without_segments_fails_the_tick_instead_of_panicking() {
+        let mut game = Game::new(rules(4), Level::empty());
+        game.restart();
+        set_body(&mut game, Vec::new());
+        assert_eq!(game.step(None), TickResult::Failed(SnakeError::EmptySnake));
+
+        game.restart();
+        assert_ne!(game.step(None), TickResult::Failed(SnakeError::EmptySnake));
+    }
+
+    #[test]
+    fn chasing_the_tail_is_not_a_collision_but_biting_the_body_is() {
+        // A 2x2 loop: the head moves onto the cell the tail is leaving.
+        let square = vec![
+            Pos { x: 1, y: 0 },
+            Pos { x: 1, y: 1 },
+            Pos { x: 0, y: 1 },
+            Pos { x: 0, y: 0 },
+        ];
+        let rules = rules(5);
+        let mut snake = Snake::new(square.clone(), Direction::Left);
+        snake.update_direction(&rules, &Level::empty()).unwrap();
+        assert!(!snake.collision());
+
+        let saved = ron::to_string(&Snake::new(square, Direction::Left)).unwrap();
+        let mut loaded: Snake = ron::from_str(&saved).unwrap();
+        loaded.grow();
+        loaded.update_direction(&rules, &Level::empty()).unwrap();
+        assert!(loaded.collision());
+        assert!(loaded.contains(Pos { x: 0, y: 0 }) && loaded.contains(Pos { x: 1, y: 1 }));
+    }
+
+    #[test]
+    fn chasing_the_tail_can_be_ruled_out() {
+        let square = vec![
+            Pos { x: 1, y: 0 },
+            Pos { x: 1, y: 1 },
+            Pos { x: 0, y: 1 },
+            Pos { x: 0, y: 0 },
+        ];
+        for &(tail_chase, dies) in &[(true, false), (false, true)] {
+            let rules = Rules {
+                tail_chase,
+                lives: 1,
+                ..rules(5)
+            };
+            let mut game = Game::new(rules, Level::empty());
+            game.foods.clear();
+            game.players[0].snake = Snake::new(square.clone(), Direction::Left);
+            let result = game.step(None);
+            assert_eq!(result == TickResult::Died(DeathCause::SelfCollision), dies);
+        }
+    }
+
+    #[test]
+    fn a_rewound_game_plays_out_the_same_way_again() {
+        let mut game = Game::new(
+            Rules {
+                seed: Some(6),
+                ..Rules::default()
+            },
+            Level::empty(),
+        );
+        game.restart();
+        game.players[0].ai = Some(ai::Difficulty::Normal);
+        let mut history = Rewind::new();
+        let play = |game: &mut Game, history: &mut Rewind| {
+            (0..40)
+                .map(|_| {
+                    history.record(game);
+                    game.step(None);
+                    let head = game.players[0].snake.head().unwrap();
+                    let foods: Vec<Pos> = game.foods.iter().map(|f| f.part).collect();
+                    (head, foods)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let first = play(&mut game, &mut history);
+        assert!(history.rewind(&mut game));
+        assert_eq!(game.tick, 30);
+        let again = play(&mut game, &mut history);
+        assert_eq!(first[30..], again[..10]);
+    }
+
+    #[test]
+    fn adaptive_ai_plays_stronger_behind_and_weaker_ahead() {
+        let rules = Rules {
+            board: Board::new(20, 20),
+            ai_opponent: true,
+            ai_difficulty: ai::Difficulty::Adaptive,
+            ..rules(16)
+        };
+        let mut game = Game::new(rules.validated(), Level::empty());
+        game.restart();
+        let food_at_head = |game: &mut Game| {
+            game.foods[0].part = game.players[0].snake.head().unwrap();
+            game.foods[0].kind = FoodKind::Normal;
+        };
+        let normal = ai::Difficulty::Normal.tuning(&game.rules);
+
+        game.players[0].score.points = 100;
+        food_at_head(&mut game);
+        game.step(None);
+        assert_eq!(game.players[1].tuning, Some(normal.stronger(&game.rules)));
+
+        game.players[0].score.points = 0;
+        game.players[1].score.points = 100;
+        food_at_head(&mut game);
+        game.step(None);
+        let back = normal.stronger(&game.rules).weaker();
+        assert_eq!(game.players[1].tuning, Some(back));
+    }
+
+    #[test]
+    fn food_can_spawn_on_every_free_cell() {
+        let mut game = Game::new(rules(2), Level::empty());
+        let mut seen = HashSet::new();
+        for _ in 0..2000 {
+            let food = respawn(&mut game);
+            seen.insert((food.x, food.y));
+        }
+        assert_eq!(seen.len(), 25 - game.players[0].snake.len());
+    }
+
+    #[test]
+    fn food_spawns_on_the_last_free_cell() {
+        let mut game = Game::new(rules(3), Level::empty());
+        let mut body = serpentine();
+        let last = body.pop().unwrap();
+        set_body(&mut game, body);
+
+        let food = respawn(&mut game);
+        assert_eq!((food.x, food.y), (last.x, last.y));
+    }
+
+    #[test]
+    fn full_board_has_no_food() {
+        let mut game = Game::new(rules(4), Level::empty());
+        set_body(&mut game, serpentine());
+        game.foods.clear();
+        assert_eq!(game.place_food(), Err(SnakeError::BoardFull));
+    }
+
+    #[test]
+    fn eating_the_last_food_wins() {
+        let mut game = Game::new(rules(5), Level::empty());
+        let mut body = serpentine();
+        body.reverse();
+        let head = body[0];
+        set_body(&mut game, body);
+        game.foods[0].part = head;
+
+        assert_eq!(game.step(None), TickResult::Won);
+        assert!(game.won);
+        assert!(game.filled);
+    }
+
+    #[test]
+    fn starting_food_is_randomized() {
+        let positions: HashSet<(i32, i32)> = (0..20)
+            .map(|seed| {
+                let game = Game::new(rules(seed), Level::empty());
+                (game.foods[0].part.x, game.foods[0].part.y)
+            })
+            .collect();
+        assert!(positions.len() > 1);
+    }
+
+    #[test]
+    fn timed_food_relocates_when_it_expires() {
+        let mut game = Game::new(rules(7), Level::empty());
+        let food = &mut game.foods[0];
+        food.behavior = Behavior::Timed;
+        food.timer = 1;
+        food.lifetime = 10;
+        let before = food.part;
+
+        game.tick_food();
+        let food = &game.foods[0];
+        assert!(food.part.x != before.x || food.part.y != before.y);
+        assert_eq!(food.timer, 10);
+    }
+
+    #[test]
+    fn fleeing_food_moves_away_from_the_head() {
+        let mut game = Game::new(rules(8), Level::empty());
+        set_body(&mut game, vec![Pos { x: 1, y: 2 }, Pos { x: 0, y: 2 }]);
+        let food = &mut game.foods[0];
+        food.part = Pos { x: 2, y: 2 };
+        food.behavior = Behavior::Fleeing;
+        food.timer = 1;
+        food.lifetime = FLEE_EVERY;
+
+        game.tick_food();
+        let food = game.foods[0].part;
+        assert_eq!(food.distance(Pos::new(1, 2)), 2);
+    }
+
+    #[test]
+    fn losing_a_life_respawns_the_snake() {
+        let mut game = Game::new(rules(9), Level::empty());
+        game.foods.clear();
+        set_body(&mut game, vec![Pos { x: 4, y: 2 }, Pos { x: 3, y: 2 }]);
+
+        assert_eq!(game.step(None), TickResult::LostLife(DeathCause::Wall));
+        let player = &game.players[0];
+        assert!(player.alive());
+        assert_eq!(player.lives, 2);
+        assert_eq!(player.snake.len(), 2);
+        assert!(player.effects.invulnerable_active());
+        assert!(!player.snake.out_of_bounds(&game.rules, 0));
+    }
+
+    #[test]
+    fn losing_the_last_life_ends_the_game() {
+        let rules = Rules {
+            lives: 1,
+            ..rules(10)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        game.foods.clear();
+        set_body(&mut game, vec![Pos { x: 4, y: 2 }, Pos { x: 3, y: 2 }]);
+
+        assert_eq!(game.step(None), TickResult::Died(DeathCause::Wall));
+        assert!(!game.players[0].alive());
+    }
+
+    #[test]
+    fn time_limit_ends_the_game() {
+        let rules = Rules {
+            board: Board::new(20, 5),
+            time_limit: Some(1),
+            ..rules(11)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        game.foods.clear();
+        let ticks = game.speed();
+        for _ in 1..ticks {
+            assert_eq!(game.step(None), TickResult::Moved);
+        }
+        assert_eq!(game.step(None), TickResult::TimeUp);
+        assert!(game.time_up());
+    }
+
+    #[test]
+    fn food_keeps_its_distance_from_the_head() {
+        let rules = Rules {
+            board: Board::new(10, 10),
+            food_distance: 2,
+            ..rules(5)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        set_body(&mut game, vec![Pos::new(4, 4), Pos::new(3, 4)]);
+        for _ in 0..300 {
+            let food = respawn(&mut game);
+            assert!(food.distance(Pos::new(4, 4)) >= 2);
+            assert_ne!(food, Pos::new(6, 4));
+        }
+
+        // A board with no fair cell left still gets its food.
+        game.rules.food_distance = 20;
+        assert!(game.place_food().is_ok());
+    }
+
+    #[test]
+    fn heads_close_to_the_edge_they_face_are_warned() {
+        let mut game = Game::new(rules(1), Level::empty());
+        set_body(&mut game, vec![Pos::new(2, 1), Pos::new(1, 1)]);
+        assert_eq!(game.edge_ahead(0), None);
+        set_body(&mut game, vec![Pos::new(3, 1), Pos::new(2, 1)]);
+        assert_eq!(game.edge_ahead(0), Some(Direction::Right));
+        game.players[0].snake.dir = Direction::Up;
+        assert_eq!(game.edge_ahead(0), Some(Direction::Up));
+        game.players[0].snake.dir = Direction::Down;
+        assert_eq!(game.edge_ahead(0), None);
+
+        game.rules.board_mode = BoardMode::Wrap;
+        game.players[0].snake.dir = Direction::Right;
+        assert_eq!(game.edge_ahead(0), None);
+    }
+
+    #[test]
+    fn eaten_pellets_are_replaced() {
+        let rules = Rules {
+            food_count: 4,
+            ..rules(6)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        assert_eq!(game.foods.len(), 4);
+
+        let head = game.players[0].snake.head().unwrap();
+        game.foods[2].part = head;
+        game.foods[2].kind = FoodKind::Normal;
+        game.step(None);
+
+        assert_eq!(game.foods.len(), 4);
+        let cells: HashSet<(i32, i32)> = game.foods.iter().map(|f| (f.part.x, f.part.y)).collect();
+        assert_eq!(cells.len(), 4);
+        assert_eq!(game.foods_eaten, 1);
+    }
+
+    #[test]
+    fn endless_mode_grows_walls_that_keep_the_board_open() {
+        let rules = Rules {
+            board: Board::new(20, 20),
+            endless: true,
+            ..rules(14)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        game.foods_eaten = ENDLESS_WALL_EVERY - 1;
+        let head = game.players[0].snake.head().unwrap();
+        game.foods[0].part = head;
+        game.foods[0].kind = FoodKind::Normal;
+        game.step(None);
+
+        assert_eq!(game.level.obstacles.len(), WALL_LENGTH as usize);
+        assert!(game.stays_connected(&[]));
+        game.restart();
+        assert!(game.level.obstacles.is_empty());
+    }
+
+    #[test]
+    fn a_shrinking_arena_closes_its_outer_ring_on_snakes_and_food() {
+        let rules = Rules {
+            board: Board::new(12, 12),
+            shrinking: true,
+            food_count: 5,
+            lives: 1,
+            // On the outer ring, so the first one to close kills it.
+            spawn: Spawn::Corner,
+            ..rules(8)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        game.elapsed = SHRINK_EVERY - game.tick_interval() / 2.0;
+        assert_eq!(game.closed_rings(), 0);
+
+        let result = game.step(None);
+        assert_eq!(game.closed_rings(), 1);
+        assert!(matches!(result, TickResult::Died(DeathCause::Wall)));
+        assert!(game.foods.iter().all(|f| game.in_arena(f.part.x, f.part.y)));
+        assert!(game
+            .events
+            .iter()
+            .any(|e| matches!(e, GameEvent::ArenaShrank { closed: 1 })));
+
+        game.elapsed = 10.0 * SHRINK_EVERY;
+        assert_eq!(game.closed_rings(), 3);
+        assert_eq!(game.next_ring_in(), None);
+    }
+
+    fn coop(board: Board) -> Game {
+        let rules = Rules {
+            board,
+            coop: true,
+            players: 2,
+            lives: 2,
+            ..rules(5)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        game.restart();
+        game.foods.clear();
+        game
+    }
+
+    #[test]
+    fn coop_snakes_wait_for_each_other_and_share_their_lives() {
+        let mut game = coop(Board::new(10, 10));
+        set_body(&mut game, vec![Pos::new(2, 5), Pos::new(1, 5)]);
+        game.players[1].snake = Snake::new(
+            vec![Pos::new(3, 6), Pos::new(3, 5), Pos::new(3, 4)],
+            Direction::Down,
+        );
+        assert!(matches!(game.step(None), TickResult::Moved));
+        assert_eq!(game.players[0].snake.head().unwrap(), Pos::new(2, 5));
+        assert_eq!(game.players[1].snake.head().unwrap(), Pos::new(3, 7));
+
+        game.players[1].snake = Snake::new(vec![Pos::new(9, 0), Pos::new(8, 0)], Direction::Right);
+        assert!(matches!(
+            game.step(None),
+            TickResult::LostLife(DeathCause::Wall)
+        ));
+        assert_eq!(game.players[0].lives, 1);
+        assert_eq!(game.players[1].lives, 1);
+    }
+
+    #[test]
+    fn coop_is_won_at_the_combined_length() {
+        let mut game = coop(Board::new(45, 5));
+        let length = COOP_LENGTH as i32 - 2;
+        set_body(
+            &mut game,
+            (0..length).rev().map(|x| Pos::new(x, 0)).collect(),
+        );
+        game.players[1].snake = Snake::new(vec![Pos::new(0, 4), Pos::new(0, 3)], Direction::Right);
+        assert!(matches!(game.step(None), TickResult::Won));
+        assert!(game.won);
+        assert!(!game.filled);
+    }
+
+    #[test]
+    fn replays_only_match_the_rules_they_were_played_with() {
+        let game = Game::new(rules(15), Level::empty());
+        let mut other = game.rules.clone();
+        other.seed = Some(16);
+        assert!(game.recording.matches(&other));
+        other.update_speed += 1;
+        assert!(!game.recording.matches(&other));
+    }
+
+    #[test]
+    fn turns_are_reported_and_recorded() {
+        let rules = Rules {
+            board: Board::new(20, 20),
+            ..rules(12)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        game.foods.clear();
+        let start = game.players[0].snake.dir;
+        let turned = |game: &Game| {
+            game.events.iter().find_map(|event| match *event {
+                GameEvent::DirectionChanged { dir, .. } => Some(dir),
+                _ => None,
+            })
+        };
+
+        game.step(None);
+        assert_eq!(turned(&game), Some(start));
+        game.step(Some(Direction::Down));
+        assert_eq!(turned(&game), Some(Direction::Down));
+        game.step(None);
+        assert_eq!(turned(&game), None);
+        assert_eq!(
+            game.recording.inputs[0],
+            [start, Direction::Down, Direction::Down]
+        );
+    }
+
+    #[test]
+    fn playback_reports_the_first_tick_its_checksums_differ() {
+        let rules = Rules {
+            board: Board::new(20, 20),
+            ..rules(17)
+        };
+        let mut game = Game::new(rules.clone(), Level::empty());
+        game.record_checksums = true;
+        for _ in 0..6 {
+            game.step(None);
+        }
+        let replay = game.recording;
+        assert_eq!(replay.checksums.len(), 6);
+        let diverged = |replay: Replay| {
+            let mut game = Game::new(rules.clone(), Level::empty());
+            game.playback = Some(replay);
+            let mut ticks = Vec::new();
+            for _ in 0..6 {
+                game.step(None);
+                ticks.extend(game.events.iter().filter_map(|event| match *event {
+                    GameEvent::Diverged { tick } => Some(tick),
+                    _ => None,
+                }));
+            }
+            (ticks, game.playback.unwrap())
+        };
+
+        let (ticks, mut replay) = diverged(replay);
+        assert_eq!(ticks, []);
+        replay.checksums[3] ^= 1;
+        replay.checksums[4] ^= 1;
+        assert_eq!(diverged(replay).0, [3]);
+    }
+
+    #[test]
+    fn portals_move_the_head_to_their_twin() {
+        let layout = "..1..\n.....\n.....\n.....\n..1..\n";
+        let mut game = Game::new(rules(13), Level::parse("Portals", layout));
+        game.foods.clear();
+        set_body(&mut game, vec![Pos { x: 1, y: 0 }, Pos { x: 0, y: 0 }]);
+
+        game.step(None);
+        let body: Vec<(i32, i32)> = game.players[0]
+            .snake
+            .segments()
+            .map(|p| (p.x, p.y))
+            .collect();
+        assert_eq!(body, [(2, 4), (1, 0)]);
+        game.step(None);
+        let head = game.players[0].snake.head().unwrap();
+        assert_eq!((head.x, head.y), (3, 4));
+        assert!(game.players[0].alive());
+    }
+
+    #[test]
+    fn diagonal_steps_need_the_rule_and_cannot_slip_between_walls() {
+        let layout = ".....\n.....\n...#.\n..#..\n.....\n";
+        for &(diagonals, turn, head) in &[
+            (false, Direction::UpRight, Some((3, 2))),
+            (true, Direction::UpRight, Some((3, 1))),
+            (true, Direction::DownRight, None),
+        ] {
+            let rules = Rules {
+                diagonals,
+                lives: 1,
+                ..rules(14)
+            };
+            let mut game = Game::new(rules, Level::parse("Corner", layout));
+            game.foods.clear();
+            set_body(&mut game, vec![Pos { x: 2, y: 2 }, Pos { x: 1, y: 2 }]);
+            game.players[0].steer(turn);
+            let result = game.step(None);
+            match head {
+                Some(head) => {
+                    let pos = game.players[0].snake.head().unwrap();
+                    assert_eq!((pos.x, pos.y), head);
+                }
+                None => assert_eq!(result, TickResult::Died(DeathCause::Obstacle)),
+            }
+        }
+    }
+
+    #[test]
+    fn hazards_patrol_back_and_forth_and_end_the_run_on_contact() {
+        let layout = ".....\naaA..\n.....\n.....\n.....\n";
+        let rules = Rules {
+            lives: 1,
+            ..rules(15)
+        };
+        let mut game = Game::new(rules, Level::parse("Patrol", layout));
+        assert!(game.foods.iter().all(|food| food.part.y != 1));
+        let path: Vec<(i32, i32)> = (0..8)
+            .step_by(2)
+            .map(|tick| game.level.patrols[0].at(tick))
+            .collect();
+        assert_eq!(path, [(2, 1), (1, 1), (0, 1), (1, 1)]);
+
+        game.foods.clear();
+        set_body(&mut game, vec![Pos { x: 1, y: 3 }, Pos { x: 0, y: 3 }]);
+        assert_eq!(game.step(Some(Direction::Up)), TickResult::Moved);
+        assert_eq!(game.step(None), TickResult::Died(DeathCause::Hazard));
+    }
+
+    fn poison_at_head(game: &mut Game) {
+        let head = game.players[0].snake.head().unwrap();
+        game.foods.clear();
+        game.foods.push(Food {
+            part: head,
+            kind: FoodKind::Poison,
+            behavior: Behavior::Still,
+            timer: 0,
+            lifetime: 0,
+        });
+    }
+
+    #[test]
+    fn poison_shrinks_the_snake_and_costs_points() {
+        let rules = Rules {
+            board: Board::new(20, 5),
+            ..rules(14)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        let body = (0..6).rev().map(|x| Pos { x, y: 0 }).collect();
+        set_body(&mut game, body);
+        game.players[0].score.points = 50;
+        poison_at_head(&mut game);
+
+        assert_eq!(game.step(None), TickResult::Ate(FoodKind::Poison));
+        assert_eq!(game.players[0].snake.len(), 3);
+        assert_eq!(game.players[0].score.points, 20);
+        assert_eq!(game.foods.len(), 1);
+    }
+
+    #[test]
+    fn poison_kills_a_short_snake() {
+        let rules = Rules {
+            board: Board::new(20, 5),
+            lives: 1,
+            ..rules(15)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        poison_at_head(&mut game);
+
+        assert_eq!(game.step(None), TickResult::Died(DeathCause::Poison));
+        assert_eq!(game.players[0].score.points, 0);
+    }
+
+    #[test]
+    fn snakes_start_in_the_middle_apart_and_facing_open_space() {
+        let rules = Rules {
+            board: Board::new(20, 20),
+            ..rules(1)
+        };
+        let mut game = Game::new(rules, Level::empty());
+        assert_eq!(game.players[0].snake.head().unwrap(), Pos::new(10, 10));
+        assert_eq!(game.edge_ahead(0), None);
+
+        for &size in &[20, 15] {
+            game.rules.board = Board::new(size, size);
+            game.rules.players = 2;
+            game.restart();
+            let heads: Vec<Pos> = game
+                .players
+                .iter()
+                .map(|p| p.snake.head().unwrap())
+                .collect();
+            assert_eq!(
+                heads[1],
+                Pos::new(size - 1 - heads[0].x, size - 1 - heads[0].y)
+            );
+            assert!(heads[0].distance(heads[1]) >= MIN_SPAWN_DISTANCE);
+            let ahead = heads[1] + game.players[1].snake.dir;
+            assert!(!game.players[0].snake.contains(ahead));
+        }
+
+        game.rules.spawn = Spawn::Corner;
+        game.restart();
+        assert_eq!(game.players[0].snake.head().unwrap(), Pos::new(0, 0));
+    }
+}
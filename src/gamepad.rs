@@ -0,0 +1,143 @@
+use piston::input::{ControllerAxisArgs, ControllerButton, ControllerHat, HatState};
+use snake_2d::Direction;
+use std::collections::HashMap;
+
+const DEAD_ZONE: f64 = 0.5;
+const AXIS_LEFT_X: u8 = 0;
+const AXIS_LEFT_Y: u8 = 1;
+const BUTTON_START: u8 = 6;
+const BUTTON_DPAD_UP: u8 = 11;
+const BUTTON_DPAD_DOWN: u8 = 12;
+const BUTTON_DPAD_LEFT: u8 = 13;
+const BUTTON_DPAD_RIGHT: u8 = 14;
+
+#[derive(Clone, Copy)]
+pub enum Input {
+    Turn(usize, Direction),
+    Start,
+}
+
+#[derive(Default)]
+pub struct Sticks {
+    positions: HashMap<i32, [f64; 2]>,
+    held: HashMap<i32, Direction>,
+}
+
+impl Sticks {
+    pub fn axis(&mut self, args: &ControllerAxisArgs) -> Option<Input> {
+        let position = self.positions.entry(args.id).or_insert([0.0, 0.0]);
+        match args.axis {
+            AXIS_LEFT_X => position[0] = args.position,
+            AXIS_LEFT_Y => position[1] = args.position,
+            _ => return None,
+        }
+
+        let [x, y] = *position;
+        let dir = if x.abs().max(y.abs()) < DEAD_ZONE {
+            self.held.remove(&args.id);
+            return None;
+        } else if x.abs() > y.abs() {
+            if x > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if y > 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        };
+
+        if self.held.insert(args.id, dir) == Some(dir) {
+            return None;
+        }
+        Some(Input::Turn(player(args.id), dir))
+    }
+}
+
+pub fn button(button: ControllerButton) -> Option<Input> {
+    let dir = match button.button {
+        BUTTON_START => return Some(Input::Start),
+        BUTTON_DPAD_UP => Direction::Up,
+        BUTTON_DPAD_DOWN => Direction::Down,
+        BUTTON_DPAD_LEFT => Direction::Left,
+        BUTTON_DPAD_RIGHT => Direction::Right,
+        _ => return None,
+    };
+    Some(Input::Turn(player(button.id), dir))
+}
+
+pub fn hat(hat: ControllerHat) -> Option<Input> {
+    let dir = match hat.state {
+        HatState::Up => Direction::Up,
+        HatState::Down => Direction::Down,
+        HatState::Left => Direction::Left,
+        HatState::Right => Direction::Right,
+        _ => return None,
+    };
+    Some(Input::Turn(player(hat.id), dir))
+}
+
+fn player(id: i32) -> usize {
+    id.max(0) as usize
+}
+
+#[cfg(feature = "gamepad")]
+pub struct Gamepads {
+    gilrs: Option<gilrs::Gilrs>,
+    sticks: Sticks,
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepads {
+    pub fn init() -> Gamepads {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                log::warn!("Could not open gamepads: {}", e);
+                None
+            }
+        };
+        Gamepads {
+            gilrs,
+            sticks: Sticks::default(),
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<Input> {
+        use gilrs::{Axis, Button, EventType};
+
+        let mut inputs = Vec::new();
+        let gilrs = match self.gilrs {
+            Some(ref mut gilrs) => gilrs,
+            None => return inputs,
+        };
+        while let Some(event) = gilrs.next_event() {
+            let id = usize::from(event.id) as i32;
+            let input = match event.event {
+                EventType::ButtonPressed(Button::Start, _) => Some(Input::Start),
+                EventType::ButtonPressed(Button::DPadUp, _) => {
+                    button(ControllerButton::new(id, BUTTON_DPAD_UP))
+                }
+                EventType::ButtonPressed(Button::DPadDown, _) => {
+                    button(ControllerButton::new(id, BUTTON_DPAD_DOWN))
+                }
+                EventType::ButtonPressed(Button::DPadLeft, _) => {
+                    button(ControllerButton::new(id, BUTTON_DPAD_LEFT))
+                }
+                EventType::ButtonPressed(Button::DPadRight, _) => {
+                    button(ControllerButton::new(id, BUTTON_DPAD_RIGHT))
+                }
+                EventType::AxisChanged(Axis::LeftStickX, value, _) => self
+                    .sticks
+                    .axis(&ControllerAxisArgs::new(id, AXIS_LEFT_X, f64::from(value))),
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => self
+                    .sticks
+                    .axis(&ControllerAxisArgs::new(id, AXIS_LEFT_Y, -f64::from(value))),
+                _ => None,
+            };
+            inputs.extend(input);
+        }
+        inputs
+    }
+}
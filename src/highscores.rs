@@ -0,0 +1,229 @@
+use snake_2d::SpeedPreset;
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_ENTRIES: usize = 10;
+// Daily challenge tables older than this many dates are dropped.
+const DAILY_DATES: usize = 7;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Clone)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    pub timestamp: u64,
+    // Missing from scores saved before presets existed.
+    pub preset: Option<SpeedPreset>,
+    // The date of the daily challenge the score was set in. Each date has a
+    // table of its own, apart from the regular one.
+    pub daily: Option<String>,
+    // Set by filling the whole board.
+    pub perfect: bool,
+}
+
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    path: Option<PathBuf>,
+}
+
+impl HighScores {
+    pub fn load() -> HighScores {
+        let path = dirs::config_dir().map(|dir| dir.join("snake_2d").join("highscores.txt"));
+        let entries = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|contents| parse(&contents))
+            .unwrap_or_default();
+
+        HighScores { entries, path }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Written aside and moved over the old table, so a crash halfway
+        // through leaves the old one whole.
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, format(&self.entries))?;
+        fs::rename(&temp, path)
+    }
+
+    // The regular table, or the one for a daily challenge date; best first.
+    pub fn table(&self, daily: Option<&str>) -> Vec<&HighScore> {
+        self.entries
+            .iter()
+            .filter(|e| e.daily.as_deref() == daily)
+            .collect()
+    }
+
+    pub fn qualifies(&self, score: u32, daily: Option<&str>) -> bool {
+        let table = self.table(daily);
+        score > 0 && (table.len() < MAX_ENTRIES || table.last().is_none_or(|e| score > e.score))
+    }
+
+    pub fn add(
+        &mut self,
+        name: &str,
+        score: u32,
+        preset: SpeedPreset,
+        daily: Option<&str>,
+        perfect: bool,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.entries.push(HighScore {
+            name: name.to_string(),
+            score,
+            timestamp,
+            preset: Some(preset),
+            daily: daily.map(String::from),
+            perfect,
+        });
+        prune(&mut self.entries);
+    }
+}
+
+// Sorts best first and keeps the top of each table, for the latest
+// `DAILY_DATES` daily challenges.
+fn prune(entries: &mut Vec<HighScore>) {
+    let mut dates: Vec<String> = entries.iter().filter_map(|e| e.daily.clone()).collect();
+    dates.sort_by(|a, b| b.cmp(a));
+    dates.dedup();
+    dates.truncate(DAILY_DATES);
+
+    entries.sort_by_key(|e| Reverse(e.score));
+    let mut counts: Vec<(Option<String>, usize)> = Vec::new();
+    entries.retain(|e| {
+        if e.daily.as_ref().is_some_and(|date| !dates.contains(date)) {
+            return false;
+        }
+        match counts.iter_mut().find(|(daily, _)| *daily == e.daily) {
+            Some((_, count)) => {
+                *count += 1;
+                *count <= MAX_ENTRIES
+            }
+            None => {
+                counts.push((e.daily.clone(), 1));
+                true
+            }
+        }
+    });
+}
+
+fn format(entries: &[HighScore]) -> String {
+    entries
+        .iter()
+        .map(|e| {
+            let preset = e.preset.map_or("", SpeedPreset::name);
+            let daily = e.daily.as_deref().unwrap_or("");
+            let perfect = if e.perfect { "perfect" } else { "" };
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                e.score, e.timestamp, e.name, preset, daily, perfect
+            )
+        })
+        .collect()
+}
+
+fn parse(contents: &str) -> Vec<HighScore> {
+    let mut entries: Vec<HighScore> = contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(6, '\t');
+            let score = fields.next()?.parse().ok()?;
+            let timestamp = fields.next()?.parse().ok()?;
+            let name = fields.next()?.to_string();
+            let preset = fields.next().and_then(SpeedPreset::parse);
+            let daily = fields.next().filter(|date| !date.is_empty());
+            let perfect = fields.next() == Some("perfect");
+            Some(HighScore {
+                name,
+                score,
+                timestamp,
+                preset,
+                daily: daily.map(String::from),
+                perfect,
+            })
+        })
+        .collect();
+    prune(&mut entries);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            score,
+            timestamp: 1_700_000_000,
+            preset: Some(SpeedPreset::Fast),
+            daily: None,
+            perfect: false,
+        }
+    }
+
+    #[test]
+    fn saved_scores_parse_back_the_same() {
+        let mut daily = entry("Bo", 30);
+        daily.daily = Some(String::from("2024-05-01"));
+        daily.preset = None;
+        let entries = vec![entry("Ann Lee", 50), daily];
+
+        let parsed = parse(&format(&entries));
+        assert_eq!(parsed.len(), 2);
+        for (parsed, entry) in parsed.iter().zip(&entries) {
+            assert_eq!(parsed.name, entry.name);
+            assert_eq!(parsed.score, entry.score);
+            assert_eq!(parsed.timestamp, entry.timestamp);
+            assert_eq!(parsed.preset, entry.preset);
+            assert_eq!(parsed.daily, entry.daily);
+        }
+    }
+
+    #[test]
+    fn perfect_runs_are_kept_apart_from_older_files() {
+        let mut perfect = entry("Ann", 50);
+        perfect.perfect = true;
+        let parsed = parse(&format(&[perfect, entry("Bo", 30)]));
+        assert!(parsed[0].perfect);
+        assert!(!parsed[1].perfect);
+
+        // Saved before the perfect field existed.
+        let parsed = parse("40\t1\tCy\tfast\t\n");
+        assert_eq!(parsed[0].preset, Some(SpeedPreset::Fast));
+        assert!(!parsed[0].perfect);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let contents = "40\t1\tAnn\nnot a score\n\t\t\nx\t1\tBo\n20\t2\tCy\n";
+        let names: Vec<String> = parse(contents).into_iter().map(|e| e.name).collect();
+        assert_eq!(names, ["Ann", "Cy"]);
+    }
+
+    #[test]
+    fn only_the_best_scores_are_kept() {
+        let entries: Vec<HighScore> = (1..=MAX_ENTRIES as u32 + 3)
+            .map(|score| entry("Ann", score))
+            .collect();
+
+        let parsed = parse(&format(&entries));
+        assert_eq!(parsed.len(), MAX_ENTRIES);
+        assert_eq!(parsed[0].score, MAX_ENTRIES as u32 + 3);
+        assert_eq!(parsed[MAX_ENTRIES - 1].score, 4);
+    }
+}
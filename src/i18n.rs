@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+
+// The built-in translations by language. English has every key; the others
+// may leave some out and fall back to it.
+pub const LOCALES: [(&str, &str); 4] = [
+    ("en", include_str!("../locales/en.ron")),
+    ("es", include_str!("../locales/es.ron")),
+    ("de", include_str!("../locales/de.ron")),
+    ("ru", include_str!("../locales/ru.ron")),
+];
+const FALLBACK: &str = "en";
+
+// On-screen text in the first preferred language that has it, then in
+// English, then the key itself.
+pub struct Locale {
+    pub language: &'static str,
+    tables: Vec<HashMap<String, String>>,
+}
+
+impl Locale {
+    // `preferred` holds language tags such as "de", "es-MX" or "ru_RU.UTF-8",
+    // most preferred first. A tag with a region also tries the language
+    // without it.
+    pub fn new(preferred: &[String]) -> Locale {
+        let mut chain = Vec::new();
+        for tag in preferred {
+            let tag = normalize(tag);
+            let language = tag.split('-').next().unwrap_or("");
+            for candidate in &[tag.as_str(), language] {
+                if let Some(&(name, _)) = LOCALES.iter().find(|&&(name, _)| name == *candidate) {
+                    if !chain.contains(&name) {
+                        chain.push(name);
+                    }
+                }
+            }
+        }
+        if !chain.contains(&FALLBACK) {
+            chain.push(FALLBACK);
+        }
+        Locale {
+            language: chain[0],
+            tables: chain.iter().map(|&name| table(name)).collect(),
+        }
+    }
+
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.tables
+            .iter()
+            .find_map(|table| table.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    // The text for `key` with every `{name}` replaced by its value.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Locale {
+        Locale::new(&[])
+    }
+}
+
+// The built-in table for `language`, an empty one if there is none.
+pub fn table(language: &str) -> HashMap<String, String> {
+    LOCALES
+        .iter()
+        .find(|&&(name, _)| name == language)
+        .map(|&(name, source)| {
+            ron::from_str(source).unwrap_or_else(|e| panic!("Invalid locale {}: {}", name, e))
+        })
+        .unwrap_or_default()
+}
+
+// The user's languages as the environment lists them: LANGUAGE may name
+// several ("es:de"), then LC_ALL, LC_MESSAGES and LANG name one each.
+pub fn system_languages() -> Vec<String> {
+    let mut languages: Vec<String> = env::var("LANGUAGE")
+        .unwrap_or_default()
+        .split(':')
+        .map(String::from)
+        .collect();
+    for name in &["LC_ALL", "LC_MESSAGES", "LANG"] {
+        if let Ok(value) = env::var(name) {
+            languages.push(value);
+        }
+    }
+    languages.retain(|tag| !tag.is_empty() && tag != "C" && tag != "POSIX");
+    languages
+}
+
+// "ru_RU.UTF-8" and "ru-ru@latin" both become "ru-ru".
+fn normalize(tag: &str) -> String {
+    tag.split(['.', '@'])
+        .next()
+        .unwrap_or("")
+        .replace('_', "-")
+        .to_lowercase()
+}
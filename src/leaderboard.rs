@@ -0,0 +1,139 @@
+#[cfg(feature = "leaderboard")]
+use log::warn;
+use serde::Deserialize;
+#[cfg(feature = "leaderboard")]
+use serde::Serialize;
+#[cfg(feature = "leaderboard")]
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+#[cfg(feature = "leaderboard")]
+use std::thread;
+#[cfg(feature = "leaderboard")]
+use std::time::Duration;
+
+pub const TOP_ENTRIES: usize = 20;
+#[cfg(feature = "leaderboard")]
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+}
+
+#[cfg(feature = "leaderboard")]
+#[derive(Serialize)]
+struct Submission {
+    name: String,
+    score: u32,
+    replay: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(not(feature = "leaderboard"), allow(dead_code))]
+pub enum Status {
+    Idle,
+    Pending,
+    Ready,
+    Failed,
+}
+
+// Submits finished runs to an HTTP endpoint and keeps the top entries it
+// returns. Requests run on a background thread so the game never waits on
+// the network; without the `leaderboard` feature every call is a no-op.
+pub struct Leaderboard {
+    pub entries: Vec<Entry>,
+    pub status: Status,
+    #[cfg(feature = "leaderboard")]
+    url: Option<String>,
+    #[cfg(feature = "leaderboard")]
+    results: Option<Receiver<Result<Vec<Entry>, String>>>,
+}
+
+impl Leaderboard {
+    pub fn init(url: Option<String>) -> Leaderboard {
+        #[cfg(not(feature = "leaderboard"))]
+        let _ = url;
+        Leaderboard {
+            entries: Vec::new(),
+            status: Status::Idle,
+            #[cfg(feature = "leaderboard")]
+            url,
+            #[cfg(feature = "leaderboard")]
+            results: None,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        #[cfg(feature = "leaderboard")]
+        {
+            self.url.is_some()
+        }
+        #[cfg(not(feature = "leaderboard"))]
+        {
+            false
+        }
+    }
+
+    // Posts the score, then fetches the top entries once the post went through.
+    pub fn submit(&mut self, name: &str, score: u32, replay_hash: u64) {
+        #[cfg(feature = "leaderboard")]
+        {
+            let url = match self.url {
+                Some(ref url) => url.clone(),
+                None => return,
+            };
+            let submission = Submission {
+                name: name.to_string(),
+                score,
+                replay: format!("{:016x}", replay_hash),
+            };
+            let (sender, results) = mpsc::channel();
+            thread::spawn(move || {
+                let _ = sender.send(exchange(&url, &submission));
+            });
+            self.results = Some(results);
+            self.status = Status::Pending;
+        }
+        #[cfg(not(feature = "leaderboard"))]
+        let _ = (name, score, replay_hash);
+    }
+
+    pub fn poll(&mut self) {
+        #[cfg(feature = "leaderboard")]
+        {
+            let result = match self.results.as_ref().map(Receiver::try_recv) {
+                Some(Ok(result)) => result,
+                Some(Err(TryRecvError::Disconnected)) => Err(String::from("request aborted")),
+                Some(Err(TryRecvError::Empty)) | None => return,
+            };
+            self.results = None;
+            match result {
+                Ok(entries) => {
+                    self.entries = entries;
+                    self.entries.truncate(TOP_ENTRIES);
+                    self.status = Status::Ready;
+                }
+                Err(e) => {
+                    warn!("Could not reach the leaderboard: {}", e);
+                    self.status = Status::Failed;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "leaderboard")]
+fn exchange(url: &str, submission: &Submission) -> Result<Vec<Entry>, String> {
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+    agent
+        .post(url)
+        .send_json(submission)
+        .map_err(|e| e.to_string())?;
+    agent
+        .get(url)
+        .query("limit", &TOP_ENTRIES.to_string())
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())
+}
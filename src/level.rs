@@ -0,0 +1,298 @@
+use crate::board::Board;
+use crate::maze;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const BUILTIN_LEVELS: [(&str, &str); 5] = [
+    ("Pillars", include_str!("../levels/1_pillars.txt")),
+    ("Cross", include_str!("../levels/2_cross.txt")),
+    ("Corridors", include_str!("../levels/3_corridors.txt")),
+    ("Rooms", include_str!("../levels/4_rooms.txt")),
+    ("Zigzag", include_str!("../levels/5_zigzag.txt")),
+];
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub x: i32,
+    pub y: i32,
+}
+
+// Each pair of portals gets its own color, repeating after the last one.
+pub const PORTAL_COLORS: [[f32; 4]; 3] = [
+    [0.0, 0.8, 1.0, 1.0],
+    [1.0, 0.4, 0.9, 1.0],
+    [1.0, 0.6, 0.1, 1.0],
+];
+
+// Two cells marked with the same digit in a level file. A snake that moves
+// onto one comes out of the other, still heading the same way.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Portal {
+    pub ends: [(i32, i32); 2],
+}
+
+// Hazards are drawn in this color on every theme.
+pub const HAZARD_COLOR: [f32; 4] = [0.9, 0.2, 0.1, 1.0];
+// A hazard moves one cell along its path every PATROL_EVERY ticks.
+pub const PATROL_EVERY: usize = 2;
+
+// Cells marked with the same letter in a level file, walked by a hazard that
+// ends a snake's life on contact. A path that closes on itself is walked
+// round and round, any other back and forth between its ends. The capital
+// letter marks where the hazard starts, by default at one end.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Patrol {
+    pub path: Vec<(i32, i32)>,
+    pub looped: bool,
+    pub start: usize,
+}
+
+impl Patrol {
+    // Where the hazard is on `tick`. It follows from the tick alone, so
+    // rewinding, replays and network games all agree on it.
+    pub fn at(&self, tick: usize) -> (i32, i32) {
+        let len = self.path.len();
+        let steps = self.start + tick / PATROL_EVERY;
+        let index = if self.looped || len < 2 {
+            steps % len
+        } else {
+            let round = 2 * (len - 1);
+            match steps % round {
+                forward if forward < len => forward,
+                back => round - back,
+            }
+        };
+        self.path[index]
+    }
+
+    // Orders the cells from one end, or from any cell of a loop, following
+    // neighbours. Cells off that single line are dropped.
+    fn trace(cells: &[(i32, i32)], start: Option<(i32, i32)>) -> Patrol {
+        let adjacent = |a: (i32, i32), b: (i32, i32)| (a.0 - b.0).abs() + (a.1 - b.1).abs() == 1;
+        let neighbours = |cell| cells.iter().filter(|&&other| adjacent(cell, other)).count();
+        let first = cells
+            .iter()
+            .copied()
+            .find(|&cell| neighbours(cell) == 1)
+            .unwrap_or(cells[0]);
+        let mut path = vec![first];
+        while let Some(&next) = cells
+            .iter()
+            .find(|&&cell| adjacent(path[path.len() - 1], cell) && !path.contains(&cell))
+        {
+            path.push(next);
+        }
+        let looped = path.len() > 3 && adjacent(path[0], path[path.len() - 1]);
+        Patrol {
+            start: start
+                .and_then(|start| path.iter().position(|&cell| cell == start))
+                .unwrap_or(0),
+            path,
+            looped,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub portals: Vec<Portal>,
+    #[serde(default)]
+    pub patrols: Vec<Patrol>,
+}
+
+impl Level {
+    pub fn empty() -> Level {
+        Level {
+            name: String::from("Classic"),
+            obstacles: Vec::new(),
+            portals: Vec::new(),
+            patrols: Vec::new(),
+        }
+    }
+
+    // A built-in level number, "maze" or "maze:<seed>" for a generated maze
+    // on `board`, or the path to a level file.
+    pub fn load(spec: &str, board: Board) -> Result<Level, String> {
+        if maze::is_spec(spec) {
+            let seed = match spec.split_once(':') {
+                Some((_, seed)) => seed
+                    .parse()
+                    .map_err(|_| format!("Invalid maze seed in {}", spec))?,
+                None => rand::random(),
+            };
+            return maze::generate(board, seed);
+        }
+        if let Ok(number) = spec.parse::<usize>() {
+            return match number {
+                0 => Ok(Level::empty()),
+                n if n <= BUILTIN_LEVELS.len() => {
+                    let (name, layout) = BUILTIN_LEVELS[n - 1];
+                    Ok(Level::parse(name, layout))
+                }
+                _ => Err(format!(
+                    "Unknown level {}, expected 0-{}, maze or a level file",
+                    number,
+                    BUILTIN_LEVELS.len()
+                )),
+            };
+        }
+
+        let path = Path::new(spec);
+        let layout = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read level {}: {}", spec, e))?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| spec.to_string());
+        Ok(Level::parse(&name, &layout))
+    }
+
+    pub fn parse(name: &str, layout: &str) -> Level {
+        let obstacles = layout
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, ch)| ch == '#')
+                    .map(move |(x, _)| Obstacle {
+                        x: x as i32,
+                        y: y as i32,
+                    })
+            })
+            .collect();
+
+        // A digit that appears anywhere but exactly twice is ignored.
+        let mut marks: BTreeMap<char, Vec<(i32, i32)>> = BTreeMap::new();
+        for (y, line) in layout.lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                if ch.is_ascii_digit() {
+                    marks.entry(ch).or_default().push((x as i32, y as i32));
+                }
+            }
+        }
+        let portals = marks
+            .values()
+            .filter(|cells| cells.len() == 2)
+            .map(|cells| Portal {
+                ends: [cells[0], cells[1]],
+            })
+            .collect();
+
+        let mut paths: BTreeMap<char, Vec<(i32, i32)>> = BTreeMap::new();
+        let mut starts = BTreeMap::new();
+        for (y, line) in layout.lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                if ch.is_ascii_alphabetic() {
+                    let (name, cell) = (ch.to_ascii_lowercase(), (x as i32, y as i32));
+                    paths.entry(name).or_default().push(cell);
+                    if ch.is_ascii_uppercase() {
+                        starts.insert(name, cell);
+                    }
+                }
+            }
+        }
+        let patrols = paths
+            .iter()
+            .map(|(name, cells)| Patrol::trace(cells, starts.get(name).copied()))
+            .collect();
+
+        Level {
+            name: name.to_string(),
+            obstacles,
+            portals,
+            patrols,
+        }
+    }
+
+    // Reports what `parse` would silently skip in a layout: characters with
+    // no meaning, portals without exactly two ends and hazard paths that
+    // aren't a single line of cells.
+    pub fn check(layout: &str) -> Result<(), String> {
+        if layout.trim().is_empty() {
+            return Err(String::from("The layout is empty"));
+        }
+        let mut marks: BTreeMap<char, Vec<(i32, i32)>> = BTreeMap::new();
+        let mut starts: BTreeMap<char, usize> = BTreeMap::new();
+        for (y, line) in layout.lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                match ch {
+                    '.' | '#' | ' ' => {}
+                    _ if ch.is_ascii_alphanumeric() => {
+                        let cell = (x as i32, y as i32);
+                        marks.entry(ch.to_ascii_lowercase()).or_default().push(cell);
+                        if ch.is_ascii_uppercase() {
+                            *starts.entry(ch.to_ascii_lowercase()).or_default() += 1;
+                        }
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Line {}, column {}: unexpected '{}'",
+                            y + 1,
+                            x + 1,
+                            ch
+                        ))
+                    }
+                }
+            }
+        }
+        for (&mark, cells) in &marks {
+            if mark.is_ascii_digit() && cells.len() != 2 {
+                return Err(format!(
+                    "Portal {} has {} ends instead of 2",
+                    mark,
+                    cells.len()
+                ));
+            }
+            if mark.is_ascii_digit() {
+                continue;
+            }
+            if starts.get(&mark).copied().unwrap_or(0) > 1 {
+                return Err(format!(
+                    "Hazard {} has more than one start",
+                    mark.to_ascii_uppercase()
+                ));
+            }
+            if Patrol::trace(cells, None).path.len() != cells.len() {
+                return Err(format!(
+                    "The cells of hazard {} don't form a single path",
+                    mark
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.obstacles.iter().any(|o| o.x == x && o.y == y)
+    }
+
+    pub fn portal_color(index: usize) -> [f32; 4] {
+        PORTAL_COLORS[index % PORTAL_COLORS.len()]
+    }
+
+    pub fn is_portal(&self, x: i32, y: i32) -> bool {
+        self.portal_exit(x, y).is_some()
+    }
+
+    // Whether a hazard passes over (x, y) at some point.
+    pub fn is_patrolled(&self, x: i32, y: i32) -> bool {
+        self.patrols
+            .iter()
+            .any(|patrol| patrol.path.contains(&(x, y)))
+    }
+
+    // The twin of the portal at (x, y), if there is one.
+    pub fn portal_exit(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        self.portals.iter().find_map(|portal| match portal.ends {
+            [a, b] if a == (x, y) => Some(b),
+            [a, b] if b == (x, y) => Some(a),
+            _ => None,
+        })
+    }
+}
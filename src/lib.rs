@@ -0,0 +1,29 @@
+pub mod accessibility;
+pub mod ai;
+pub mod board;
+pub mod bot;
+pub mod error;
+pub mod event;
+pub mod food;
+pub mod game;
+pub mod i18n;
+pub mod level;
+pub mod maze;
+pub mod metrics;
+pub mod player;
+pub mod pos;
+pub mod replay;
+pub mod rewind;
+pub mod rules;
+pub mod score;
+pub mod simulate;
+pub mod snake;
+
+pub use board::Board;
+pub use error::SnakeError;
+pub use event::GameEvent;
+pub use game::{DeathCause, Game, TickResult};
+pub use i18n::Locale;
+pub use pos::Pos;
+pub use rules::{BoardMode, Contact, Rules, Spawn, SpeedPreset};
+pub use snake::{Direction, Snake};
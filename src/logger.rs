@@ -0,0 +1,38 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::Warn;
+
+// Writes log records to stderr as "LEVEL target: message". The level starts
+// at DEFAULT_LEVEL so problems reading the config are reported, then follows
+// `log_level` in the config and finally `--log-level`.
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{} {}: {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(DEFAULT_LEVEL);
+    }
+}
+
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+pub fn parse_level(name: &str) -> Option<LevelFilter> {
+    name.parse().ok()
+}
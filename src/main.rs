@@ -2,232 +2,3129 @@ extern crate glutin_window;
 extern crate graphics;
 extern crate opengl_graphics;
 extern crate piston;
-extern crate rand;
+
+mod achievements;
+mod assets;
+mod attract;
+mod audio;
+mod campaign;
+mod cli;
+mod clip;
+mod config;
+mod daily;
+mod diagnostics;
+mod discord;
+mod feedback;
+mod gamepad;
+mod highscores;
+mod leaderboard;
+mod logger;
+mod net;
+mod particles;
+mod render;
+mod screenshot;
+mod snake_colors;
+mod spectator;
+mod stats;
+mod telemetry;
+#[cfg(feature = "tui")]
+mod terminal;
+mod theme;
+mod time_scale;
+mod tutorial;
+mod ui;
+mod user_assets;
 
 use glutin_window::GlutinWindow;
-use opengl_graphics::{GlGraphics, OpenGL};
+use graphics::character::CharacterCache;
+use opengl_graphics::{GlGraphics, GlyphCache, OpenGL, TextureSettings};
 use piston::event_loop::*;
 use piston::input::*;
-use piston::window::WindowSettings;
-use rand::Rng;
-use std::collections::LinkedList;
-use std::iter::FromIterator;
-
-const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.5, 0.2, 1.0];
-const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
-const GRID_ROWS: i32 = 20;
-const GRID_COLUMNS: i32 = 20;
-const BODY_SIZE: i32 = 25;
-const UPDATE_SPEED: u64 = 6;
+use piston::window::{AdvancedWindow, Window, WindowSettings};
+use std::collections::VecDeque;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::iter;
+use std::path::Path;
+use std::process;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use achievements::{Achievements, ACHIEVEMENTS};
+use assets::{Sprite, Sprites, SPRITES_PATH};
+use attract::Attract;
+use audio::{Audio, Sound};
+use campaign::{Campaign, STAGES};
+use cli::Command;
+use clip::Clip;
+use config::{Action, Config, ConfigWatcher, KeyMap, Renderer, ACTIONS, CONFIG_FILE};
+use daily::Daily;
+use diagnostics::Diagnostics;
+use discord::{Activity, Discord};
+use feedback::Feedback;
+use gamepad::{Input, Sticks};
+use highscores::{HighScores, MAX_NAME_LENGTH};
+use leaderboard::{Leaderboard, Status};
+use log::{debug, error, info, warn};
+use net::Connection;
+use particles::Particles;
+use render::{BoardStyle, Layout, Mark};
+use snake_2d::bot::{self, BoardView, Controller};
+use snake_2d::food::FoodKind;
+use snake_2d::game::{self, COOP_LENGTH};
+use snake_2d::level::{Level, BUILTIN_LEVELS, HAZARD_COLOR};
+use snake_2d::maze;
+use snake_2d::metrics::RunMetrics;
+use snake_2d::player::MAX_QUEUED_INPUTS;
+use snake_2d::replay::{self, Replay};
+use snake_2d::rewind::{Rewind, REWIND_TICKS};
+use snake_2d::rules::{MAX_FOOD, TIME_ATTACK_SECONDS};
+use snake_2d::simulate;
+use snake_2d::{
+    BoardMode, Direction, Game, GameEvent, Locale, Pos, Rules, Snake, SnakeError, TickResult,
+};
+use snake_colors::{color_key, Part, SnakeColors};
+use spectator::Spectator;
+use stats::Stats;
+use telemetry::Telemetry;
+use theme::{SnakeStyle, Theme};
+use time_scale::TimeScale;
+use tutorial::{Step, Tutorial};
+use ui::{Menu, Popup, Toast};
+use user_assets::UserAssets;
+
+const CRASH_COLOR: [f32; 4] = [1.0, 0.15, 0.0, 1.0];
+const OVERLAY_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const COUNTDOWN_SECONDS: f64 = 3.0;
+const AUTOSAVE_SECONDS: f64 = 5.0;
+const FPS_CHOICES: [u64; 6] = [30, 60, 120, 144, 240, 0];
+const TICK_RATE_CHOICES: [u64; 4] = [30, 60, 120, 240];
+const VOLUME_STEP: f32 = 0.1;
+// The board in the corner of the settings screen.
+const PREVIEW_COLUMNS: i32 = 8;
+const PREVIEW_ROWS: i32 = 6;
+const PREVIEW_CELL_SIZE: f64 = 16.0;
+const NET_QUEUE_LIMIT: usize = 3;
+const GHOST_ALPHA: f32 = 0.3;
+// Wrapping edges are drawn faintly, as they don't stop the snake.
+const WRAP_BORDER_ALPHA: f32 = 0.3;
+// The length graph on the game-over screen, in pixels.
+const GRAPH_SIZE: [f64; 2] = [200.0, 100.0];
+const ARENA_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+// The next ring of a shrinking arena darkens over its last seconds.
+const RING_WARNING_SECONDS: f64 = 5.0;
+const EDGE_WARNING_COLOR: [f32; 4] = [1.0, 0.25, 0.1, 0.8];
+// Pulses per second of the edge warning strip.
+const EDGE_WARNING_PULSE: f64 = 3.0;
+const FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono.ttf");
+const ICON: &[u8] = include_bytes!("../assets/icon.png");
 
 fn main() {
-    let opengl = OpenGL::V3_2;
+    logger::init();
+    let mut config = Config::load(CONFIG_FILE);
+    let mut headless = None;
+    let mut host = None;
+    let mut connection = None;
+    let mut bot = None;
+    let playback = match cli::parse_args(&mut config, env::args().skip(1)) {
+        Ok(Command::Play) => None,
+        Ok(Command::Headless(games, policy)) => {
+            headless = Some((games, policy));
+            None
+        }
+        Ok(Command::Host(port)) => {
+            config.rules.players = 2;
+            config.rules.ai_opponent = false;
+            config.rules.seed = Some(config.rules.seed.unwrap_or_else(rand::random));
+            host = Some(port);
+            None
+        }
+        Ok(Command::Connect(address)) => match net::connect(&address) {
+            Ok((conn, rules)) => {
+                config.rules = rules;
+                connection = Some(conn);
+                None
+            }
+            Err(e) => {
+                eprintln!("Could not join {}: {}", address, e);
+                process::exit(1);
+            }
+        },
+        Ok(Command::Bot(path)) => match bot::Process::spawn(&path) {
+            Ok(process) => {
+                config.rules.players = 2;
+                config.rules.ai_opponent = false;
+                config.rules.coop = false;
+                bot = Some(process);
+                None
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        },
+        Ok(Command::Replay(path)) => match Replay::load(&path) {
+            Ok(replay) => {
+                replay.apply_to(&mut config.rules);
+                Some(replay)
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        },
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Ok(Command::CheckAssets) => {
+            let assets = UserAssets::scan();
+            if let Some(dir) = user_assets::dir() {
+                println!("Looking in {}", dir.display());
+            }
+            for path in &assets.levels {
+                println!("Level {}", user_assets::name(path));
+            }
+            for theme in &assets.themes {
+                println!("Theme {}", theme.name);
+            }
+            for pack in &assets.sound_packs {
+                println!("Sound pack {}", pack.name);
+            }
+            for e in &assets.errors {
+                eprintln!("{}", e);
+            }
+            if !assets.errors.is_empty() {
+                process::exit(2);
+            }
+            return;
+        }
+        Ok(Command::StatsReport) => {
+            match telemetry::Report::load() {
+                Ok(report) => print!("{}", report),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(2);
+                }
+            }
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    logger::set_level(config.log_level);
+    if (host.is_some() || connection.is_some()) && config.renderer == Renderer::Terminal {
+        eprintln!("Network play needs the OpenGL renderer");
+        process::exit(2);
+    }
+    if bot.is_some() && config.renderer == Renderer::Terminal {
+        eprintln!("Racing a bot needs the OpenGL renderer");
+        process::exit(2);
+    }
+    let config = config.validated();
+    let level = match config.rules.level {
+        Some(ref spec) => Level::load(spec, config.rules.board).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        }),
+        None => Level::empty(),
+    };
+    if let Some((games, policy)) = headless {
+        match simulate::run(config.rules, level, games, policy) {
+            Ok(stats) => println!("{}", stats),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+        return;
+    }
+    if let Some(port) = host {
+        match net::host(port, &config.rules) {
+            Ok(conn) => connection = Some(conn),
+            Err(e) => {
+                eprintln!("Could not host on port {}: {}", port, e);
+                process::exit(1);
+            }
+        }
+    }
+    if config.renderer == Renderer::Terminal {
+        run_terminal(&config, level, playback);
+        return;
+    }
+
+    let (mut window, opengl) = make_window(&config);
 
-    let mut window = make_window(opengl);
+    let mut app = make_app(opengl, config, level);
+    let (size, draw_size) = (window.size(), window.draw_size());
+    app.resized([size.width, size.height], draw_size.width / size.width);
+    if playback.is_some() {
+        app.game.playback = playback;
+        app.restart();
+    }
+    if connection.is_some() {
+        app.net = connection;
+        app.restart();
+    }
+    if bot.is_some() {
+        app.bot = bot;
+        app.restart();
+    }
+    if app.state == GameState::Menu && game::autosave_path().is_some_and(|path| path.exists()) {
+        app.state = GameState::Resume;
+    }
+    if app.state == GameState::Menu && !tutorial::seen() {
+        app.start_tutorial();
+    }
 
-    let mut game = make_game(opengl);
+    game_loop(&mut app, &mut window);
+    if app.autosaved {
+        app.clear_autosave();
+    }
+    if let Err(e) = app.telemetry.save() {
+        error!("Could not save telemetry: {}", e);
+    }
+}
 
-    game_loop(&mut game, &mut window);
+#[cfg(feature = "tui")]
+fn run_terminal(config: &Config, level: Level, playback: Option<Replay>) {
+    let mut game = Game::new(config.rules.clone(), level);
+    game.playback = playback;
+    if let Err(e) = terminal::run(config, &mut game) {
+        eprintln!("Terminal error: {}", e);
+        process::exit(1);
+    }
 }
 
-fn make_window(opengl: OpenGL) -> GlutinWindow {
-    WindowSettings::new(
-        "Snake",
-        [
-            (GRID_COLUMNS * BODY_SIZE) as u32,
-            (GRID_ROWS * BODY_SIZE) as u32,
-        ],
-    )
-    .graphics_api(opengl)
-    .exit_on_esc(true)
-    .build()
-    .unwrap()
+#[cfg(not(feature = "tui"))]
+fn run_terminal(_config: &Config, _level: Level, _playback: Option<Replay>) {
+    eprintln!("The terminal renderer needs the tui feature: cargo run --features tui");
+    process::exit(2);
 }
 
-fn make_game(opengl: OpenGL) -> Game {
-    Game {
-        gl: GlGraphics::new(opengl),
-        snake: Snake::init(),
-        food: BodyPart {
-            x: GRID_COLUMNS / 2,
-            y: GRID_ROWS / 2,
+// Tries OpenGL 3.2 and then 2.1 for older drivers, unless a version is
+// forced with --gl. GlGraphics needs the version that worked to pick its
+// shaders.
+fn make_window(config: &Config) -> (GlutinWindow, OpenGL) {
+    let versions = match config.gl {
+        Some(ref name) => match config::gl_version(name) {
+            Some(version) => vec![version],
+            None => {
+                eprintln!("Unknown OpenGL version '{}'", name);
+                process::exit(2);
+            }
         },
+        None => vec![OpenGL::V3_2, OpenGL::V2_1],
+    };
+
+    let title = config.locale().get("title").to_string();
+    let board = config.rules.board;
+    let size = Layout::window_size(board.columns, board.rows, config.cell_size);
+    let mut errors = Vec::new();
+    for &opengl in &versions {
+        let window = WindowSettings::new(title.as_str(), [size[0] as u32, size[1] as u32])
+            .graphics_api(opengl)
+            .fullscreen(config.fullscreen)
+            .vsync(config.vsync)
+            .exit_on_esc(true)
+            .build();
+        match window {
+            Ok(window) => {
+                let (major, minor) = opengl.get_major_minor();
+                info!("Opened a window with OpenGL {}.{}", major, minor);
+                set_icon(&window);
+                return (window, opengl);
+            }
+            Err(e) => {
+                let (major, minor) = opengl.get_major_minor();
+                warn!(
+                    "Could not open a window with OpenGL {}.{}: {}",
+                    major, minor, e
+                );
+                errors.push(format!("OpenGL {}.{}: {}", major, minor, e));
+            }
+        }
+    }
+
+    eprintln!("Could not open a window:");
+    for error in &errors {
+        eprintln!("  {}", error);
+    }
+    eprintln!("Update the graphics driver, try another version with --gl, or play in a terminal with --renderer terminal.");
+    process::exit(1);
+}
+
+// Shown in the title bar and taskbar where the platform supports it.
+fn set_icon(window: &GlutinWindow) {
+    let icon = image::load_from_memory(ICON)
+        .map_err(|e| e.to_string())
+        .and_then(|image| {
+            let image = image.to_rgba();
+            let (width, height) = image.dimensions();
+            glutin::Icon::from_rgba(image.into_raw(), width, height).map_err(|e| e.to_string())
+        });
+    match icon {
+        Ok(icon) => window.ctx.window().set_window_icon(Some(icon)),
+        Err(e) => warn!("Could not set the window icon: {}", e),
+    }
+}
+
+fn make_app(opengl: OpenGL, config: Config, level: Level) -> App {
+    let user_assets = UserAssets::scan();
+    for e in &user_assets.errors {
+        warn!("Skipped {}", e);
+    }
+    let themes = user_assets.all_themes();
+    let theme = config.theme(&themes);
+    let mut audio = Audio::init(config.muted, config.volume);
+    if let Some(ref name) = config.sound_pack {
+        match user_assets.sound_pack(name) {
+            Some(pack) => audio.set_pack(Some(pack)),
+            None => warn!("Unknown sound pack '{}', using the built-in sounds", name),
+        }
+    }
+    let locale = config.locale();
+    let snake_colors = SnakeColors::load();
+    let settings = Menu::new(App::settings_items(
+        &config.rules,
+        &theme,
+        config.board_style,
+        &config,
+        &snake_colors,
+        &locale,
+    ));
+    let mut menu = Menu::new(App::menu_items(&locale));
+    let mode = Mode::from_rules(&config.rules);
+    menu.select(MODES.iter().position(|&(_, m)| m == mode).unwrap_or(0));
+    let mut game = Game::new(config.rules.clone(), level);
+    game.accessibility = config.accessibility;
+    game.record_checksums = config.checksums;
+
+    App {
+        gl: GlGraphics::new(opengl),
+        glyphs: GlyphCache::from_bytes(FONT, (), TextureSettings::new())
+            .expect("Could not load font"),
+        game,
+        highscores: HighScores::load(),
+        leaderboard: Leaderboard::init(config.leaderboard_url.clone()),
+        discord: Discord::init(config.discord_client_id.clone()),
+        show_online: false,
+        achievements: Achievements::load(),
+        stats: Stats::load(),
+        telemetry: Telemetry::init(config.telemetry),
+        campaign: Campaign::load(),
+        snake_colors,
+        in_campaign: false,
+        ghost: None,
+        screenshot: false,
+        clip: Clip::default(),
+        diagnostics: Diagnostics::init(),
+        tutorial: None,
+        spectator: None,
+        time_scale: TimeScale::new(),
+        practice: None,
+        metrics: RunMetrics::new(0),
+        attract: Attract::new(),
+        daily: None,
+        toasts: Vec::new(),
+        audio,
+        user_assets,
+        themes,
+        sprites: Sprites::load(SPRITES_PATH),
+        name_entry: None,
+        state: GameState::Menu,
+        menu,
+        controls: Menu::new(App::controls_items(&config.keys, &locale)),
+        rebinding: None,
+        sticks: Sticks::default(),
+        settings,
+        theme,
+        board_style: config.board_style,
+        fullscreen: config.fullscreen,
+        window_size: Layout::window_size(
+            config.rules.board.columns,
+            config.rules.board.rows,
+            config.cell_size,
+        ),
+        scale: 1.0,
+        level_spec: config
+            .rules
+            .level
+            .clone()
+            .unwrap_or_else(|| String::from("1")),
+        config_watcher: ConfigWatcher::new(CONFIG_FILE),
+        resize_pending: false,
+        previous: Vec::new(),
+        popups: Vec::new(),
+        particles: Particles::default(),
+        feedback: Feedback::default(),
+        net: None,
+        net_pending: VecDeque::new(),
+        bot: None,
+        tick_accumulator: 0.0,
+        autosave_timer: AUTOSAVE_SECONDS,
+        autosaved: false,
+        cursor: [0.0, 0.0],
+        dragging: false,
+        held: Vec::new(),
+        countdown: 0.0,
+        locale,
+        config,
+    }
+}
+
+// Frame and update rate on screens where nothing moves.
+const IDLE_FPS: u64 = 10;
+
+// How hard the event loop runs, from `App::pace`.
+#[derive(Clone, Copy, PartialEq)]
+enum Pace {
+    Full,
+    // A still screen still waiting on the network, a clip, the overlay or
+    // the main menu's idle timer.
+    Idle,
+    // A still screen; no updates or frames until the next input event.
+    Asleep,
+}
+
+fn event_settings(config: &Config, pace: Pace) -> EventSettings {
+    let max_fps = match config.max_fps {
+        0 => u64::MAX,
+        fps => fps,
+    };
+    let settings = EventSettings::new().ups(config.tick_rate).max_fps(max_fps);
+    match pace {
+        Pace::Full => settings,
+        Pace::Idle => settings.ups(IDLE_FPS).max_fps(IDLE_FPS.min(max_fps)),
+        Pace::Asleep => settings
+            .ups(IDLE_FPS)
+            .max_fps(IDLE_FPS.min(max_fps))
+            .lazy(true),
     }
 }
 
-fn game_loop(game: &mut Game, window: &mut GlutinWindow) {
-    let mut events = Events::new(EventSettings::new()).ups(UPDATE_SPEED);
+fn game_loop(app: &mut App, window: &mut GlutinWindow) {
+    let mut settings = event_settings(&app.config, app.pace());
+    let mut events = Events::new(settings);
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = gamepad::Gamepads::init();
     while let Some(e) = events.next(window) {
+        #[cfg(feature = "gamepad")]
+        for input in gamepads.poll() {
+            app.gamepad(input);
+        }
+
         if let Some(r) = e.render_args() {
-            game.render(&r);
+            app.render(&r);
+        }
+
+        if let Some(focused) = e.focus_args() {
+            app.focus_changed(focused);
+        }
+
+        if let Some(r) = e.resize_args() {
+            let scale = f64::from(r.draw_size[0]) / r.window_size[0].max(1.0);
+            app.resized(r.window_size, scale);
+        }
+
+        if let Some(a) = e.controller_axis_args() {
+            if let Some(input) = app.sticks.axis(&a) {
+                app.gamepad(input);
+            }
+        }
+
+        if let Some(u) = e.update_args() {
+            app.update(&u);
+        }
+
+        if let Some(position) = e.mouse_cursor_args() {
+            app.cursor_moved(position);
         }
 
-        if let Some(_u) = e.update_args() {
-            game.update();
+        if let Some(t) = e.text_args() {
+            app.typed(&t);
         }
 
         if let Some(k) = e.button_args() {
-            if k.state == ButtonState::Press {
-                game.pressed(&k.button)
+            if k.state == ButtonState::Press && k.button == Button::Keyboard(Key::F11) {
+                app.fullscreen = !app.fullscreen;
+                set_fullscreen(window, app.fullscreen);
+            } else if k.state == ButtonState::Press {
+                app.pressed(&k.button);
+                if let Button::Keyboard(key) = k.button {
+                    if !app.held.contains(&key) {
+                        app.held.push(key);
+                    }
+                }
+            } else if let Button::Keyboard(key) = k.button {
+                app.held.retain(|&held| held != key);
+            } else if k.button == Button::Mouse(MouseButton::Left) {
+                app.dragging = false;
             }
         }
+
+        let title = app.title();
+        if title != window.get_title() {
+            window.set_title(title);
+        }
+
+        // Changed from Settings, by reloading the config file or by moving
+        // between a still screen and a running game.
+        let changed = event_settings(&app.config, app.pace());
+        if (changed.ups, changed.max_fps, changed.lazy)
+            != (settings.ups, settings.max_fps, settings.lazy)
+        {
+            settings = changed;
+            events.set_event_settings(settings);
+        }
     }
 }
 
-struct Game {
+fn set_fullscreen(window: &GlutinWindow, fullscreen: bool) {
+    let window = window.ctx.window();
+    let monitor = if fullscreen {
+        Some(window.get_current_monitor())
+    } else {
+        None
+    };
+    window.set_fullscreen(monitor);
+}
+
+struct App {
     gl: GlGraphics,
-    snake: Snake,
-    food: BodyPart,
+    glyphs: GlyphCache<'static>,
+    game: Game,
+    highscores: HighScores,
+    leaderboard: Leaderboard,
+    discord: Discord,
+    show_online: bool,
+    achievements: Achievements,
+    stats: Stats,
+    telemetry: Telemetry,
+    campaign: Campaign,
+    snake_colors: SnakeColors,
+    in_campaign: bool,
+    // The personal best in this mode, replayed alongside the live game.
+    ghost: Option<Game>,
+    diagnostics: Diagnostics,
+    // Set by F12; the next frame is saved once it has been drawn.
+    screenshot: bool,
+    clip: Clip,
+    tutorial: Option<Tutorial>,
+    // Set from Watch AI until the game after the player takes over.
+    spectator: Option<Spectator>,
+    // Slow motion or fast forward for the player's own games, from +/-.
+    time_scale: TimeScale,
+    // Recent states of a practice game, for rewinding after a death.
+    practice: Option<Rewind>,
+    // How the local snake's run is going, for the game-over summary.
+    metrics: RunMetrics,
+    // A demo game behind the main menu once it has been left alone.
+    attract: Attract,
+    daily: Option<Daily>,
+    toasts: Vec<Toast>,
+    audio: Audio,
+    user_assets: UserAssets,
+    // The built-in themes and the user's, in the order Settings cycles them.
+    themes: Vec<Theme>,
+    sprites: Option<Sprites>,
+    theme: Theme,
+    window_size: [f64; 2],
+    scale: f64,
+    fullscreen: bool,
+    board_style: BoardStyle,
+    name_entry: Option<String>,
+    state: GameState,
+    menu: Menu,
+    settings: Menu,
+    controls: Menu,
+    rebinding: Option<Action>,
+    sticks: Sticks,
+    level_spec: String,
+    config_watcher: ConfigWatcher,
+    // A new board size from the reloaded config, used from the next game.
+    resize_pending: bool,
+    previous: Vec<Vec<Pos>>,
+    popups: Vec<Popup>,
+    particles: Particles,
+    feedback: Feedback,
+    net: Option<Connection>,
+    net_pending: VecDeque<Direction>,
+    // A bot program steering the second snake, from --bot.
+    bot: Option<bot::Process>,
+    tick_accumulator: f64,
+    // Until the run is next written to the autosave, and whether this
+    // session has written it, so it's cleared when the run ends.
+    autosave_timer: f64,
+    autosaved: bool,
+    // The mouse position in the window, and whether the left button is held
+    // to keep steering towards it.
+    cursor: [f64; 2],
+    dragging: bool,
+    // Keyboard keys held down, oldest first, for diagonal chords.
+    held: Vec<Key>,
+    // Seconds left before the snakes start moving; turns pressed meanwhile
+    // are queued as usual.
+    countdown: f64,
+    locale: Locale,
+    config: Config,
 }
 
-impl Game {
+impl App {
     fn render(&mut self, arg: &RenderArgs) {
-        self.gl.draw(arg.viewport(), |_c, gl| {
-            graphics::clear(BACKGROUND_COLOR, gl)
-        });
-        self.snake.render(&mut self.gl, arg);
-        self.food.render(&mut self.gl, arg);
-    }
+        self.diagnostics.frame();
+        self.telemetry.frame(self.state == GameState::Playing);
+        // The HUD sits on the background above the board's border.
+        let background = self.theme.background;
+        self.gl
+            .draw(arg.viewport(), |_c, gl| graphics::clear(background, gl));
 
-    fn update(&mut self) {
-        if !self.is_end() {
-            if self.snake.check_eat(&self.food) {
-                self.snake.grow();
-                self.place_food();
+        match self.state {
+            GameState::Menu => {
+                if let Some(demo) = self.attract.demo.take() {
+                    self.render_demo(arg, &demo);
+                    self.attract.demo = Some(demo);
+                    self.render_overlay(arg);
+                }
+                self.render_menu(arg);
+            }
+            GameState::Resume => {
+                self.render_menu(arg);
+                self.render_resume(arg);
+            }
+            GameState::Settings => self.render_settings(arg),
+            GameState::Controls => self.render_controls(arg),
+            GameState::Achievements => self.render_achievements(arg),
+            GameState::Stats => self.render_stats(arg),
+            GameState::Playing => {
+                self.render_board(arg);
+                if self.countdown > 0.0 {
+                    self.render_countdown(arg);
+                }
+            }
+            GameState::Paused => {
+                self.render_board(arg);
+                self.render_paused(arg);
+            }
+            GameState::GameOver => {
+                self.render_board(arg);
+                if !self.feedback.active() {
+                    self.render_game_over(arg);
+                }
+            }
+            GameState::LevelComplete => {
+                self.render_board(arg);
+                self.render_level_complete(arg);
+            }
+            GameState::Disconnected => {
+                self.render_board(arg);
+                self.render_disconnected(arg);
+            }
+            GameState::Failed(e) => {
+                self.render_board(arg);
+                self.render_failed(arg, e);
             }
-            self.snake.update_direction();
         }
+        if let Some(ref tutorial) = self.tutorial {
+            let prompt = tutorial.prompt(&self.config.keys, &self.locale);
+            let y = ui::FONT_SIZE as f64 + 5.0 + ui::LINE_HEIGHT;
+            self.render_text(arg, &prompt, 5.0, y);
+        }
+        if let Some(ref spectator) = self.spectator {
+            if spectator.watching {
+                let prompt = spectator.prompt(&self.config.keys, &self.locale);
+                let y = ui::FONT_SIZE as f64 + 5.0 + ui::LINE_HEIGHT;
+                self.render_text(arg, &prompt, 5.0, y);
+            }
+        }
+        if self.diagnostics.visible {
+            self.render_diagnostics(arg);
+        }
+        self.render_toasts(arg);
+        if self.screenshot {
+            self.screenshot = false;
+            match screenshot::capture(arg.draw_size) {
+                Ok(path) => {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let text = self.locale.format("toast.screenshot", &[("name", &name)]);
+                    self.toasts.push(Toast::new(text));
+                }
+                Err(e) => error!("Could not save screenshot: {}", e),
+            }
+        }
+        self.clip.record(arg.draw_size);
     }
 
-    fn place_food(&mut self) {
-        let mut free_space: Vec<(i32, i32)> = Vec::new();
-        for x in 0..GRID_COLUMNS {
-            for y in 0..GRID_ROWS {
-                if !self.snake.body.iter().any(|&p| p.x == x && p.y == y) {
-                    free_space.push((x, y));
+    fn render_board(&mut self, arg: &RenderArgs) {
+        let mut layout = Layout::fit(
+            self.window_size,
+            self.scale,
+            self.game.rules.board.columns,
+            self.game.rules.board.rows,
+        );
+        let offset = self.feedback.offset();
+        layout.origin[0] += offset[0] * layout.cell_size;
+        layout.origin[1] += offset[1] * layout.cell_size;
+        let progress = (self.tick_accumulator / self.game.tick_interval()).min(1.0);
+        let board = render::Board {
+            layout,
+            background: self.feedback.background(self.theme.background),
+            sprites: self.sprites.as_ref(),
+            grid_line: self.theme.grid_line,
+            style: self.board_style,
+            time: self.game.elapsed + progress * self.game.tick_interval(),
+        };
+        let size = self.game.rules.board;
+        let mut walls = self.theme.obstacle;
+        if self.game.rules.board_mode == BoardMode::Wrap {
+            walls[3] *= WRAP_BORDER_ALPHA;
+        }
+        board.border(&mut self.gl, arg, walls);
+        board.background(&mut self.gl, arg, size.columns, size.rows);
+        let (grid, closed) = ([size.columns, size.rows], self.game.closed_rings());
+        if let Some(seconds) = self.game.next_ring_in() {
+            let mut warning = ARENA_COLOR;
+            warning[3] *= (1.0 - seconds / RING_WARNING_SECONDS).max(0.0) as f32;
+            board.rings(&mut self.gl, arg, grid, closed + 1, warning);
+        }
+        board.rings(&mut self.gl, arg, grid, closed, ARENA_COLOR);
+        for obstacle in &self.game.level.obstacles {
+            let part = Pos {
+                x: obstacle.x,
+                y: obstacle.y,
+            };
+            let color = self.theme.obstacle;
+            board.cell(&mut self.gl, arg, part, Sprite::Obstacle, color);
+            if self.config.patterns {
+                board.mark(&mut self.gl, arg, part, Mark::Cross);
+            }
+        }
+        for (i, portal) in self.game.level.portals.iter().enumerate() {
+            for &(x, y) in &portal.ends {
+                board.portal(&mut self.gl, arg, x, y, Level::portal_color(i));
+            }
+        }
+        for hazard in self.game.hazards() {
+            board.hazard(&mut self.gl, arg, hazard, HAZARD_COLOR);
+        }
+        if let Some(ref ghost) = self.ghost {
+            let style = self.snake_style(0).faded(GHOST_ALPHA);
+            board.snake(&mut self.gl, arg, &ghost.players[0].snake, &[], 1.0, style);
+        }
+        for (i, player) in self.game.players.iter().enumerate() {
+            let mut style = self.snake_style(i);
+            if player.effects.ghost_active() {
+                style = style.faded(0.5);
+            }
+            if player.effects.invulnerable_active() {
+                let blink = player.effects.invulnerable / 3 % 2 == 0;
+                if !self.game.accessibility.flashes {
+                    style = style.faded(0.5);
+                } else if blink {
+                    style = style.faded(0.2);
+                }
+            }
+            let previous = self.previous.get(i).map_or(&[][..], |p| &p[..]);
+            board.snake(&mut self.gl, arg, &player.snake, previous, progress, style);
+        }
+        if self.config.edge_warning {
+            let mut color = EDGE_WARNING_COLOR;
+            if !self.game.accessibility.reduced_motion {
+                let pulse = (board.time * EDGE_WARNING_PULSE * std::f64::consts::TAU).sin();
+                color[3] *= (0.65 + 0.35 * pulse) as f32;
+            }
+            for i in self.human_players() {
+                if let Some(side) = self.game.edge_ahead(i) {
+                    board.edge(&mut self.gl, arg, grid, closed, side, color);
                 }
             }
         }
-        let mut rng = rand::thread_rng();
-        let pos = rng.gen_range(1, free_space.len());
-        self.food.x = free_space[pos - 1].0;
-        self.food.y = free_space[pos - 1].1;
+        self.particles.render(&mut self.gl, arg, &board.layout);
+        for food in &self.game.foods {
+            let mut food_color = food.kind.color(self.theme.food);
+            food_color[3] *= (0.25 + 0.75 * food.remaining()) as f32;
+            board.cell(&mut self.gl, arg, food.part, Sprite::Food, food_color);
+            if self.config.patterns {
+                board.mark(&mut self.gl, arg, food.part, Mark::Dot);
+            }
+        }
+        let layout = board.layout;
+        for popup in &self.popups {
+            let mut color = self.theme.text;
+            color[3] *= (1.0 - popup.progress()) as f32;
+            let rise = if self.game.accessibility.reduced_motion {
+                0.0
+            } else {
+                popup.progress()
+            };
+            let x = layout.origin[0] + f64::from(popup.part.x) * layout.cell_size;
+            let y = layout.origin[1] + (f64::from(popup.part.y) - rise) * layout.cell_size;
+            ui::text(
+                &mut self.gl,
+                &mut self.glyphs,
+                arg,
+                color,
+                &popup.text,
+                x,
+                y,
+            );
+        }
+        self.render_hud(arg);
     }
 
-    fn pressed(&mut self, btn: &Button) {
-        let last_direction = self.snake.dir.clone();
+    fn render_menu(&mut self, arg: &RenderArgs) {
+        let top = arg.window_size[1] / 2.0 - 60.0;
+        let color = self.theme.text;
+        let title = self.locale.get("menu.title").to_string();
+        self.render_text(arg, &title, 40.0, top);
+        self.menu
+            .render(&mut self.gl, &mut self.glyphs, arg, color, 40.0, top + 30.0);
+    }
 
-        self.snake.dir = match btn {
-            &Button::Keyboard(Key::Up) if last_direction != Direction::Down => Direction::Up,
-            &Button::Keyboard(Key::Down) if last_direction != Direction::Up => Direction::Down,
-            &Button::Keyboard(Key::Left) if last_direction != Direction::Right => Direction::Left,
-            &Button::Keyboard(Key::Right) if last_direction != Direction::Left => Direction::Right,
-            _ => last_direction,
+    // The attract mode game, drawn plainly under the menu.
+    fn render_demo(&mut self, arg: &RenderArgs, demo: &Game) {
+        let size = demo.rules.board;
+        let board = render::Board {
+            layout: Layout::fit(self.window_size, self.scale, size.columns, size.rows),
+            background: self.theme.background,
+            sprites: self.sprites.as_ref(),
+            grid_line: self.theme.grid_line,
+            style: self.board_style,
+            time: demo.elapsed,
         };
-
-        if btn == &Button::Keyboard(Key::Space) && self.is_end() {
-            self.restart();
+        let mut walls = self.theme.obstacle;
+        if demo.rules.board_mode == BoardMode::Wrap {
+            walls[3] *= WRAP_BORDER_ALPHA;
+        }
+        board.border(&mut self.gl, arg, walls);
+        board.background(&mut self.gl, arg, size.columns, size.rows);
+        for obstacle in &demo.level.obstacles {
+            let part = Pos::new(obstacle.x, obstacle.y);
+            board.cell(
+                &mut self.gl,
+                arg,
+                part,
+                Sprite::Obstacle,
+                self.theme.obstacle,
+            );
+        }
+        for hazard in demo.hazards() {
+            board.hazard(&mut self.gl, arg, hazard, HAZARD_COLOR);
+        }
+        for (i, player) in demo.players.iter().enumerate() {
+            let style = self.snake_style(i);
+            board.snake(&mut self.gl, arg, &player.snake, &[], 1.0, style);
+        }
+        for food in &demo.foods {
+            let color = food.kind.color(self.theme.food);
+            board.cell(&mut self.gl, arg, food.part, Sprite::Food, color);
         }
     }
 
-    fn is_end(&mut self) -> bool {
-        self.snake.collision() || self.snake.out_of_bounds()
+    fn render_settings(&mut self, arg: &RenderArgs) {
+        let top = 40.0;
+        let color = self.theme.text;
+        let title = self.locale.get("settings.title").to_string();
+        self.render_text(arg, &title, 40.0, top);
+        self.settings
+            .render(&mut self.gl, &mut self.glyphs, arg, color, 40.0, top + 30.0);
+        self.render_preview(arg);
     }
 
-    fn restart(&mut self) {
-        self.snake = Snake::init();
-        self.place_food();
+    // A few cells in the top right corner drawn with the theme, board style,
+    // patterns and snake colors picked so far.
+    fn render_preview(&mut self, arg: &RenderArgs) {
+        let width = f64::from(PREVIEW_COLUMNS) * PREVIEW_CELL_SIZE;
+        let board = render::Board {
+            layout: Layout {
+                cell_size: PREVIEW_CELL_SIZE,
+                origin: [arg.window_size[0] - width - 20.0, 40.0],
+                size: [width, f64::from(PREVIEW_ROWS) * PREVIEW_CELL_SIZE],
+            },
+            background: self.theme.background,
+            sprites: self.sprites.as_ref(),
+            grid_line: self.theme.grid_line,
+            style: self.board_style,
+            time: 0.0,
+        };
+        board.background(&mut self.gl, arg, PREVIEW_COLUMNS, PREVIEW_ROWS);
+        let obstacle = Pos::new(6, 4);
+        board.cell(
+            &mut self.gl,
+            arg,
+            obstacle,
+            Sprite::Obstacle,
+            self.theme.obstacle,
+        );
+        let food = Pos::new(5, 1);
+        board.cell(&mut self.gl, arg, food, Sprite::Food, self.theme.food);
+        if self.config.patterns {
+            board.mark(&mut self.gl, arg, obstacle, Mark::Cross);
+            board.mark(&mut self.gl, arg, food, Mark::Dot);
+        }
+        let snakes = [
+            Snake::new(
+                vec![
+                    Pos::new(3, 1),
+                    Pos::new(2, 1),
+                    Pos::new(1, 1),
+                    Pos::new(1, 2),
+                ],
+                Direction::Right,
+            ),
+            Snake::new(
+                vec![Pos::new(2, 4), Pos::new(3, 4), Pos::new(4, 4)],
+                Direction::Left,
+            ),
+        ];
+        for (i, snake) in snakes.iter().enumerate() {
+            let style = self.snake_style(i);
+            board.snake(&mut self.gl, arg, snake, &[], 1.0, style);
+        }
     }
-}
 
-struct Snake {
-    body: LinkedList<BodyPart>,
-    dir: Direction,
-}
+    fn render_controls(&mut self, arg: &RenderArgs) {
+        let top = arg.window_size[1] / 2.0 - 90.0;
+        let color = self.theme.text;
+        let title = self.locale.get("controls.title").to_string();
+        self.render_text(arg, &title, 40.0, top);
+        self.controls
+            .render(&mut self.gl, &mut self.glyphs, arg, color, 40.0, top + 30.0);
+        if let Some(action) = self.rebinding {
+            let action = self.locale.get(action.key());
+            let prompt = self
+                .locale
+                .format("controls.prompt", &[("action", &action)]);
+            let y = top + 40.0 + (ACTIONS.len() + 1) as f64 * ui::LINE_HEIGHT;
+            self.render_text(arg, &prompt, 40.0, y);
+        }
+    }
 
-impl Snake {
-    fn render(&self, gl: &mut GlGraphics, args: &RenderArgs) {
-        self.body.iter().for_each(|part| part.render(gl, args));
+    fn render_achievements(&mut self, arg: &RenderArgs) {
+        let top = arg.window_size[1] / 2.0 - 60.0;
+        let title = self.locale.get("achievements.title").to_string();
+        self.render_text(arg, &title, 40.0, top);
+        for (i, &achievement) in ACHIEVEMENTS.iter().enumerate() {
+            let mark = if self.achievements.is_unlocked(achievement) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let y = top + 30.0 + 2.0 * i as f64 * ui::LINE_HEIGHT;
+            let name = format!("{} {}", mark, achievement.name(&self.locale));
+            let description = achievement.description(&self.locale);
+            self.render_text(arg, &name, 40.0, y);
+            self.render_text(arg, &description, 76.0, y + ui::LINE_HEIGHT);
+        }
     }
 
-    fn update_direction(&mut self) {
-        let mut new_head = (*self.body.front().expect("Snake has no body")).clone();
-        match self.dir {
-            Direction::Left => new_head.x -= 1,
-            Direction::Right => new_head.x += 1,
-            Direction::Up => new_head.y -= 1,
-            Direction::Down => new_head.y += 1,
+    fn render_stats(&mut self, arg: &RenderArgs) {
+        let stats = &self.stats;
+        let locale = &self.locale;
+        let entry = |name: &str, value: &dyn Display| {
+            locale.format("stats.entry", &[("name", &name), ("value", value)])
+        };
+        let mut lines = vec![
+            locale.format("stats.title", &[]),
+            locale.format("stats.games_played", &[("count", &stats.games_played)]),
+            locale.format("stats.food_eaten", &[("count", &stats.food_eaten)]),
+            locale.format("stats.play_time", &[("time", &ui::clock(stats.playtime))]),
+            String::new(),
+            locale.format("stats.best_length", &[]),
+        ];
+        for &(name, mode) in MODES.iter() {
+            let best = stats.best_length.get(name).copied().unwrap_or(0);
+            lines.push(entry(locale.get(mode.key()), &best));
         }
+        lines.push(String::new());
+        lines.push(locale.format("stats.deaths", &[]));
+        if stats.deaths.is_empty() {
+            lines.push(format!("  {}", locale.get("stats.none")));
+        }
+        for &(cause, count) in &stats.deaths {
+            lines.push(entry(locale.get(cause.key()), &count));
+        }
+        for (i, line) in lines.iter().enumerate() {
+            self.render_text(arg, line, 40.0, 40.0 + i as f64 * ui::LINE_HEIGHT);
+        }
+    }
 
-        self.body.push_front(new_head);
-        self.body.pop_back().unwrap();
+    // Right-aligned in the top corner, clear of the HUD.
+    fn render_diagnostics(&mut self, arg: &RenderArgs) {
+        let lines = self
+            .diagnostics
+            .lines(&self.game, self.particles.count(), &self.locale);
+        for (i, line) in lines.iter().enumerate() {
+            let width = self.glyphs.width(ui::FONT_SIZE, line).unwrap_or(0.0);
+            let x = arg.window_size[0] - width - 5.0;
+            let y = ui::FONT_SIZE as f64 + 5.0 + i as f64 * ui::LINE_HEIGHT;
+            self.render_text(arg, line, x, y);
+        }
     }
 
-    fn grow(&mut self) {
-        let mut new_tail = (*self.body.back().expect("Snake has no body")).clone();
-        new_tail.x += 1;
-        self.body.push_back(new_tail);
+    fn render_toasts(&mut self, arg: &RenderArgs) {
+        let bottom = arg.window_size[1] - 10.0;
+        for (i, toast) in self.toasts.iter().enumerate() {
+            let mut color = self.theme.text;
+            color[3] *= toast.alpha();
+            let y = bottom - i as f64 * ui::LINE_HEIGHT;
+            ui::text(
+                &mut self.gl,
+                &mut self.glyphs,
+                arg,
+                color,
+                &toast.text,
+                10.0,
+                y,
+            );
+        }
     }
 
-    fn check_eat(&mut self, food: &BodyPart) -> bool {
-        let head = *self.body.front().expect("Snake has no body");
-        head.x == food.x && head.y == food.y
+    fn menu_items(locale: &Locale) -> Vec<String> {
+        let mut items: Vec<String> = MODES
+            .iter()
+            .map(|&(_, mode)| locale.get(mode.key()).to_string())
+            .collect();
+        for key in &[
+            "menu.watch_ai",
+            "menu.settings",
+            "menu.achievements",
+            "menu.stats",
+            "menu.tutorial",
+        ] {
+            items.push(locale.get(key).to_string());
+        }
+        items
     }
 
-    fn collision(&mut self) -> bool {
-        let head = *self.body.front().expect("Snake has no body");
-        let mut body_without_head = self.body.clone();
-        body_without_head.pop_front();
-        body_without_head
+    fn controls_items(keys: &KeyMap, locale: &Locale) -> Vec<String> {
+        let mut items: Vec<String> = ACTIONS
             .iter()
-            .any(|&p| p.x == head.x && p.y == head.y)
+            .map(|&action| {
+                locale.format(
+                    "controls.binding",
+                    &[
+                        ("action", &locale.get(action.key())),
+                        ("key", &format!("{:?}", keys.key(action))),
+                    ],
+                )
+            })
+            .collect();
+        items.push(locale.get("back").to_string());
+        items
+    }
+
+    fn settings_items(
+        rules: &Rules,
+        theme: &Theme,
+        style: BoardStyle,
+        config: &Config,
+        colors: &SnakeColors,
+        locale: &Locale,
+    ) -> Vec<String> {
+        let fps = match config.max_fps {
+            0 => locale.get("settings.unlimited").to_string(),
+            fps => fps.to_string(),
+        };
+        let edges = match config.rules.board_mode {
+            BoardMode::Walls => "settings.walls",
+            BoardMode::Wrap => "settings.wrap",
+        };
+        let motion = if config.accessibility.reduced_motion {
+            "settings.reduced"
+        } else {
+            "settings.full"
+        };
+        let option = |name: &str, value: &dyn Display| {
+            locale.format(
+                "settings.option",
+                &[("name", &locale.get(name)), ("value", value)],
+            )
+        };
+        let color = |name: &str, color| option(name, &locale.get(color_key(color)));
+        let level = config
+            .rules
+            .level
+            .clone()
+            .unwrap_or_else(|| String::from("1"));
+        let sounds = match config.sound_pack {
+            Some(ref pack) => pack.clone(),
+            None => locale.get("settings.built_in").to_string(),
+        };
+        vec![
+            option("settings.speed", &locale.get(rules.speed_preset().key())),
+            option("settings.ai", &locale.get(rules.ai_difficulty.key())),
+            option("settings.food", &rules.food_count),
+            option("settings.edges", &locale.get(edges)),
+            option("settings.theme", &theme.name),
+            option("settings.board", &locale.get(style.key())),
+            option("settings.cells", &locale.get(config.zoom.key())),
+            option("settings.patterns", &locale.get(on_off(config.patterns))),
+            option("settings.motion", &locale.get(motion)),
+            option(
+                "settings.flashes",
+                &locale.get(on_off(config.accessibility.flashes)),
+            ),
+            option(
+                "settings.edge_warning",
+                &locale.get(on_off(config.edge_warning)),
+            ),
+            option("settings.fps", &fps),
+            option("settings.tick_rate", &config.tick_rate),
+            option("settings.volume", &format!("{:.0}%", config.volume * 100.0)),
+            color("settings.p1_body", colors.get(0, Part::Body)),
+            color("settings.p1_head", colors.get(0, Part::Head)),
+            color("settings.p2_body", colors.get(1, Part::Body)),
+            color("settings.p2_head", colors.get(1, Part::Head)),
+            option("settings.level", &level_name(&level)),
+            option("settings.sounds", &sounds),
+            locale.get("settings.controls").to_string(),
+            locale.get("back").to_string(),
+        ]
+    }
+
+    fn render_hud(&mut self, arg: &RenderArgs) {
+        let mut hud = String::new();
+        let game = &self.game;
+        let locale = &self.locale;
+        if game.rules.coop {
+            hud.push_str(&locale.format("hud.team", &[("score", &game.team_score())]));
+            hud.push(' ');
+            hud.push_str(&locale.format("hud.lives", &[("lives", &game.players[0].lives)]));
+            hud.push(' ');
+            hud.push_str(&locale.format(
+                "hud.team_length",
+                &[("length", &game.team_length()), ("target", &COOP_LENGTH)],
+            ));
+            hud.push_str("  ");
+        } else {
+            for (i, player) in game.players.iter().enumerate() {
+                hud.push_str(&self.score_label(i));
+                if player.score.multiplier() > 1 {
+                    hud.push_str(&format!(" x{}", player.score.multiplier()));
+                }
+                hud.push(' ');
+                hud.push_str(&locale.format("hud.lives", &[("lives", &player.lives)]));
+                if player.effects.slow_mo_active() {
+                    let seconds = f64::from(player.effects.slow_mo) * game.tick_interval();
+                    hud.push(' ');
+                    hud.push_str(&locale.format("hud.slow", &[("seconds", &seconds.ceil())]));
+                }
+                if player.effects.ghost_active() {
+                    let seconds = f64::from(player.effects.ghost) * game.tick_interval();
+                    hud.push(' ');
+                    hud.push_str(&locale.format("hud.ghost", &[("seconds", &seconds.ceil())]));
+                }
+                hud.push_str("  ");
+            }
+        }
+        hud.push_str(&locale.format("hud.speed", &[("speed", &game.speed())]));
+        hud.push_str("  ");
+        match game.time_left() {
+            Some(left) => hud.push_str(&locale.format("hud.left", &[("time", &ui::clock(left))])),
+            None => hud.push_str(&locale.format("hud.time", &[("time", &ui::clock(game.elapsed))])),
+        }
+        let watching = self.spectator.as_ref().is_some_and(|s| s.watching);
+        if self.time_scale() != 1.0 && !watching {
+            hud.push_str("  ");
+            let scale = self.time_scale();
+            hud.push_str(&locale.format("hud.time_scale", &[("scale", &scale)]));
+        }
+        if let Some(seconds) = game.next_ring_in() {
+            hud.push_str("  ");
+            hud.push_str(&locale.format("hud.closing", &[("seconds", &seconds.ceil())]));
+        }
+        if self.in_campaign {
+            let (current, target) = self.campaign.current().goal.progress(game);
+            hud.push_str("  ");
+            hud.push_str(&locale.format("hud.goal", &[("current", &current), ("target", &target)]));
+        }
+        if let Some(ref daily) = self.daily {
+            hud.push_str("  ");
+            hud.push_str(&locale.format("hud.daily", &[("date", &daily.date)]));
+        }
+        self.render_text(arg, &hud, 5.0, render::HUD_HEIGHT - 5.0);
     }
 
-    fn out_of_bounds(&mut self) -> bool {
-        let head = *self.body.front().expect("Snake has no body");
+    fn render_overlay(&mut self, arg: &RenderArgs) {
+        let size = arg.window_size;
+        self.gl.draw(arg.viewport(), |c, gl| {
+            graphics::rectangle(OVERLAY_COLOR, [0.0, 0.0, size[0], size[1]], c.transform, gl);
+        });
+    }
 
-        head.x < 0 || head.x > GRID_COLUMNS - 1 || head.y < 0 || head.y > GRID_ROWS - 1
+    fn render_countdown(&mut self, arg: &RenderArgs) {
+        self.render_overlay(arg);
+        let center = [arg.window_size[0] / 2.0, arg.window_size[1] / 2.0];
+        let count = self.countdown.ceil().to_string();
+        let ready = self.locale.get("countdown.ready").to_string();
+        self.render_text(arg, &ready, center[0] - 45.0, center[1] - 20.0);
+        self.render_text(arg, &count, center[0] - 5.0, center[1] + 10.0);
     }
 
-    fn init() -> Snake {
-        Snake {
-            body: LinkedList::from_iter(
-                (vec![BodyPart { x: 0, y: 0 }, BodyPart { x: 0, y: 1 }]).into_iter(),
-            ),
-            dir: Direction::Right,
+    fn render_paused(&mut self, arg: &RenderArgs) {
+        self.render_overlay(arg);
+        let center_y = arg.window_size[1] / 2.0;
+        let key = format!("{:?}", self.config.keys.pause);
+        let title = self.locale.format("paused.title", &[]);
+        let resume = self.locale.format("paused.resume", &[("key", &key)]);
+        self.render_text(arg, &title, 40.0, center_y - 20.0);
+        self.render_text(arg, &resume, 40.0, center_y + 10.0);
+    }
+
+    fn render_level_complete(&mut self, arg: &RenderArgs) {
+        self.render_overlay(arg);
+        let center_y = arg.window_size[1] / 2.0;
+        let locale = &self.locale;
+        if self.campaign.finished() {
+            let title = locale.format("campaign.complete", &[]);
+            let menu = locale.format("campaign.menu", &[]);
+            self.render_text(arg, &title, 40.0, center_y - 20.0);
+            self.render_text(arg, &menu, 40.0, center_y + 10.0);
+            return;
         }
+        let stage = self.campaign.current();
+        let title = locale.format("campaign.level_complete", &[]);
+        let next = locale.format(
+            "campaign.next",
+            &[
+                ("level", &(self.campaign.stage + 1)),
+                ("count", &STAGES.len()),
+                ("goal", &stage.goal.describe(locale)),
+            ],
+        );
+        let proceed = locale.format("campaign.continue", &[]);
+        self.render_text(arg, &title, 40.0, center_y - 20.0);
+        self.render_text(arg, &next, 40.0, center_y + 10.0);
+        self.render_text(arg, &proceed, 40.0, center_y + 40.0);
     }
-}
 
-#[derive(Clone, Copy)]
-struct BodyPart {
-    x: i32,
-    y: i32,
-}
+    fn render_disconnected(&mut self, arg: &RenderArgs) {
+        self.render_overlay(arg);
+        let center_y = arg.window_size[1] / 2.0;
+        let title = self.locale.format("disconnected.title", &[]);
+        let quit = self.locale.format("disconnected.quit", &[]);
+        self.render_text(arg, &title, 40.0, center_y - 20.0);
+        self.render_text(arg, &quit, 40.0, center_y + 10.0);
+    }
+
+    fn render_resume(&mut self, arg: &RenderArgs) {
+        self.render_overlay(arg);
+        let center_y = arg.window_size[1] / 2.0;
+        let title = self.locale.format("resume.title", &[]);
+        let keys = self.locale.format("resume.keys", &[]);
+        self.render_text(arg, &title, 40.0, center_y - 20.0);
+        self.render_text(arg, &keys, 40.0, center_y + 10.0);
+    }
 
-impl BodyPart {
-    fn square(&self) -> graphics::types::Rectangle {
-        graphics::rectangle::square(
-            (self.x * BODY_SIZE) as f64,
-            (self.y * BODY_SIZE) as f64,
-            BODY_SIZE as f64,
-        )
+    fn render_failed(&mut self, arg: &RenderArgs, error: SnakeError) {
+        self.render_overlay(arg);
+        let center_y = arg.window_size[1] / 2.0;
+        let key = format!("{:?}", self.config.keys.restart);
+        let title = self.locale.format("failed.title", &[]);
+        let restart = self.locale.format("failed.restart", &[("key", &key)]);
+        let menu = self.locale.format("failed.menu", &[]);
+        let message = self.locale.format(error.key(), &[]);
+        self.render_text(arg, &title, 40.0, center_y - 40.0);
+        self.render_text(arg, &message, 40.0, center_y - 10.0);
+        self.render_text(arg, &restart, 40.0, center_y + 20.0);
+        self.render_text(arg, &menu, 40.0, center_y + 50.0);
     }
 
-    fn render(&self, gl: &mut GlGraphics, args: &RenderArgs) {
-        let square = self.square();
+    fn render_game_over(&mut self, arg: &RenderArgs) {
+        self.render_overlay(arg);
+
+        let mut lines = Vec::new();
+        let players = &self.game.players;
+        let locale = &self.locale;
+        if self.game.rules.coop {
+            if self.game.won {
+                lines.push(locale.format("over.you_win", &[]));
+                lines.push(locale.format("over.team_won", &[("length", &COOP_LENGTH)]));
+            } else {
+                lines.push(locale.format("over.game_over", &[]));
+            }
+            for (i, player) in players.iter().enumerate() {
+                if let Some(cause) = player.death {
+                    let name = player.name(i, locale);
+                    lines.push(locale.format(
+                        "over.player_death",
+                        &[
+                            ("name", &name),
+                            ("score", &player.score.points),
+                            ("cause", &locale.get(cause.key())),
+                        ],
+                    ));
+                }
+            }
+            lines.push(locale.format(
+                "over.team_score",
+                &[
+                    ("score", &self.game.team_score()),
+                    ("length", &self.game.team_length()),
+                ],
+            ));
+        } else if players.len() > 1 {
+            let survivors: Vec<usize> =
+                (0..players.len()).filter(|&i| players[i].alive()).collect();
+            match survivors.as_slice() {
+                _ if self.game.won => lines.push(locale.format("over.board_full", &[])),
+                _ if self.game.time_up() => lines.push(locale.format("over.time_up", &[])),
+                [winner] => {
+                    let name = players[*winner].name(*winner, locale);
+                    lines.push(locale.format("over.wins", &[("name", &name)]));
+                }
+                _ => lines.push(locale.format("over.draw", &[])),
+            }
+            for (i, player) in players.iter().enumerate() {
+                let line = match player.death {
+                    Some(cause) => locale.format(
+                        "over.player_death",
+                        &[
+                            ("name", &player.name(i, locale)),
+                            ("score", &player.score.points),
+                            ("cause", &locale.get(cause.key())),
+                        ],
+                    ),
+                    None => self.score_label(i),
+                };
+                lines.push(line);
+            }
+        } else {
+            let player = &players[0];
+            if self.game.won {
+                lines.push(locale.format("over.you_win", &[]));
+                lines.push(locale.format("over.filled", &[]));
+            } else if self.game.time_up() {
+                lines.push(locale.format("over.time_up_title", &[]));
+            } else {
+                lines.push(locale.format("over.game_over", &[]));
+            }
+            if let Some(cause) = player.death {
+                lines.push(locale.format(cause.key(), &[]));
+            }
+            lines.push(locale.format(
+                "over.score_length",
+                &[
+                    ("score", &player.score.points),
+                    ("length", &player.snake.len()),
+                ],
+            ));
+            lines.push(locale.format(
+                "over.food_time",
+                &[
+                    ("food", &self.game.foods_eaten),
+                    ("time", &ui::clock(self.game.elapsed)),
+                ],
+            ));
+            lines.push(locale.format(
+                "over.combo_speed",
+                &[
+                    ("combo", &self.metrics.max_combo),
+                    ("speed", &format!("{:.1}", self.metrics.average_speed())),
+                ],
+            ));
+            lines.push(String::new());
+            if self.show_online {
+                self.online_lines(&mut lines);
+            } else {
+                let daily = self.daily.as_ref().map(|daily| daily.date.as_str());
+                match daily {
+                    Some(date) => {
+                        lines.push(locale.format("over.daily", &[("date", &date)]));
+                        lines.push(daily::describe(&self.game.rules, locale));
+                    }
+                    None => lines.push(locale.format("over.high_scores", &[])),
+                }
+                for (i, entry) in self.highscores.table(daily).iter().enumerate() {
+                    let preset = entry.preset.map_or("", |preset| locale.get(preset.key()));
+                    let perfect = if entry.perfect {
+                        locale.get("over.perfect")
+                    } else {
+                        ""
+                    };
+                    lines.push(format!(
+                        "{} {} {}",
+                        score_line(i, &entry.name, entry.score),
+                        preset,
+                        perfect
+                    ));
+                }
+            }
+        }
+        lines.push(String::new());
+        if self.game.playback.is_some() {
+            lines.push(locale.format("over.replay_finished", &[]));
+        }
+        if self.practice.is_some() {
+            lines.push(locale.format("over.rewind", &[("ticks", &REWIND_TICKS)]));
+        }
+        lines.push(locale.format("seed", &[("seed", &self.game.seed)]));
+        match self.name_entry {
+            Some(ref name) => {
+                lines.push(locale.format("over.new_high_score", &[]));
+                lines.push(format!("{}_", name));
+            }
+            None if self.net.as_ref().is_some_and(|net| net.restart_requested) => {
+                lines.push(locale.format("over.waiting", &[]));
+            }
+            None => {
+                let key = format!("{:?}", self.config.keys.restart);
+                lines.push(locale.format("over.restart", &[("key", &key)]));
+            }
+        }
+        if self.leaderboard.enabled() && players.len() == 1 && self.name_entry.is_none() {
+            let other = if self.show_online {
+                "over.show_local"
+            } else {
+                "over.show_online"
+            };
+            lines.push(locale.format(other, &[]));
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            self.render_text(arg, line, 20.0, 40.0 + i as f64 * ui::LINE_HEIGHT);
+        }
+        if self.game.players.len() == 1 && self.metrics.lengths().len() > 1 {
+            let rect = [
+                arg.window_size[0] - GRAPH_SIZE[0] - 20.0,
+                40.0 + ui::LINE_HEIGHT,
+                GRAPH_SIZE[0],
+                GRAPH_SIZE[1],
+            ];
+            let title = self.locale.format("over.length_graph", &[]);
+            self.render_text(arg, &title, rect[0], 40.0);
+            let color = self.theme.text;
+            ui::graph(&mut self.gl, arg, rect, self.metrics.lengths(), color);
+        }
+    }
 
-        gl.draw(args.viewport(), |c, gl| {
-            let transform = c.transform;
-            graphics::rectangle(RED, square, transform, gl);
+    // The online table in two columns of ten so it fits in the default window.
+    fn online_lines(&self, lines: &mut Vec<String>) {
+        let entries = &self.leaderboard.entries;
+        let locale = &self.locale;
+        lines.push(match self.leaderboard.status {
+            Status::Idle | Status::Pending => locale.format("online.submitting", &[]),
+            Status::Failed => locale.format("online.unavailable", &[]),
+            Status::Ready => locale.format("online.top", &[("count", &leaderboard::TOP_ENTRIES)]),
         });
+        let half = (leaderboard::TOP_ENTRIES / 2).min(entries.len());
+        for i in 0..half {
+            let mut line = score_line(i, &entries[i].name, entries[i].score);
+            if let Some(entry) = entries.get(i + half) {
+                line.push_str("   ");
+                line.push_str(&score_line(i + half, &entry.name, entry.score));
+            }
+            lines.push(line);
+        }
     }
-}
 
-#[derive(Clone, PartialEq)]
-enum Direction {
-    Right,
-    Left,
-    Up,
-    Down,
+    // "Snake — Score: 42 — Paused" while a game is on, plain "Snake" in the
+    // menus.
+    fn title(&self) -> String {
+        let status = match self.state {
+            GameState::Playing => "",
+            GameState::Paused => "state.paused",
+            GameState::GameOver => "state.game_over",
+            GameState::LevelComplete => "state.level_complete",
+            GameState::Disconnected => "state.disconnected",
+            GameState::Failed(_) => "state.error",
+            _ => return self.locale.get("title").to_string(),
+        };
+        let mut title = self.locale.get("title").to_string();
+        for i in 0..self.game.players.len() {
+            title.push_str(" — ");
+            title.push_str(&self.score_label(i));
+        }
+        if !status.is_empty() {
+            title.push_str(" — ");
+            title.push_str(self.locale.get(status));
+        }
+        title
+    }
+
+    // "Score: 42" alone, or with the player's name when there are several.
+    fn score_label(&self, index: usize) -> String {
+        let players = &self.game.players;
+        let score = players[index].score.points;
+        if players.len() == 1 {
+            self.locale.format("hud.score", &[("score", &score)])
+        } else {
+            let name = players[index].name(index, &self.locale);
+            self.locale
+                .format("hud.player_score", &[("name", &name), ("score", &score)])
+        }
+    }
+
+    fn pace(&self) -> Pace {
+        let still = matches!(
+            self.state,
+            GameState::Menu
+                | GameState::Settings
+                | GameState::Controls
+                | GameState::Achievements
+                | GameState::Stats
+                | GameState::Resume
+                | GameState::Paused
+                | GameState::GameOver
+                | GameState::LevelComplete
+                | GameState::Failed(_)
+        );
+        let animating = !self.toasts.is_empty()
+            || !self.popups.is_empty()
+            || self.particles.count() > 0
+            || self.feedback.active();
+        if !still || animating || self.attract.demo.is_some() {
+            Pace::Full
+        } else if self.state == GameState::Menu
+            || self.net.is_some()
+            || self.leaderboard.status == Status::Pending
+            || self.clip.saving()
+            || self.diagnostics.visible
+        {
+            Pace::Idle
+        } else {
+            Pace::Asleep
+        }
+    }
+
+    fn render_text(&mut self, arg: &RenderArgs, text: &str, x: f64, y: f64) {
+        let color = self.theme.text;
+        ui::text(&mut self.gl, &mut self.glyphs, arg, color, text, x, y);
+    }
+
+    fn update(&mut self, args: &UpdateArgs) {
+        self.diagnostics.update();
+        self.popups.retain_mut(|popup| popup.update(args.dt));
+        self.toasts.retain_mut(|toast| toast.update(args.dt));
+        self.particles.update(args.dt);
+        self.autosave(args.dt);
+        if self.state == GameState::GameOver && self.game.filled {
+            let colors = [
+                self.theme.head,
+                self.theme.snake,
+                self.theme.food,
+                FoodKind::Golden.color(self.theme.food),
+            ];
+            let options = self.game.accessibility;
+            self.particles
+                .fireworks(&options, self.game.rules.board, &colors, args.dt);
+        }
+        self.feedback.update(args.dt);
+        self.leaderboard.poll();
+        if self.state == GameState::Menu {
+            self.attract.update(args.dt, &self.config);
+            self.discord.set(None);
+        } else {
+            self.attract.wake();
+        }
+        if let Some(config) = self.config_watcher.poll(args.dt) {
+            self.reload_config(config);
+        }
+        match self.clip.poll() {
+            Some(Ok(path)) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let text = self.locale.format("toast.clip", &[("name", &name)]);
+                self.toasts.push(Toast::new(text));
+            }
+            Some(Err(e)) => error!("Could not save clip: {}", e),
+            None => {}
+        }
+        if let Some(ref mut net) = self.net {
+            net.poll();
+            if net.disconnected {
+                self.state = GameState::Disconnected;
+            } else if matches!(self.state, GameState::GameOver | GameState::Failed(_)) {
+                if let Some(seed) = net.next_game() {
+                    self.game.rules.seed = Some(seed);
+                    self.net_pending.clear();
+                    self.restart();
+                }
+            }
+        }
+        if self.state != GameState::Playing {
+            return;
+        }
+        if self.countdown > 0.0 {
+            self.countdown -= args.dt;
+            return;
+        }
+
+        let speed = match self.spectator {
+            Some(ref spectator) if spectator.watching => spectator.speed(),
+            _ => self.time_scale(),
+        };
+        self.tick_accumulator += args.dt * speed;
+        while self.state == GameState::Playing && self.tick_accumulator >= self.game.tick_interval()
+        {
+            if let Some(ref mut net) = self.net {
+                match net.inputs(self.game.tick, &mut self.net_pending) {
+                    Some(inputs) => {
+                        for (player, dir) in self.game.players.iter_mut().zip(inputs.iter()) {
+                            if let Some(dir) = *dir {
+                                player.steer(dir);
+                            }
+                        }
+                    }
+                    None => {
+                        // Waiting on the other side; don't pile up ticks to
+                        // fast-forward through once its input arrives.
+                        self.tick_accumulator =
+                            self.tick_accumulator.min(self.game.tick_interval());
+                        break;
+                    }
+                }
+            }
+            if let Some(ref mut bot) = self.bot {
+                if self.game.players.len() > 1 {
+                    let dir = bot.next_move(&BoardView::new(&self.game, 1));
+                    self.game.players[1].steer(dir);
+                }
+                if let Some(e) = bot.error.take() {
+                    error!("{}; the second snake is yours now", e);
+                    self.bot = None;
+                }
+            }
+            self.tick_accumulator -= self.game.tick_interval();
+            self.previous = self
+                .game
+                .players
+                .iter()
+                .map(|p| p.snake.segments().collect())
+                .collect();
+            if let Some(ref mut practice) = self.practice {
+                practice.record(&self.game);
+            }
+            let started = Instant::now();
+            let result = self.game.step(None);
+            self.diagnostics.tick(started.elapsed());
+            if let (Some(ref mut net), true) = (&mut self.net, self.config.checksums) {
+                net.checksum(self.game.tick, self.game.checksum());
+                if let Some(tick) = net.desynced.take() {
+                    error!("Out of step with the other player from tick {}", tick);
+                    let text = self
+                        .locale
+                        .format("toast.desynced", &[("tick", &tick.to_string())]);
+                    self.toasts.push(Toast::new(text));
+                }
+            }
+            self.metrics.observe(&self.game);
+            self.step_ghost();
+            let humans = self.human_players();
+            let mut scored = false;
+            for event in self.game.events.clone() {
+                scored |= matches!(
+                    event,
+                    GameEvent::FoodEaten { .. }
+                        | GameEvent::Poisoned { .. }
+                        | GameEvent::SnakeDied { .. }
+                );
+                self.handle_event(event, &humans);
+            }
+            if self.game.playback.is_none() {
+                for achievement in self.achievements.update(&self.game, &humans, result) {
+                    let name = achievement.name(&self.locale);
+                    let text = self
+                        .locale
+                        .format("achievements.unlocked", &[("name", &name)]);
+                    self.toasts.push(Toast::new(text));
+                }
+            }
+            match result {
+                TickResult::Died(_) | TickResult::Won => self.game_over(),
+                TickResult::TimeUp => {
+                    self.audio.play(Sound::PowerUp);
+                    self.game_over();
+                }
+                TickResult::Failed(e) => {
+                    error!("The game stopped at tick {}: {}", self.game.tick, e);
+                    self.state = GameState::Failed(e);
+                }
+                _ if self.in_campaign && self.campaign.current().goal.reached(&self.game) => {
+                    self.level_complete()
+                }
+                _ => {}
+            }
+            if scored || self.state != GameState::Playing {
+                self.update_presence();
+            }
+        }
+    }
+
+    // Applies a config file saved while the game runs. Colors, keys and
+    // speed change at once; a new board size waits for the next game. Modes
+    // with rules of their own and network games keep their speed.
+    fn reload_config(&mut self, config: Config) {
+        let resized = config.rules.board != self.config.rules.board;
+        self.theme = config.theme(&self.themes);
+        let new_pack = config.sound_pack != self.config.sound_pack;
+        self.board_style = config.board_style;
+        self.game.accessibility = config.accessibility;
+        self.game.record_checksums = config.checksums;
+        if config.language != self.config.language {
+            self.locale = config.locale();
+            self.menu.items = App::menu_items(&self.locale);
+        }
+        self.controls.items = App::controls_items(&config.keys, &self.locale);
+        let own_rules = self.in_campaign || self.daily.is_some() || self.tutorial.is_some();
+        if !own_rules && self.net.is_none() && self.game.playback.is_none() {
+            let rules = &mut self.game.rules;
+            rules.update_speed = config.rules.update_speed;
+            rules.max_speed = config.rules.max_speed;
+            rules.speed_up_every = config.rules.speed_up_every;
+        }
+        self.resize_pending |= resized;
+        self.config = config;
+        if new_pack {
+            self.use_sound_pack();
+        }
+        self.settings.items = App::settings_items(
+            &self.game.rules,
+            &self.theme,
+            self.board_style,
+            &self.config,
+            &self.snake_colors,
+            &self.locale,
+        );
+        let text = if resized {
+            "toast.reloaded_resize"
+        } else {
+            "toast.reloaded"
+        };
+        self.toasts.push(Toast::new(self.locale.format(text, &[])));
+    }
+
+    fn handle_event(&mut self, event: GameEvent, humans: &[usize]) {
+        debug!("Tick {}: {:?}", self.game.tick, event);
+        match event {
+            GameEvent::FoodEaten {
+                player,
+                part,
+                kind,
+                points,
+            } => {
+                let multiplier = self.game.players[player].score.multiplier();
+                let text = match multiplier {
+                    1 => format!("+{}", points),
+                    _ => format!("+{} x{}", points, multiplier),
+                };
+                self.popups.push(Popup::new(part, text));
+                let color = kind.color(self.theme.food);
+                let options = self.game.accessibility;
+                self.particles.burst(&options, part, color, 12, 3.0);
+                match kind {
+                    FoodKind::Normal => self.audio.play(Sound::Eat),
+                    FoodKind::Golden => self.audio.play(Sound::PowerUp),
+                    _ => {}
+                }
+                if self.game.playback.is_none() && humans.contains(&player) {
+                    self.stats.ate();
+                }
+                self.tutorial_ate();
+            }
+            GameEvent::Poisoned { part, penalty, .. } => {
+                self.popups.push(Popup::new(part, format!("-{}", penalty)));
+                let color = FoodKind::Poison.color(self.theme.food);
+                let options = self.game.accessibility;
+                self.particles.burst(&options, part, color, 12, 3.0);
+                self.audio.play(Sound::Death);
+            }
+            GameEvent::PowerUpActivated { .. } | GameEvent::LevelCompleted => {
+                self.audio.play(Sound::PowerUp);
+            }
+            GameEvent::SnakeDied { player, part, .. } => {
+                if player == 0 {
+                    self.telemetry.clear_turns();
+                }
+                let options = self.game.accessibility;
+                self.particles.burst(&options, part, CRASH_COLOR, 40, 6.0);
+                self.feedback.crash(&options);
+                self.audio.play(Sound::Death);
+            }
+            GameEvent::ArenaShrank { .. } => {
+                let options = self.game.accessibility;
+                self.feedback.rumble(&options);
+            }
+            GameEvent::DirectionChanged { player: 0, .. } => self.telemetry.turned(),
+            GameEvent::DirectionChanged { .. } => {}
+            GameEvent::Diverged { tick } => {
+                error!("The replay no longer matches the game from tick {}", tick);
+                let text = self
+                    .locale
+                    .format("toast.diverged", &[("tick", &tick.to_string())]);
+                self.toasts.push(Toast::new(text));
+            }
+        }
+    }
+
+    // The players steered from this machine, who can earn achievements.
+    // Network games stay at normal pace so both sides keep in step.
+    fn time_scale(&self) -> f64 {
+        if self.net.is_some() {
+            1.0
+        } else {
+            self.time_scale.factor()
+        }
+    }
+
+    fn human_players(&self) -> Vec<usize> {
+        if self.spectator.is_some() || self.practice.is_some() {
+            return Vec::new();
+        }
+        match self.net {
+            Some(ref net) => vec![net.local],
+            None => (0..self.game.players.len())
+                .filter(|&i| self.game.players[i].ai.is_none())
+                .filter(|&i| self.bot.is_none() || i == 0)
+                .collect(),
+        }
+    }
+
+    fn game_over(&mut self) {
+        self.state = GameState::GameOver;
+        if let Some(ref mut tutorial) = self.tutorial {
+            if tutorial.step == Step::Crash {
+                tutorial.step = Step::Restart;
+            }
+            return;
+        }
+        // A game the AI played doesn't count, even after taking it over, and
+        // neither does one that can be rewound.
+        if self.spectator.is_some() || self.practice.is_some() {
+            return;
+        }
+        if self.game.playback.is_none() {
+            self.save_recording();
+            self.record_stats();
+        }
+        if self.game.playback.is_some() || self.game.players.len() != 1 {
+            return;
+        }
+        let points = self.game.players[0].score.points;
+        let daily = self.daily.as_ref().map(|daily| daily.date.as_str());
+        if self.highscores.qualifies(points, daily) {
+            self.name_entry = Some(String::new());
+        }
+        if daily.is_none() {
+            let name = &self.config.leaderboard_name;
+            self.leaderboard
+                .submit(name, points, self.game.recording.hash());
+        }
+        self.save_best(points);
+    }
+
+    // Keeps the recording as the ghost for this mode if it beat the last one.
+    fn save_best(&mut self, points: u32) {
+        let path = match replay::best_run_path(self.mode().name()) {
+            Some(path) => path,
+            None => return,
+        };
+        let best = Replay::load(&path)
+            .ok()
+            .filter(|best| best.matches(&self.game.rules))
+            .and_then(|best| best.score);
+        if best.is_some_and(|best| best >= points) {
+            return;
+        }
+        self.game.recording.score = Some(points);
+        if let Err(e) = self.game.recording.save(&path) {
+            error!("Could not save replay: {}", e);
+        }
+    }
+
+    // Starts the best run for the current mode and rules, if there is one.
+    fn load_ghost(&mut self) {
+        self.ghost = None;
+        let solo = self.game.players.len() == 1 && self.game.playback.is_none();
+        let guided = self.tutorial.is_some() || self.spectator.is_some();
+        if !self.config.ghost || !solo || self.net.is_some() || guided {
+            return;
+        }
+        let replay = match replay::best_run_path(self.mode().name())
+            .and_then(|path| Replay::load(&path).ok())
+        {
+            Some(replay) if replay.matches(&self.game.rules) => replay,
+            _ => return,
+        };
+        let mut rules = self.game.rules.clone();
+        replay.apply_to(&mut rules);
+        let level = match rules.level {
+            Some(ref spec) => Level::load(spec, rules.board),
+            None => Ok(Level::empty()),
+        };
+        if let Ok(level) = level {
+            let mut ghost = Game::new(rules, level);
+            ghost.playback = Some(replay);
+            self.ghost = Some(ghost);
+        }
+    }
+
+    // Steps the ghost until it has played as long as the live game, which
+    // keeps both in time even when their speeds differ.
+    fn step_ghost(&mut self) {
+        let ghost = match self.ghost {
+            Some(ref mut ghost) => ghost,
+            None => return,
+        };
+        while ghost.elapsed < self.game.elapsed {
+            let ticks = ghost.playback.as_ref().map_or(0, |p| p.inputs[0].len());
+            let finished = ghost.tick >= ticks
+                || matches!(
+                    ghost.step(None),
+                    TickResult::Died(_)
+                        | TickResult::Won
+                        | TickResult::TimeUp
+                        | TickResult::Failed(_)
+                );
+            if finished {
+                self.ghost = None;
+                return;
+            }
+        }
+    }
+
+    // The stage's goal was reached: move on and remember how far we got.
+    fn level_complete(&mut self) {
+        self.state = GameState::LevelComplete;
+        self.audio.play(Sound::PowerUp);
+        self.record_stats();
+        self.campaign.advance();
+        if let Err(e) = self.campaign.save() {
+            error!("Could not save campaign: {}", e);
+        }
+    }
+
+    fn snake_style(&self, index: usize) -> SnakeStyle {
+        let style = self
+            .snake_colors
+            .style(index, self.theme.snake_style(index));
+        if self.game.accessibility.reduced_motion {
+            style.still()
+        } else {
+            style
+        }
+    }
+
+    fn mode(&self) -> Mode {
+        if self.in_campaign {
+            Mode::Campaign
+        } else if self.daily.is_some() {
+            Mode::Daily
+        } else if self.practice.is_some() {
+            Mode::Practice
+        } else {
+            Mode::from_rules(&self.game.rules)
+        }
+    }
+
+    fn record_stats(&mut self) {
+        let mode = self.mode();
+        self.stats
+            .record(&self.game, &self.human_players(), mode.name());
+        if let Err(e) = self.stats.save() {
+            error!("Could not save stats: {}", e);
+        }
+    }
+
+    fn save_recording(&self) {
+        if let Some(path) = replay::last_run_path() {
+            if let Err(e) = self.game.recording.save(&path) {
+                error!("Could not save replay: {}", e);
+            }
+        }
+    }
+
+    fn gamepad(&mut self, input: Input) {
+        if self.rebinding.is_some() {
+            return;
+        }
+
+        match (input, self.state) {
+            (Input::Turn(0, dir), GameState::Playing) if self.net.is_some() => {
+                self.queue_net_turn(dir);
+            }
+            (Input::Turn(_, _), GameState::Playing) if self.net.is_some() => {}
+            (Input::Turn(index, dir), GameState::Playing) => {
+                if self.game.playback.is_none() {
+                    if index == 0 {
+                        self.take_over();
+                    }
+                    if let Some(player) = self.game.players.get_mut(index) {
+                        player.steer(dir);
+                    }
+                }
+            }
+            (Input::Turn(_, dir), _) => {
+                let key = match dir {
+                    Direction::Up => Key::Up,
+                    Direction::Down => Key::Down,
+                    Direction::Left => Key::Left,
+                    Direction::Right => Key::Right,
+                    Direction::UpLeft | Direction::UpRight => Key::Up,
+                    Direction::DownLeft | Direction::DownRight => Key::Down,
+                };
+                self.pressed(&Button::Keyboard(key));
+            }
+            (Input::Start, GameState::Playing) | (Input::Start, GameState::Paused) => {
+                self.pressed(&Button::Keyboard(self.config.keys.pause));
+            }
+            (Input::Start, GameState::GameOver) if self.feedback.active() => {}
+            (Input::Start, GameState::GameOver) if self.name_entry.is_none() => {
+                self.pressed_game_over(&Button::Keyboard(self.config.keys.restart));
+            }
+            (Input::Start, _) => self.pressed(&Button::Keyboard(Key::Return)),
+        }
+    }
+
+    fn save_game(&self) {
+        if self.state != GameState::Playing && self.state != GameState::Paused {
+            return;
+        }
+        if let Some(path) = game::save_path() {
+            match self.game.save(&path) {
+                Ok(()) => info!("Saved the game to {}", path.display()),
+                Err(e) => error!("Could not save game: {}", e),
+            }
+        }
+    }
+
+    // Keeps the run in the autosave every AUTOSAVE_SECONDS while it's on,
+    // and clears the file once it's over or left for the menu. Network
+    // games, replays, the tutorial and the AI's demo games aren't kept.
+    fn autosave(&mut self, dt: f64) {
+        let running = matches!(self.state, GameState::Playing | GameState::Paused)
+            && self.net.is_none()
+            && self.game.playback.is_none()
+            && self.tutorial.is_none()
+            && self.spectator.is_none();
+        if !running {
+            if self.autosaved {
+                self.clear_autosave();
+            }
+            self.autosave_timer = AUTOSAVE_SECONDS;
+            return;
+        }
+        self.autosave_timer -= dt;
+        if self.autosave_timer > 0.0 {
+            return;
+        }
+        self.autosave_timer = AUTOSAVE_SECONDS;
+        if let Some(path) = game::autosave_path() {
+            match self.game.save(&path) {
+                Ok(()) => self.autosaved = true,
+                Err(e) => error!("Could not autosave: {}", e),
+            }
+        }
+    }
+
+    fn clear_autosave(&mut self) {
+        self.autosaved = false;
+        if let Some(path) = game::autosave_path() {
+            if let Err(e) = fs::remove_file(&path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    error!("Could not remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    fn resume_autosave(&mut self) {
+        self.state = GameState::Menu;
+        if let Some(path) = game::autosave_path() {
+            self.load_game_from(&path);
+        }
+        // A run that won't load isn't offered again.
+        if self.state == GameState::Menu {
+            self.clear_autosave();
+        } else {
+            self.autosaved = true;
+        }
+    }
+
+    fn load_game(&mut self) {
+        if let Some(path) = game::save_path() {
+            self.load_game_from(&path);
+        }
+    }
+
+    fn load_game_from(&mut self, path: &Path) {
+        match Game::load(path) {
+            Ok(game) => {
+                info!("Loaded the game from {}", path.display());
+                self.game = game;
+                self.game.accessibility = self.config.accessibility;
+                self.game.record_checksums = self.config.checksums;
+                self.in_campaign = false;
+                self.ghost = None;
+                self.achievements.new_game();
+                self.previous.clear();
+                self.tick_accumulator = 0.0;
+                self.name_entry = None;
+                self.state = GameState::Paused;
+            }
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    fn typed(&mut self, text: &str) {
+        if self.feedback.active() {
+            return;
+        }
+        if let Some(ref mut name) = self.name_entry {
+            for ch in text.chars() {
+                if name.chars().count() < MAX_NAME_LENGTH && (ch.is_alphanumeric() || ch == ' ') {
+                    name.push(ch);
+                }
+            }
+        }
+    }
+
+    fn submit_name(&mut self) {
+        if let Some(name) = self.name_entry.take() {
+            let name = name.trim();
+            let anonymous = self.locale.format("scores.anonymous", &[]);
+            let name = if name.is_empty() { &anonymous } else { name };
+            let preset = self.game.rules.speed_preset();
+            let points = self.game.players[0].score.points;
+            let daily = self.daily.as_ref().map(|daily| daily.date.as_str());
+            let perfect = self.game.filled;
+            self.highscores.add(name, points, preset, daily, perfect);
+            if let Err(e) = self.highscores.save() {
+                error!("Could not save high scores: {}", e);
+            }
+        }
+    }
+
+    fn pressed(&mut self, btn: &Button) {
+        if self.attract.wake() {
+            return;
+        }
+        let gamepad_input = match *btn {
+            Button::Controller(button) => gamepad::button(button),
+            Button::Hat(hat) => gamepad::hat(hat),
+            _ => None,
+        };
+        if let Some(input) = gamepad_input {
+            self.gamepad(input);
+            return;
+        }
+
+        if self.rebinding.is_some() {
+            self.pressed_controls(btn);
+            return;
+        }
+
+        match *btn {
+            Button::Keyboard(key) if key == self.config.keys.mute && self.name_entry.is_none() => {
+                self.audio.toggle_mute();
+                return;
+            }
+            Button::Keyboard(Key::F5) | Button::Keyboard(Key::F9) if self.net.is_some() => return,
+            Button::Keyboard(Key::F5) => {
+                self.save_game();
+                return;
+            }
+            Button::Keyboard(Key::F9) => {
+                self.load_game();
+                return;
+            }
+            Button::Keyboard(Key::F10) => {
+                self.clip.save();
+                return;
+            }
+            Button::Keyboard(Key::F12) => {
+                self.screenshot = true;
+                return;
+            }
+            Button::Keyboard(Key::F3) => {
+                self.diagnostics.toggle();
+                return;
+            }
+            _ => {}
+        }
+
+        match self.state {
+            GameState::Menu => self.pressed_menu(btn),
+            GameState::Settings => self.pressed_settings(btn),
+            GameState::Controls => self.pressed_controls(btn),
+            GameState::Achievements | GameState::Stats => {
+                if let Button::Keyboard(Key::Return) | Button::Keyboard(Key::Backspace) = *btn {
+                    self.state = GameState::Menu;
+                }
+            }
+            GameState::Playing => self.pressed_playing(btn),
+            GameState::Paused => self.pressed_paused(btn),
+            GameState::GameOver => self.pressed_game_over(btn),
+            GameState::LevelComplete => {
+                if *btn == Button::Keyboard(Key::Return) {
+                    self.next_stage();
+                }
+            }
+            GameState::Disconnected => {}
+            GameState::Resume => match *btn {
+                Button::Keyboard(Key::Return) => self.resume_autosave(),
+                Button::Keyboard(Key::Backspace) => {
+                    self.clear_autosave();
+                    self.state = GameState::Menu;
+                }
+                _ => {}
+            },
+            GameState::Failed(_) => self.pressed_failed(btn),
+        }
+    }
+
+    fn pressed_menu(&mut self, btn: &Button) {
+        if let Button::Keyboard(Key::Up) | Button::Keyboard(Key::Down) = *btn {
+            self.audio.play(Sound::Menu);
+        }
+
+        match *btn {
+            Button::Keyboard(Key::Up) => self.menu.previous(),
+            Button::Keyboard(Key::Down) => self.menu.next(),
+            Button::Keyboard(Key::Return) => {
+                self.spectator = None;
+                match MODES.get(self.menu.selected()) {
+                    Some(&(_, mode)) => self.start(mode),
+                    None => match self.menu.selected() - MODES.len() {
+                        0 => self.watch_ai(),
+                        1 => self.state = GameState::Settings,
+                        2 => self.state = GameState::Achievements,
+                        3 => self.state = GameState::Stats,
+                        _ => self.start_tutorial(),
+                    },
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn pressed_settings(&mut self, btn: &Button) {
+        if let Button::Keyboard(Key::Up) | Button::Keyboard(Key::Down) = *btn {
+            self.audio.play(Sound::Menu);
+        }
+
+        let rules = &mut self.game.rules;
+        match (*btn, self.settings.selected()) {
+            (Button::Keyboard(Key::Up), _) => self.settings.previous(),
+            (Button::Keyboard(Key::Down), _) => self.settings.next(),
+            (Button::Keyboard(Key::Left), 0) => {
+                rules.set_speed_preset(rules.speed_preset().previous());
+                self.config.speed = Some(rules.speed_preset());
+            }
+            (Button::Keyboard(Key::Right), 0) => {
+                rules.set_speed_preset(rules.speed_preset().next());
+                self.config.speed = Some(rules.speed_preset());
+            }
+            (Button::Keyboard(Key::Left), 1) | (Button::Keyboard(Key::Right), 1) => {
+                rules.ai_difficulty = rules.ai_difficulty.next();
+            }
+            (Button::Keyboard(Key::Left), 2) => {
+                rules.food_count = rules.food_count.saturating_sub(1).max(1);
+            }
+            (Button::Keyboard(Key::Right), 2) => {
+                rules.food_count = (rules.food_count + 1).min(MAX_FOOD);
+            }
+            (Button::Keyboard(Key::Left), 3) | (Button::Keyboard(Key::Right), 3) => {
+                let edges = &mut self.config.rules.board_mode;
+                *edges = match *edges {
+                    BoardMode::Walls => BoardMode::Wrap,
+                    BoardMode::Wrap => BoardMode::Walls,
+                };
+            }
+            (Button::Keyboard(Key::Left), 4) | (Button::Keyboard(Key::Right), 4) => {
+                self.theme = self
+                    .theme
+                    .next(&self.themes)
+                    .with_overrides(&self.config.colors);
+            }
+            (Button::Keyboard(Key::Left), 5) | (Button::Keyboard(Key::Right), 5) => {
+                self.board_style = self.board_style.next();
+            }
+            (Button::Keyboard(Key::Left), 6) | (Button::Keyboard(Key::Right), 6) => {
+                self.config.zoom = self.config.zoom.next();
+            }
+            (Button::Keyboard(Key::Left), 7) | (Button::Keyboard(Key::Right), 7) => {
+                self.config.patterns = !self.config.patterns;
+            }
+            (Button::Keyboard(Key::Left), 8) | (Button::Keyboard(Key::Right), 8) => {
+                let options = &mut self.config.accessibility;
+                options.set_reduced_motion(!options.reduced_motion);
+            }
+            (Button::Keyboard(Key::Left), 9) | (Button::Keyboard(Key::Right), 9) => {
+                let options = &mut self.config.accessibility;
+                options.flashes = !options.flashes;
+            }
+            (Button::Keyboard(Key::Left), 10) | (Button::Keyboard(Key::Right), 10) => {
+                self.config.edge_warning = !self.config.edge_warning;
+            }
+            (Button::Keyboard(Key::Left), 11) => {
+                self.config.max_fps = cycle(&FPS_CHOICES, &self.config.max_fps, false);
+            }
+            (Button::Keyboard(Key::Right), 11) => {
+                self.config.max_fps = cycle(&FPS_CHOICES, &self.config.max_fps, true);
+            }
+            (Button::Keyboard(Key::Left), 12) => {
+                self.config.tick_rate = cycle(&TICK_RATE_CHOICES, &self.config.tick_rate, false);
+            }
+            (Button::Keyboard(Key::Right), 12) => {
+                self.config.tick_rate = cycle(&TICK_RATE_CHOICES, &self.config.tick_rate, true);
+            }
+            (Button::Keyboard(key @ Key::Left), 13) | (Button::Keyboard(key @ Key::Right), 13) => {
+                let step = if key == Key::Right {
+                    VOLUME_STEP
+                } else {
+                    -VOLUME_STEP
+                };
+                // Rounded so repeated steps land on whole percentages.
+                let volume = ((self.config.volume + step) / VOLUME_STEP).round() * VOLUME_STEP;
+                self.config.volume = volume.clamp(0.0, 1.0);
+                self.audio.set_volume(self.config.volume);
+                self.audio.play(Sound::Menu);
+            }
+            (Button::Keyboard(key @ Key::Left), item @ 14..=17)
+            | (Button::Keyboard(key @ Key::Right), item @ 14..=17) => {
+                let (player, part) = match item {
+                    14 => (0, Part::Body),
+                    15 => (0, Part::Head),
+                    16 => (1, Part::Body),
+                    _ => (1, Part::Head),
+                };
+                self.snake_colors.cycle(player, part, key == Key::Right);
+                if let Err(e) = self.snake_colors.save() {
+                    error!("Could not save snake colors: {}", e);
+                }
+            }
+            (Button::Keyboard(key @ Key::Left), 18) | (Button::Keyboard(key @ Key::Right), 18) => {
+                let levels: Vec<String> = (1..=BUILTIN_LEVELS.len())
+                    .map(|n| n.to_string())
+                    .chain(
+                        self.user_assets
+                            .levels
+                            .iter()
+                            .map(|path| path.display().to_string()),
+                    )
+                    .collect();
+                self.level_spec = cycle(&levels, &self.level_spec, key == Key::Right);
+                self.config.rules.level = Some(self.level_spec.clone());
+            }
+            (Button::Keyboard(key @ Key::Left), 19) | (Button::Keyboard(key @ Key::Right), 19) => {
+                let packs: Vec<Option<String>> = iter::once(None)
+                    .chain(
+                        self.user_assets
+                            .sound_packs
+                            .iter()
+                            .map(|pack| Some(pack.name.clone())),
+                    )
+                    .collect();
+                self.config.sound_pack = cycle(&packs, &self.config.sound_pack, key == Key::Right);
+                self.use_sound_pack();
+                self.audio.play(Sound::Menu);
+            }
+            (Button::Keyboard(Key::Return), 20) => self.state = GameState::Controls,
+            (Button::Keyboard(Key::Return), 21) | (Button::Keyboard(Key::Backspace), _) => {
+                self.save_settings();
+                self.state = GameState::Menu;
+            }
+            _ => {}
+        }
+        self.game.accessibility = self.config.accessibility;
+        self.settings.items = App::settings_items(
+            &self.game.rules,
+            &self.theme,
+            self.board_style,
+            &self.config,
+            &self.snake_colors,
+            &self.locale,
+        );
+    }
+
+    // Plays the configured sound pack from now on.
+    fn use_sound_pack(&mut self) {
+        let assets = &self.user_assets;
+        let pack = self
+            .config
+            .sound_pack
+            .as_deref()
+            .and_then(|name| assets.sound_pack(name));
+        self.audio.set_pack(pack);
+    }
+
+    // Writes the settings screen's choices to the config file. The file
+    // watcher is told, so the write doesn't come back as a reload.
+    fn save_settings(&mut self) {
+        let config = &mut self.config;
+        config.rules.ai_difficulty = self.game.rules.ai_difficulty;
+        config.rules.food_count = self.game.rules.food_count;
+        config.theme = self.theme.name.to_string();
+        config.board_style = self.board_style;
+        match config.save(CONFIG_FILE) {
+            Ok(()) => info!("Saved settings to {}", CONFIG_FILE),
+            Err(e) => error!("Could not save settings: {}", e),
+        }
+        self.config_watcher.saved();
+    }
+
+    fn pressed_controls(&mut self, btn: &Button) {
+        if let Some(action) = self.rebinding.take() {
+            if let Button::Keyboard(key) = *btn {
+                self.config.keys.bind(action, key);
+                self.controls.items = App::controls_items(&self.config.keys, &self.locale);
+            }
+            return;
+        }
+
+        match *btn {
+            Button::Keyboard(Key::Up) => self.controls.previous(),
+            Button::Keyboard(Key::Down) => self.controls.next(),
+            Button::Keyboard(Key::Return) => match ACTIONS.get(self.controls.selected()) {
+                Some(&action) => self.rebinding = Some(action),
+                None => self.state = GameState::Settings,
+            },
+            Button::Keyboard(Key::Backspace) => self.state = GameState::Settings,
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, mode: Mode) {
+        self.leave_daily();
+        if mode != Mode::Campaign {
+            self.campaign.leave(&mut self.game.rules);
+        }
+        self.game.rules.board = self.config.zoom.grid(self.config.rules.board);
+        self.in_campaign = mode == Mode::Campaign;
+        self.practice = match mode {
+            Mode::Practice => Some(Rewind::new()),
+            _ => None,
+        };
+        if self.in_campaign {
+            if self.campaign.finished() {
+                self.campaign.reset();
+            }
+            self.campaign.apply_to(&mut self.game.rules);
+            let spec = self.campaign.current().level.to_string();
+            self.game.level = load_level(&spec, self.game.rules.board);
+            self.restart();
+            return;
+        }
+        if mode == Mode::Daily {
+            let daily = Daily::today(self.game.rules.clone());
+            daily.apply_to(&mut self.game.rules);
+            self.game.level = match self.game.rules.level {
+                Some(ref spec) => load_level(spec, self.game.rules.board),
+                None => Level::empty(),
+            };
+            info!(
+                "Daily challenge {}: {}",
+                daily.date,
+                daily::describe(&self.game.rules, &self.locale)
+            );
+            self.daily = Some(daily);
+            self.restart();
+            return;
+        }
+        let rules = &mut self.game.rules;
+        rules.board_mode = match mode {
+            Mode::Wrap => BoardMode::Wrap,
+            Mode::Maze => BoardMode::Walls,
+            _ => self.config.rules.board_mode,
+        };
+        rules.players = match mode {
+            Mode::TwoPlayer | Mode::Coop | Mode::VersusAi | Mode::Battle => 2,
+            _ => 1,
+        };
+        rules.coop = mode == Mode::Coop;
+        rules.ai_opponent = mode == Mode::VersusAi || mode == Mode::Battle;
+        rules.endless = mode == Mode::Endless;
+        rules.shrinking = mode == Mode::Battle;
+        rules.time_limit = match mode {
+            Mode::TimeAttack => Some(TIME_ATTACK_SECONDS),
+            _ => None,
+        };
+        if mode == Mode::Obstacles {
+            rules.level = Some(self.level_spec.clone());
+            self.game.level = load_level(&self.level_spec, rules.board);
+        } else if mode == Mode::Maze {
+            let spec = maze::spec(rules.seed.unwrap_or_else(rand::random));
+            self.game.level = load_level(&spec, rules.board);
+            rules.level = Some(spec);
+        } else {
+            rules.level = None;
+            self.game.level = Level::empty();
+        }
+        self.restart();
+    }
+
+    fn leave_daily(&mut self) {
+        if let Some(daily) = self.daily.take() {
+            self.game.rules = daily.rules;
+        }
+    }
+
+    fn next_stage(&mut self) {
+        if !self.campaign.finished() {
+            self.start(Mode::Campaign);
+            return;
+        }
+        self.campaign.reset();
+        if let Err(e) = self.campaign.save() {
+            error!("Could not save campaign: {}", e);
+        }
+        self.campaign.leave(&mut self.game.rules);
+        self.in_campaign = false;
+        self.state = GameState::Menu;
+    }
+
+    fn pressed_paused(&mut self, btn: &Button) {
+        if *btn == Button::Keyboard(self.config.keys.pause) {
+            self.state = GameState::Playing;
+            self.countdown = COUNTDOWN_SECONDS;
+            self.tutorial_reached(Step::Resume);
+        }
+    }
+
+    // A classic game with the first snake steered by the AI at the
+    // configured difficulty.
+    fn watch_ai(&mut self) {
+        self.spectator = Some(Spectator::new());
+        self.start(Mode::Classic);
+    }
+
+    // Hands the first snake back to the player mid-game.
+    fn take_over(&mut self) {
+        match self.spectator {
+            Some(ref mut spectator) if spectator.watching => spectator.watching = false,
+            _ => return,
+        }
+        self.game.players[0].ai = None;
+        let text = self.locale.format("toast.control", &[]);
+        self.toasts.push(Toast::new(text));
+    }
+
+    // Winds a practice game back a few ticks and plays on from there after
+    // the usual countdown.
+    fn rewind(&mut self) {
+        let rewound = match self.practice {
+            Some(ref mut practice) => practice.rewind(&mut self.game),
+            None => false,
+        };
+        if !rewound {
+            return;
+        }
+        self.previous.clear();
+        self.popups.clear();
+        self.particles.clear();
+        self.feedback.clear();
+        self.tick_accumulator = 0.0;
+        self.countdown = COUNTDOWN_SECONDS;
+        self.state = GameState::Playing;
+    }
+
+    // Classic rules with one pellet and one life, so the crash step ends
+    // the game; the player's own rules come back afterwards.
+    fn start_tutorial(&mut self) {
+        self.leave_daily();
+        let rules = self.game.rules.clone();
+        self.game.rules.food_count = 1;
+        self.game.rules.lives = 1;
+        self.tutorial = Some(Tutorial::new(rules));
+        self.start(Mode::Classic);
+    }
+
+    fn finish_tutorial(&mut self) {
+        if let Some(tutorial) = self.tutorial.take() {
+            self.game.rules = tutorial.rules;
+        }
+        tutorial::mark_seen();
+        self.state = GameState::Menu;
+    }
+
+    // Moves on from `step` if that is the current one.
+    fn tutorial_reached(&mut self, step: Step) {
+        let tutorial = match self.tutorial {
+            Some(ref mut tutorial) if tutorial.step == step => tutorial,
+            _ => return,
+        };
+        tutorial.step = match step {
+            Step::Turn => Step::Eat,
+            Step::Eat => Step::Grow,
+            Step::Grow => Step::Pause,
+            Step::Pause => Step::Resume,
+            Step::Resume => Step::Crash,
+            Step::Crash => Step::Restart,
+            Step::Restart | Step::Done => Step::Done,
+        };
+    }
+
+    // Replaces whatever food the game placed with one just ahead of the snake.
+    fn place_tutorial_food(&mut self, dir: Direction) {
+        let head = match self.game.players[0].snake.head() {
+            Ok(head) => head,
+            Err(_) => return,
+        };
+        let food = tutorial::food_ahead(head, dir, &self.game.rules);
+        self.game.foods.clear();
+        self.game.foods.push(food);
+    }
+
+    fn tutorial_ate(&mut self) {
+        let step = match self.tutorial {
+            Some(ref tutorial) => tutorial.step,
+            None => return,
+        };
+        let snake = &self.game.players[0].snake;
+        let length = snake.len() + snake.growth;
+        let dir = snake.dir;
+        match step {
+            Step::Eat => {
+                self.tutorial_reached(Step::Eat);
+                if let Some(ref mut tutorial) = self.tutorial {
+                    tutorial.target_length = length + tutorial::GROW_SEGMENTS;
+                }
+                self.place_tutorial_food(dir);
+            }
+            Step::Grow => match self.tutorial {
+                Some(ref tutorial) if length >= tutorial.target_length => {
+                    self.tutorial_reached(Step::Grow)
+                }
+                _ => self.place_tutorial_food(dir),
+            },
+            _ => {}
+        }
+    }
+
+    // A crash before the crash step goes back to the start of eating.
+    fn tutorial_restarted(&mut self) {
+        let step = match self.tutorial {
+            Some(ref mut tutorial) => {
+                if tutorial.step == Step::Grow {
+                    tutorial.step = Step::Eat;
+                }
+                tutorial.step
+            }
+            None => return,
+        };
+        match step {
+            Step::Turn => self.game.foods.clear(),
+            Step::Eat => {
+                let dir = self.game.players[0].snake.dir;
+                self.place_tutorial_food(dir);
+            }
+            Step::Restart => self.tutorial_reached(Step::Restart),
+            _ => {}
+        }
+    }
+
+    fn pressed_game_over(&mut self, btn: &Button) {
+        if self.feedback.active() {
+            return;
+        }
+        if self.name_entry.is_some() {
+            match *btn {
+                Button::Keyboard(Key::Backspace) => {
+                    if let Some(ref mut name) = self.name_entry {
+                        name.pop();
+                    }
+                }
+                Button::Keyboard(Key::Return) => self.submit_name(),
+                _ => {}
+            }
+        } else if *btn == Button::Keyboard(Key::Tab) && self.leaderboard.enabled() {
+            self.show_online = !self.show_online;
+        } else if *btn == Button::Keyboard(Key::Backspace) && self.practice.is_some() {
+            self.rewind();
+        } else if *btn == Button::Keyboard(Key::Backspace) && self.spectator.is_some() {
+            self.spectator = None;
+            self.state = GameState::Menu;
+        } else if *btn == Button::Keyboard(self.config.keys.restart) {
+            match self.net {
+                Some(ref mut net) => net.request_restart(),
+                None => self.restart(),
+            }
+        }
+    }
+
+    // Only a fresh game is safe to go on with after a failure.
+    fn pressed_failed(&mut self, btn: &Button) {
+        if *btn == Button::Keyboard(self.config.keys.restart) {
+            match self.net {
+                Some(ref mut net) => net.request_restart(),
+                None => self.restart(),
+            }
+        } else if *btn == Button::Keyboard(Key::Backspace) && self.net.is_none() {
+            self.state = GameState::Menu;
+        }
+    }
+
+    fn pressed_playing(&mut self, btn: &Button) {
+        if *btn == Button::Mouse(MouseButton::Left) {
+            self.dragging = true;
+            self.steer_to_cursor();
+            return;
+        }
+        if let Button::Keyboard(key) = *btn {
+            let action = self.config.keys.action(key);
+            if self.net.is_some() {
+                if let Some(dir) = action.and_then(Action::direction) {
+                    let dir = self.chord(0, dir);
+                    self.steer(dir);
+                }
+                return;
+            }
+            if action == Some(Action::Pause) {
+                self.state = GameState::Paused;
+                self.tutorial_reached(Step::Pause);
+                return;
+            }
+            if key == Key::Return && self.tutorial.as_ref().is_some_and(|t| t.step == Step::Done) {
+                self.finish_tutorial();
+                return;
+            }
+            if self.game.playback.is_some() {
+                return;
+            }
+            let changed_speed = match self.spectator {
+                Some(ref mut spectator) if spectator.watching => spectator.pressed(key),
+                _ => self.net.is_none() && self.time_scale.pressed(key),
+            };
+            if changed_speed {
+                return;
+            }
+            if let Some(dir) = action.and_then(Action::direction) {
+                let dir = self.chord(0, dir);
+                self.steer(dir);
+            }
+            if let Some(dir) = self.config.keys_player_two.direction(key) {
+                let dir = self.chord(1, dir);
+                if let Some(player) = self.game.players.get_mut(1) {
+                    player.steer(dir);
+                }
+            }
+        }
+    }
+
+    // With diagonals on, a direction pressed while a perpendicular one of the
+    // same player is still held goes between the two. The held key's turn is
+    // taken back if the snake hasn't made it yet, so a quick chord goes
+    // straight to the diagonal.
+    fn chord(&mut self, player: usize, dir: Direction) -> Direction {
+        if !self.game.rules.diagonals {
+            return dir;
+        }
+        let config = &self.config;
+        let direction = |key: Key| match player {
+            0 => config.keys.action(key).and_then(Action::direction),
+            _ => config.keys_player_two.direction(key),
+        };
+        let chord = self.held.iter().rev().find_map(|&key| {
+            let held = direction(key)?;
+            held.combined(dir).map(|diagonal| (held, diagonal))
+        });
+        let (held, diagonal) = match chord {
+            Some(chord) => chord,
+            None => return dir,
+        };
+        if self.net.is_none() {
+            let retracted = self
+                .game
+                .players
+                .get_mut(player)
+                .is_some_and(|p| p.retract(held));
+            if retracted && player == 0 {
+                self.telemetry.turn_retracted();
+            }
+        }
+        diagonal
+    }
+
+    // Turns the first snake, or this side's snake in a network game.
+    fn steer(&mut self, dir: Direction) {
+        if self.net.is_some() {
+            self.queue_net_turn(dir);
+            return;
+        }
+        if self.game.playback.is_some() {
+            return;
+        }
+        self.take_over();
+        let player = &mut self.game.players[0];
+        let queued = player.queued_inputs();
+        player.steer(dir);
+        if queued == MAX_QUEUED_INPUTS {
+            self.telemetry.turn_dropped();
+        } else if player.queued_inputs() > queued && self.countdown <= 0.0 {
+            self.telemetry.turn_queued();
+        }
+        if self.tutorial.as_ref().is_some_and(|t| t.step == Step::Turn) {
+            self.tutorial_reached(Step::Turn);
+            self.place_tutorial_food(dir);
+        }
+    }
+
+    fn cursor_moved(&mut self, position: [f64; 2]) {
+        self.cursor = position;
+        if self.dragging && self.state == GameState::Playing {
+            self.steer_to_cursor();
+        }
+    }
+
+    // Turns towards the cursor from the head of the snake played here.
+    // Clicking the head itself does nothing.
+    fn steer_to_cursor(&mut self) {
+        let local = self.net.as_ref().map_or(0, |net| net.local);
+        let snake = match self.game.players.get(local) {
+            Some(player) => &player.snake,
+            None => return,
+        };
+        let head = match snake.head() {
+            Ok(head) => head,
+            Err(_) => return,
+        };
+        let board = self.game.rules.board;
+        let layout = Layout::fit(self.window_size, self.scale, board.columns, board.rows);
+        let cell = layout.cell_at(self.cursor);
+        let dx = cell[0] - (f64::from(head.x) + 0.5);
+        let dy = cell[1] - (f64::from(head.y) + 0.5);
+        if dx.abs() < 0.5 && dy.abs() < 0.5 {
+            return;
+        }
+        if let Some(dir) = Direction::towards(dx, dy, snake.dir) {
+            self.steer(dir);
+        }
+    }
+
+    fn queue_net_turn(&mut self, dir: Direction) {
+        if self.net_pending.len() < NET_QUEUE_LIMIT {
+            self.net_pending.push_back(dir);
+        } else {
+            self.telemetry.turn_dropped();
+        }
+    }
+
+    // Pauses a game left behind by switching to another window; it waits
+    // for the pause key like any other pause. Network games can't pause.
+    fn focus_changed(&mut self, focused: bool) {
+        if !focused && self.state == GameState::Playing && self.net.is_none() {
+            self.state = GameState::Paused;
+            self.dragging = false;
+        }
+        if !focused {
+            self.held.clear();
+        }
+    }
+
+    fn resized(&mut self, size: [f64; 2], scale: f64) {
+        self.window_size = size;
+        self.scale = scale;
+    }
+
+    fn restart(&mut self) {
+        self.countdown = COUNTDOWN_SECONDS;
+        let fixed_board =
+            self.net.is_some() || self.daily.is_some() || self.game.playback.is_some();
+        if self.resize_pending && !fixed_board {
+            self.resize_pending = false;
+            self.game.rules.board = self.config.zoom.grid(self.config.rules.board);
+            // Mazes are generated for the board, and other levels may not
+            // fit a smaller one.
+            if let Some(ref spec) = self.game.rules.level {
+                self.game.level = load_level(spec, self.game.rules.board);
+            }
+        }
+        self.game.restart();
+        match self.spectator {
+            Some(ref spectator) if spectator.watching => {
+                self.game.players[0].ai = Some(self.game.rules.ai_difficulty);
+            }
+            // The player took over last game and plays this one alone.
+            Some(_) => self.spectator = None,
+            None => {}
+        }
+        if let Some(ref mut practice) = self.practice {
+            practice.clear();
+        }
+        self.tutorial_restarted();
+        self.load_ghost();
+        self.achievements.new_game();
+        self.previous.clear();
+        self.popups.clear();
+        self.particles.clear();
+        self.feedback.clear();
+        self.tick_accumulator = 0.0;
+        self.metrics = RunMetrics::new(self.net.as_ref().map_or(0, |net| net.local));
+        self.telemetry.clear_turns();
+        self.state = GameState::Playing;
+        self.update_presence();
+    }
+
+    // Shows the mode and score of the game being played on the player's
+    // Discord profile. Replays and games the AI plays show nothing.
+    fn update_presence(&mut self) {
+        let watching = self.spectator.as_ref().is_some_and(|s| s.watching);
+        let playing = matches!(self.state, GameState::Playing | GameState::Paused);
+        if !playing || watching || self.game.playback.is_some() {
+            self.discord.set(None);
+            return;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let local = self.net.as_ref().map_or(0, |net| net.local);
+        self.discord.set(Some(Activity {
+            details: self.locale.get(self.mode().key()).to_string(),
+            state: self.score_label(local),
+            start: now.saturating_sub(self.game.elapsed as u64),
+        }));
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+// The choice after (or before) `current`, wrapping around; values that are
+// not among the choices jump to the first one.
+fn cycle<T: Clone + PartialEq>(choices: &[T], current: &T, forward: bool) -> T {
+    let next = match choices.iter().position(|c| c == current) {
+        Some(i) if forward => (i + 1) % choices.len(),
+        Some(i) => (i + choices.len() - 1) % choices.len(),
+        None => 0,
+    };
+    choices[next].clone()
+}
+
+// A built-in level's name, or a level file's.
+fn level_name(spec: &str) -> String {
+    match spec.parse::<usize>() {
+        Ok(n) if (1..=BUILTIN_LEVELS.len()).contains(&n) => BUILTIN_LEVELS[n - 1].0.to_string(),
+        _ => user_assets::name(Path::new(spec)),
+    }
+}
+
+// The level for `spec`, or no walls at all when it won't load.
+fn load_level(spec: &str, board: snake_2d::Board) -> Level {
+    Level::load(spec, board).unwrap_or_else(|e| {
+        warn!("{}, playing without walls", e);
+        Level::empty()
+    })
+}
+
+fn score_line(index: usize, name: &str, score: u32) -> String {
+    format!(
+        "{:>2}. {:<width$} {:>6}",
+        index + 1,
+        name,
+        score,
+        width = MAX_NAME_LENGTH
+    )
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum GameState {
+    Menu,
+    Settings,
+    Controls,
+    Achievements,
+    Stats,
+    Playing,
+    Paused,
+    GameOver,
+    LevelComplete,
+    Disconnected,
+    // Asking whether to go on with the run an unclean shutdown left in the
+    // autosave.
+    Resume,
+    // The game hit a state it can't go on from and has to be restarted.
+    Failed(SnakeError),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Classic,
+    Wrap,
+    Obstacles,
+    TwoPlayer,
+    Coop,
+    VersusAi,
+    TimeAttack,
+    Endless,
+    Maze,
+    Battle,
+    Campaign,
+    Daily,
+    Practice,
+}
+
+// The names also key the stats and best-run files, so they stay in English.
+const MODES: [(&str, Mode); 13] = [
+    ("Classic", Mode::Classic),
+    ("Wrap", Mode::Wrap),
+    ("Obstacles", Mode::Obstacles),
+    ("Two-Player", Mode::TwoPlayer),
+    ("Co-op", Mode::Coop),
+    ("Versus AI", Mode::VersusAi),
+    ("Time Attack", Mode::TimeAttack),
+    ("Endless", Mode::Endless),
+    ("Maze", Mode::Maze),
+    ("Battle", Mode::Battle),
+    ("Campaign", Mode::Campaign),
+    ("Daily", Mode::Daily),
+    ("Practice", Mode::Practice),
+];
+
+impl Mode {
+    fn name(self) -> &'static str {
+        MODES
+            .iter()
+            .find(|&&(_, mode)| mode == self)
+            .map_or("Classic", |&(name, _)| name)
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Mode::Classic => "mode.classic",
+            Mode::Wrap => "mode.wrap",
+            Mode::Obstacles => "mode.obstacles",
+            Mode::TwoPlayer => "mode.two_player",
+            Mode::Coop => "mode.coop",
+            Mode::VersusAi => "mode.versus_ai",
+            Mode::TimeAttack => "mode.time_attack",
+            Mode::Endless => "mode.endless",
+            Mode::Maze => "mode.maze",
+            Mode::Battle => "mode.battle",
+            Mode::Campaign => "mode.campaign",
+            Mode::Daily => "mode.daily",
+            Mode::Practice => "mode.practice",
+        }
+    }
+
+    fn from_rules(rules: &Rules) -> Mode {
+        if rules.shrinking {
+            Mode::Battle
+        } else if rules.coop {
+            Mode::Coop
+        } else if rules.ai_opponent {
+            Mode::VersusAi
+        } else if rules.players > 1 {
+            Mode::TwoPlayer
+        } else if rules.time_limit.is_some() {
+            Mode::TimeAttack
+        } else if rules.endless {
+            Mode::Endless
+        } else if rules.level.as_deref().is_some_and(maze::is_spec) {
+            Mode::Maze
+        } else if rules.level.is_some() {
+            Mode::Obstacles
+        } else if rules.board_mode == BoardMode::Wrap {
+            Mode::Wrap
+        } else {
+            Mode::Classic
+        }
+    }
 }
@@ -0,0 +1,115 @@
+use crate::board::Board;
+use crate::level::{Level, Obstacle};
+use crate::pos::Pos;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use std::collections::{HashSet, VecDeque};
+
+pub const SPEC: &str = "maze";
+// Corridors are this many cells wide, so a short snake can still turn
+// around, with one-cell walls between them.
+const CORRIDOR: i32 = 2;
+const PITCH: i32 = CORRIDOR + 1;
+
+// The level spec for the maze grown from `seed`, e.g. "maze:42".
+pub fn spec(seed: u64) -> String {
+    format!("{}:{}", SPEC, seed)
+}
+
+pub fn is_spec(spec: &str) -> bool {
+    spec == SPEC || spec.starts_with("maze:")
+}
+
+// A perfect maze over `board`: every open cell is reachable and there is
+// exactly one way between any two rooms. Carved by a recursive backtracker
+// from the top-left room, whose first corridor runs east so a snake starting
+// there heading right has somewhere to go. Rows and columns left over at the
+// right and bottom edges are walled off.
+pub fn generate(board: Board, seed: u64) -> Result<Level, String> {
+    let (columns, rows) = ((board.columns + 1) / PITCH, (board.rows + 1) / PITCH);
+    if columns < 2 || rows < 2 {
+        return Err(format!("A {} board is too small for a maze", board.name()));
+    }
+
+    let mut rng = Pcg32::seed_from_u64(seed);
+    let mut open = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![(0, 0)];
+    visited.insert((0, 0));
+    carve_room(&mut open, (0, 0));
+    let mut first = Some((1, 0));
+    while let Some(&(x, y)) = stack.last() {
+        let mut choices: Vec<(i32, i32)> = [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+            .iter()
+            .copied()
+            .filter(|&(nx, ny)| nx >= 0 && ny >= 0 && nx < columns && ny < rows)
+            .filter(|room| !visited.contains(room))
+            .collect();
+        choices.shuffle(&mut rng);
+        let next = match first.take().or_else(|| choices.first().copied()) {
+            Some(next) => next,
+            None => {
+                stack.pop();
+                continue;
+            }
+        };
+        carve_passage(&mut open, (x, y), next);
+        carve_room(&mut open, next);
+        visited.insert(next);
+        stack.push(next);
+    }
+
+    if !reachable(board, &open) {
+        return Err(format!("Maze {} leaves cells out of reach", seed));
+    }
+    let obstacles = board
+        .positions()
+        .filter(|cell| !open.contains(cell))
+        .map(|(x, y)| Obstacle { x, y })
+        .collect();
+    Ok(Level {
+        name: String::from("Maze"),
+        obstacles,
+        portals: Vec::new(),
+        patrols: Vec::new(),
+    })
+}
+
+fn carve_room(open: &mut HashSet<(i32, i32)>, (x, y): (i32, i32)) {
+    for dx in 0..CORRIDOR {
+        for dy in 0..CORRIDOR {
+            open.insert((x * PITCH + dx, y * PITCH + dy));
+        }
+    }
+}
+
+// Opens the wall between two neighbouring rooms.
+fn carve_passage(open: &mut HashSet<(i32, i32)>, a: (i32, i32), b: (i32, i32)) {
+    let (x, y) = (a.0.min(b.0), a.1.min(b.1));
+    for i in 0..CORRIDOR {
+        let cell = if a.1 == b.1 {
+            (x * PITCH + CORRIDOR, y * PITCH + i)
+        } else {
+            (x * PITCH + i, y * PITCH + CORRIDOR)
+        };
+        open.insert(cell);
+    }
+}
+
+// Whether a walk from the first room reaches every open cell.
+fn reachable(board: Board, open: &HashSet<(i32, i32)>) -> bool {
+    let mut seen = HashSet::new();
+    seen.insert((0, 0));
+    let mut queue = VecDeque::new();
+    queue.push_back(Pos::new(0, 0));
+    while let Some(cell) = queue.pop_front() {
+        for next in cell.neighbours().iter() {
+            let key = (next.x, next.y);
+            if next.on(board) && open.contains(&key) && seen.insert(key) {
+                queue.push_back(*next);
+            }
+        }
+    }
+    seen.len() == open.len()
+}
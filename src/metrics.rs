@@ -0,0 +1,72 @@
+use crate::event::GameEvent;
+use crate::game::Game;
+
+// At most this many length samples are kept; a longer run keeps every
+// other one and samples half as often from then on.
+const MAX_SAMPLES: usize = 240;
+
+// How one snake's run went, gathered from the game's events after every
+// tick for the summary once it is over.
+pub struct RunMetrics {
+    player: usize,
+    // The longest streak of food eaten within the combo window.
+    pub max_combo: u32,
+    ticks: usize,
+    total_speed: u64,
+    // The snake's length every `stride` ticks.
+    lengths: Vec<usize>,
+    stride: usize,
+}
+
+impl RunMetrics {
+    pub fn new(player: usize) -> RunMetrics {
+        RunMetrics {
+            player,
+            max_combo: 0,
+            ticks: 0,
+            total_speed: 0,
+            lengths: Vec::new(),
+            stride: 1,
+        }
+    }
+
+    // Call after every step.
+    pub fn observe(&mut self, game: &Game) {
+        let player = match game.players.get(self.player) {
+            Some(player) => player,
+            None => return,
+        };
+        for event in &game.events {
+            if let GameEvent::FoodEaten { player: p, .. } = *event {
+                if p == self.player {
+                    self.max_combo = self.max_combo.max(player.score.streak);
+                }
+            }
+        }
+        if self.ticks.is_multiple_of(self.stride) {
+            if self.lengths.len() == MAX_SAMPLES {
+                self.lengths = self.lengths.iter().copied().step_by(2).collect();
+                self.stride *= 2;
+            }
+            if self.ticks.is_multiple_of(self.stride) {
+                self.lengths.push(player.snake.len());
+            }
+        }
+        self.ticks += 1;
+        self.total_speed += game.speed();
+    }
+
+    // Moves per second, averaged over the ticks of the run.
+    pub fn average_speed(&self) -> f64 {
+        if self.ticks == 0 {
+            0.0
+        } else {
+            self.total_speed as f64 / self.ticks as f64
+        }
+    }
+
+    // The snake's length over the run, evenly spaced in time.
+    pub fn lengths(&self) -> &[usize] {
+        &self.lengths
+    }
+}
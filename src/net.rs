@@ -0,0 +1,289 @@
+use snake_2d::{Direction, Rules};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+pub const DEFAULT_PORT: u16 = 7878;
+const HEADER: &str = "snake_2d-net 2";
+// Each input is scheduled this many ticks ahead, which hides up to that much
+// round-trip latency before the simulation has to wait for the other side.
+const INPUT_DELAY: usize = 4;
+
+enum Message {
+    Input(u32, usize, Option<Direction>),
+    Checksum(u32, usize, u32),
+    Restart,
+    Start(u64),
+}
+
+// A lockstep link to the other player. Both ends run the same seeded game and
+// only exchange one input per player per tick, tagged with the round so
+// inputs from a finished game are never applied to the next one.
+pub struct Connection {
+    pub local: usize,
+    pub disconnected: bool,
+    pub restart_requested: bool,
+    // The first tick this round whose checksums differ on the two sides.
+    pub desynced: Option<usize>,
+    stream: TcpStream,
+    incoming: Receiver<Message>,
+    round: u32,
+    sent: usize,
+    local_inputs: HashMap<usize, Option<Direction>>,
+    // Keyed by round too: the host may start sending inputs for the next
+    // round before this side has seen its start message.
+    remote_inputs: HashMap<(u32, usize), Option<Direction>>,
+    remote_restart: bool,
+    start: Option<u64>,
+    // Checksums of ticks the other side hasn't sent its own for yet, from
+    // this side and from the other.
+    local_checksums: HashMap<usize, u32>,
+    remote_checksums: HashMap<(u32, usize), u32>,
+    checked: bool,
+}
+
+// Waits for a player to join and sends them the rules, seed included.
+pub fn host(port: u16, rules: &Rules) -> io::Result<Connection> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("Waiting for a player to connect on port {}...", port);
+    let (mut stream, address) = listener.accept()?;
+    println!("{} joined", address);
+
+    let rules = ron::to_string(rules).map_err(|e| io::Error::other(e.to_string()))?;
+    writeln!(stream, "{}\n{}", HEADER, rules)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    Connection::new(stream, reader, 0)
+}
+
+pub fn connect(address: &str) -> io::Result<(Connection, Rules)> {
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    };
+    let stream = TcpStream::connect(&address)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != HEADER {
+        return Err(invalid(format!("{} is not a snake_2d host", address)));
+    }
+    line.clear();
+    reader.read_line(&mut line)?;
+    let rules = ron::from_str(line.trim_end())
+        .map_err(|e| invalid(format!("Invalid rules from host: {}", e)))?;
+    Ok((Connection::new(stream, reader, 1)?, rules))
+}
+
+impl Connection {
+    fn new(
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+        local: usize,
+    ) -> io::Result<Connection> {
+        stream.set_nodelay(true)?;
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let message = match line.ok().and_then(|line| parse(&line)) {
+                    Some(message) => message,
+                    None => break,
+                };
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Connection {
+            local,
+            disconnected: false,
+            restart_requested: false,
+            desynced: None,
+            stream,
+            incoming,
+            round: 0,
+            sent: 0,
+            local_inputs: HashMap::new(),
+            remote_inputs: HashMap::new(),
+            remote_restart: false,
+            start: None,
+            local_checksums: HashMap::new(),
+            remote_checksums: HashMap::new(),
+            checked: false,
+        })
+    }
+
+    pub fn poll(&mut self) {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(Message::Input(round, tick, dir)) => {
+                    if round >= self.round {
+                        self.remote_inputs.insert((round, tick), dir);
+                    }
+                }
+                Ok(Message::Checksum(round, tick, checksum)) => {
+                    if round >= self.round {
+                        self.remote_checksums.insert((round, tick), checksum);
+                    }
+                }
+                Ok(Message::Restart) => self.remote_restart = true,
+                Ok(Message::Start(seed)) => self.start = Some(seed),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    // The direction each player turns on `tick`, indexed by player, or None
+    // while the other side's input has not arrived yet. Sends the next queued
+    // local direction for tick + INPUT_DELAY on the way.
+    pub fn inputs(
+        &mut self,
+        tick: usize,
+        pending: &mut VecDeque<Direction>,
+    ) -> Option<[Option<Direction>; 2]> {
+        self.poll();
+        let target = tick + INPUT_DELAY;
+        if self.sent <= target {
+            let dir = pending.pop_front();
+            let line = format!("i {} {} {}", self.round, target, direction_char(dir));
+            self.send(&line);
+            self.local_inputs.insert(target, dir);
+            self.sent = target + 1;
+        }
+
+        if tick < INPUT_DELAY {
+            return Some([None, None]);
+        }
+        let remote = self.remote_inputs.remove(&(self.round, tick))?;
+        let local = self.local_inputs.remove(&tick).flatten();
+        let mut inputs = [None, None];
+        inputs[self.local] = local;
+        inputs[1 - self.local] = remote;
+        Some(inputs)
+    }
+
+    // Sends the game's checksum after `tick` and holds it against the other
+    // side's, setting `desynced` the first time they differ.
+    pub fn checksum(&mut self, tick: usize, checksum: u32) {
+        self.poll();
+        self.send(&format!("c {} {} {:08x}", self.round, tick, checksum));
+        self.local_checksums.insert(tick, checksum);
+        let round = self.round;
+        let remote = &mut self.remote_checksums;
+        let mut matched: Vec<(usize, bool)> = self
+            .local_checksums
+            .iter()
+            .filter_map(|(&tick, &local)| {
+                let remote = remote.remove(&(round, tick))?;
+                Some((tick, remote == local))
+            })
+            .collect();
+        matched.sort_unstable();
+        for (tick, same) in matched {
+            self.local_checksums.remove(&tick);
+            if !same && !self.checked {
+                self.checked = true;
+                self.desynced = Some(tick);
+            }
+        }
+    }
+
+    pub fn request_restart(&mut self) {
+        if !self.restart_requested {
+            self.restart_requested = true;
+            self.send("restart");
+        }
+    }
+
+    // The seed for the next game once both players asked for a restart. The
+    // host picks it and tells the client.
+    pub fn next_game(&mut self) -> Option<u64> {
+        self.poll();
+        let seed = if self.local == 0 {
+            if !(self.restart_requested && self.remote_restart) {
+                return None;
+            }
+            let seed = rand::random();
+            self.send(&format!("start {}", seed));
+            seed
+        } else {
+            self.start.take()?
+        };
+
+        self.round += 1;
+        self.sent = 0;
+        self.local_inputs.clear();
+        self.local_checksums.clear();
+        self.checked = false;
+        let round = self.round;
+        self.remote_inputs.retain(|&(r, _), _| r >= round);
+        self.remote_checksums.retain(|&(r, _), _| r >= round);
+        self.restart_requested = false;
+        self.remote_restart = false;
+        Some(seed)
+    }
+
+    fn send(&mut self, line: &str) {
+        if writeln!(self.stream, "{}", line).is_err() {
+            self.disconnected = true;
+        }
+    }
+}
+
+fn parse(line: &str) -> Option<Message> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match fields.as_slice() {
+        ["i", round, tick, dir] => {
+            let dir = match *dir {
+                "-" => None,
+                dir => Some(char_direction(dir.chars().next()?)?),
+            };
+            Some(Message::Input(round.parse().ok()?, tick.parse().ok()?, dir))
+        }
+        ["c", round, tick, checksum] => Some(Message::Checksum(
+            round.parse().ok()?,
+            tick.parse().ok()?,
+            u32::from_str_radix(checksum, 16).ok()?,
+        )),
+        ["restart"] => Some(Message::Restart),
+        ["start", seed] => Some(Message::Start(seed.parse().ok()?)),
+        _ => None,
+    }
+}
+
+fn direction_char(dir: Option<Direction>) -> char {
+    match dir {
+        Some(Direction::Up) => 'U',
+        Some(Direction::Down) => 'D',
+        Some(Direction::Left) => 'L',
+        Some(Direction::Right) => 'R',
+        Some(Direction::UpLeft) => 'Q',
+        Some(Direction::UpRight) => 'E',
+        Some(Direction::DownLeft) => 'Z',
+        Some(Direction::DownRight) => 'C',
+        None => '-',
+    }
+}
+
+fn char_direction(ch: char) -> Option<Direction> {
+    match ch {
+        'U' => Some(Direction::Up),
+        'D' => Some(Direction::Down),
+        'L' => Some(Direction::Left),
+        'R' => Some(Direction::Right),
+        'Q' => Some(Direction::UpLeft),
+        'E' => Some(Direction::UpRight),
+        'Z' => Some(Direction::DownLeft),
+        'C' => Some(Direction::DownRight),
+        _ => None,
+    }
+}
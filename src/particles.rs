@@ -0,0 +1,117 @@
+use crate::render::Layout;
+use graphics::Transformed;
+use opengl_graphics::GlGraphics;
+use piston::input::RenderArgs;
+use rand::Rng;
+use snake_2d::accessibility::AccessibilityOptions;
+use snake_2d::{Board, Pos};
+use std::f64::consts::PI;
+
+const PARTICLE_SIZE: f64 = 0.2;
+// Seconds between fireworks over a won game.
+const FIREWORK_EVERY: f64 = 0.3;
+
+// Positions and velocities are in cells, so bursts scale with the board.
+struct Particle {
+    pos: [f64; 2],
+    velocity: [f64; 2],
+    lifetime: f64,
+    age: f64,
+    color: [f32; 4],
+}
+
+#[derive(Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    // Until the next firework.
+    firework: f64,
+}
+
+impl Particles {
+    pub fn count(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn burst(
+        &mut self,
+        options: &AccessibilityOptions,
+        part: Pos,
+        color: [f32; 4],
+        count: usize,
+        speed: f64,
+    ) {
+        if !options.particles {
+            return;
+        }
+        let speed = options.effect_speed(speed);
+        let mut rng = rand::thread_rng();
+        let center = [f64::from(part.x) + 0.5, f64::from(part.y) + 0.5];
+        for _ in 0..count {
+            let angle = rng.gen_range(0.0, 2.0 * PI);
+            let speed = speed * rng.gen_range(0.3, 1.0);
+            self.particles.push(Particle {
+                pos: center,
+                velocity: [angle.cos() * speed, angle.sin() * speed],
+                lifetime: rng.gen_range(0.3, 0.8),
+                age: 0.0,
+                color,
+            });
+        }
+    }
+
+    // Call on every update while the won game is shown: bursts in turn
+    // over random cells of the board, in one of `colors` each.
+    pub fn fireworks(
+        &mut self,
+        options: &AccessibilityOptions,
+        board: Board,
+        colors: &[[f32; 4]],
+        dt: f64,
+    ) {
+        self.firework -= dt;
+        if self.firework > 0.0 || colors.is_empty() {
+            return;
+        }
+        self.firework = FIREWORK_EVERY;
+        let mut rng = rand::thread_rng();
+        let part = Pos::new(
+            rng.gen_range(0, board.columns.max(1)),
+            rng.gen_range(0, board.rows.max(1)),
+        );
+        let color = colors[rng.gen_range(0, colors.len())];
+        self.burst(options, part, color, 30, 5.0);
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.pos[0] += particle.velocity[0] * dt;
+            particle.pos[1] += particle.velocity[1] * dt;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn render(&self, gl: &mut GlGraphics, args: &RenderArgs, layout: &Layout) {
+        let cell_size = layout.cell_size;
+        gl.draw(args.viewport(), |c, gl| {
+            let transform = c.transform.trans(layout.origin[0], layout.origin[1]);
+            for particle in &self.particles {
+                let life = 1.0 - particle.age / particle.lifetime;
+                let size = PARTICLE_SIZE * life * cell_size;
+                let mut color = particle.color;
+                color[3] *= life as f32;
+                let rect = [
+                    particle.pos[0] * cell_size - size / 2.0,
+                    particle.pos[1] * cell_size - size / 2.0,
+                    size,
+                    size,
+                ];
+                graphics::rectangle(color, rect, transform, gl);
+            }
+        });
+    }
+}
@@ -0,0 +1,104 @@
+use crate::ai::{Difficulty, Tuning};
+use crate::food::Effects;
+use crate::game::DeathCause;
+use crate::i18n::Locale;
+use crate::rules::Rules;
+use crate::score::Score;
+use crate::snake::{Direction, Snake};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub const MAX_QUEUED_INPUTS: usize = 3;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub snake: Snake,
+    pub score: Score,
+    pub effects: Effects,
+    pub death: Option<DeathCause>,
+    #[serde(default)]
+    pub lives: u32,
+    pub ai: Option<Difficulty>,
+    // Where an adaptive AI has got to; its difficulty's own until the score
+    // first drifts apart.
+    #[serde(default)]
+    pub tuning: Option<Tuning>,
+    // Whether diagonal turns are taken, from `Rules::diagonals`.
+    #[serde(default)]
+    pub diagonals: bool,
+    #[serde(skip)]
+    inputs: VecDeque<Direction>,
+}
+
+impl Player {
+    pub fn new(index: usize, snake: Snake, rules: &Rules) -> Player {
+        Player {
+            snake,
+            score: Score::init(),
+            effects: Effects::default(),
+            death: None,
+            lives: rules.lives,
+            ai: if index > 0 && rules.ai_opponent {
+                Some(rules.ai_difficulty)
+            } else {
+                None
+            },
+            tuning: None,
+            diagonals: rules.diagonals,
+            inputs: VecDeque::new(),
+        }
+    }
+
+    pub fn alive(&self) -> bool {
+        self.death.is_none()
+    }
+
+    pub fn name(&self, index: usize, locale: &Locale) -> String {
+        match self.ai {
+            Some(_) => locale.get("player.ai").to_string(),
+            None => locale.format("player.number", &[("number", &(index + 1))]),
+        }
+    }
+
+    pub fn respawn(&mut self, snake: Snake, invulnerable_ticks: u32) {
+        self.snake = snake;
+        self.lives = self.lives.saturating_sub(1);
+        self.effects.invulnerable = invulnerable_ticks;
+        self.inputs.clear();
+    }
+
+    pub fn steer(&mut self, dir: Direction) {
+        if self.ai.is_some()
+            || self.inputs.len() >= MAX_QUEUED_INPUTS
+            || (dir.is_diagonal() && !self.diagonals)
+        {
+            return;
+        }
+
+        let last_direction = self.inputs.back().copied().unwrap_or(self.snake.dir);
+        if dir != last_direction && dir != last_direction.opposite() {
+            self.inputs.push_back(dir);
+        }
+    }
+
+    // Takes back the newest queued turn if it is `dir`.
+    pub fn retract(&mut self, dir: Direction) -> bool {
+        if self.inputs.back() == Some(&dir) {
+            self.inputs.pop_back();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Turns waiting for the next ticks.
+    pub fn queued_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn next_input(&mut self) {
+        if let Some(dir) = self.inputs.pop_front() {
+            self.snake.dir = dir;
+        }
+    }
+}
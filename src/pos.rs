@@ -0,0 +1,87 @@
+use crate::board::Board;
+use crate::snake::Direction;
+use serde::{Deserialize, Serialize};
+use std::ops::Add;
+
+// A cell on the board. Moving by a direction may leave the board; use
+// `wrapped` or `clamped` to bring the result back on it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Pos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Pos {
+    pub const fn new(x: i32, y: i32) -> Pos {
+        Pos { x, y }
+    }
+
+    // Comes back in on the opposite edge, as on a wrapping board.
+    pub fn wrapped(self, board: Board) -> Pos {
+        let (x, y) = board.wrap(self.x, self.y);
+        Pos { x, y }
+    }
+
+    // The nearest cell on the board, for edges that stop rather than wrap.
+    // A board without cells has no nearest one; (0, 0) comes back instead.
+    pub fn clamped(self, board: Board) -> Pos {
+        Pos {
+            x: self.x.clamp(0, (board.columns - 1).max(0)),
+            y: self.y.clamp(0, (board.rows - 1).max(0)),
+        }
+    }
+
+    pub fn on(self, board: Board) -> bool {
+        board.contains(self.x, self.y)
+    }
+
+    // Manhattan distance, i.e. the number of moves between the cells on a
+    // board without wrapping.
+    pub fn distance(self, other: Pos) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn neighbours(self) -> [Pos; 4] {
+        [
+            self + Direction::Up,
+            self + Direction::Down,
+            self + Direction::Left,
+            self + Direction::Right,
+        ]
+    }
+}
+
+impl Add<Direction> for Pos {
+    type Output = Pos;
+
+    fn add(self, dir: Direction) -> Pos {
+        let (dx, dy) = dir.offset();
+        Pos {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping_comes_back_in_on_the_opposite_edge() {
+        let board = Board::new(5, 4);
+        assert_eq!(Pos::new(-1, 2).wrapped(board), Pos::new(4, 2));
+        assert_eq!(Pos::new(5, 4).wrapped(board), Pos::new(0, 0));
+        assert_eq!(Pos::new(2, 3).wrapped(board), Pos::new(2, 3));
+    }
+
+    #[test]
+    fn clamping_stops_at_the_edge_cells() {
+        let board = Board::new(5, 4);
+        assert_eq!(Pos::new(-1, 2).clamped(board), Pos::new(0, 2));
+        assert_eq!(Pos::new(5, 4).clamped(board), Pos::new(4, 3));
+        assert_eq!(Pos::new(-3, -3).clamped(board), Pos::new(0, 0));
+        assert_eq!(Pos::new(4, 3).clamped(board), Pos::new(4, 3));
+        assert_eq!(Pos::new(7, 7).clamped(Board::new(0, 0)), Pos::new(0, 0));
+    }
+}
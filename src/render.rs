@@ -0,0 +1,476 @@
+use crate::assets::{Sprite, Sprites};
+use crate::theme::SnakeStyle;
+use graphics::rectangle::Rectangle;
+use graphics::{Context, Transformed};
+use opengl_graphics::GlGraphics;
+use piston::input::RenderArgs;
+use serde::{Deserialize, Serialize};
+use snake_2d::{Direction, Pos, Snake};
+
+const EYE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const HEAD_SIZE: f64 = 0.9;
+const BODY_SIZE: f64 = 0.75;
+const TAIL_SIZE: f64 = 0.45;
+const EYE_SIZE: f64 = 0.18;
+const PORTAL_SIZE: f64 = 0.8;
+const HAZARD_SIZE: f64 = 0.9;
+const MARK_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const MARK_SIZE: f64 = 0.35;
+// The edge warning strip's thickness, in cells.
+const EDGE_STRIP: f64 = 0.3;
+// The walls around the board and the band above them for the HUD, in
+// window pixels.
+const BORDER: f64 = 6.0;
+pub const HUD_HEIGHT: f64 = 26.0;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardStyle {
+    Plain,
+    Grid,
+    Checkerboard,
+}
+
+impl BoardStyle {
+    pub fn key(self) -> &'static str {
+        match self {
+            BoardStyle::Plain => "board.plain",
+            BoardStyle::Grid => "board.grid",
+            BoardStyle::Checkerboard => "board.checkerboard",
+        }
+    }
+
+    pub fn next(self) -> BoardStyle {
+        match self {
+            BoardStyle::Plain => BoardStyle::Grid,
+            BoardStyle::Grid => BoardStyle::Checkerboard,
+            BoardStyle::Checkerboard => BoardStyle::Plain,
+        }
+    }
+}
+
+// Fewer, bigger cells for players who find the default board hard to read.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Zoom {
+    Normal,
+    Large,
+    Huge,
+}
+
+impl Zoom {
+    pub fn key(self) -> &'static str {
+        match self {
+            Zoom::Normal => "zoom.normal",
+            Zoom::Large => "zoom.large",
+            Zoom::Huge => "zoom.huge",
+        }
+    }
+
+    pub fn next(self) -> Zoom {
+        match self {
+            Zoom::Normal => Zoom::Large,
+            Zoom::Large => Zoom::Huge,
+            Zoom::Huge => Zoom::Normal,
+        }
+    }
+
+    // The board size to play on instead of `size`.
+    pub fn grid(self, size: snake_2d::Board) -> snake_2d::Board {
+        let (numerator, denominator) = match self {
+            Zoom::Normal => (1, 1),
+            Zoom::Large => (3, 4),
+            Zoom::Huge => (1, 2),
+        };
+        snake_2d::Board::new(
+            size.columns * numerator / denominator,
+            size.rows * numerator / denominator,
+        )
+        .validated()
+    }
+}
+
+// Shapes drawn over cells so they can be told apart without color.
+#[derive(Clone, Copy)]
+pub enum Mark {
+    Dot,
+    Cross,
+}
+
+// Where the board sits in the window: `origin` is its top-left corner and
+// `size` its width and height, both in window pixels.
+#[derive(Clone, Copy)]
+pub struct Layout {
+    pub cell_size: f64,
+    pub origin: [f64; 2],
+    pub size: [f64; 2],
+}
+
+impl Layout {
+    // The board as large as it fits inside its border walls, below the HUD.
+    pub fn fit(size: [f64; 2], scale: f64, columns: i32, rows: i32) -> Layout {
+        let (columns, rows) = (f64::from(columns), f64::from(rows));
+        let room = [size[0] - 2.0 * BORDER, size[1] - 2.0 * BORDER - HUD_HEIGHT];
+        let cell_pixels = ((room[0] / columns).min(room[1] / rows) * scale).floor();
+        let cell_size = cell_pixels.max(1.0) / scale;
+        let board = [cell_size * columns, cell_size * rows];
+        Layout {
+            cell_size,
+            origin: [
+                BORDER + (room[0] - board[0]) / 2.0,
+                HUD_HEIGHT + BORDER + (room[1] - board[1]) / 2.0,
+            ],
+            size: board,
+        }
+    }
+
+    // The window that fits a board of `cell_size` pixel cells exactly.
+    pub fn window_size(columns: i32, rows: i32, cell_size: i32) -> [f64; 2] {
+        [
+            f64::from(columns * cell_size) + 2.0 * BORDER,
+            f64::from(rows * cell_size) + 2.0 * BORDER + HUD_HEIGHT,
+        ]
+    }
+
+    // A window position in cells, e.g. [2.5, 0.5] for the middle of the
+    // third cell in the top row.
+    pub fn cell_at(&self, point: [f64; 2]) -> [f64; 2] {
+        [
+            (point[0] - self.origin[0]) / self.cell_size,
+            (point[1] - self.origin[1]) / self.cell_size,
+        ]
+    }
+}
+
+pub fn scale_factor(args: &RenderArgs) -> f64 {
+    if args.window_size[0] > 0.0 {
+        f64::from(args.draw_size[0]) / args.window_size[0]
+    } else {
+        1.0
+    }
+}
+
+pub struct Board<'a> {
+    pub layout: Layout,
+    pub background: [f32; 4],
+    pub sprites: Option<&'a Sprites>,
+    pub grid_line: [f32; 4],
+    pub style: BoardStyle,
+    // Seconds of play, for the snakes' skin animation.
+    pub time: f64,
+}
+
+impl<'a> Board<'a> {
+    pub fn background(&self, gl: &mut GlGraphics, args: &RenderArgs, columns: i32, rows: i32) {
+        let cell_size = self.layout.cell_size;
+        let (width, height) = (f64::from(columns) * cell_size, f64::from(rows) * cell_size);
+
+        self.draw(gl, args, |c, gl| {
+            graphics::rectangle(self.background, [0.0, 0.0, width, height], c.transform, gl);
+        });
+        self.draw(gl, args, |c, gl| match self.style {
+            BoardStyle::Plain => {}
+            BoardStyle::Grid => {
+                let line = graphics::Line::new(self.grid_line, 0.5);
+                for x in 1..columns {
+                    let x = f64::from(x) * cell_size;
+                    line.draw([x, 0.0, x, height], &c.draw_state, c.transform, gl);
+                }
+                for y in 1..rows {
+                    let y = f64::from(y) * cell_size;
+                    line.draw([0.0, y, width, y], &c.draw_state, c.transform, gl);
+                }
+            }
+            BoardStyle::Checkerboard => {
+                for x in 0..columns {
+                    for y in (x % 2..rows).step_by(2) {
+                        let rect = centered([f64::from(x), f64::from(y)], 1.0, cell_size);
+                        graphics::rectangle(self.grid_line, rect, c.transform, gl);
+                    }
+                }
+            }
+        });
+    }
+
+    // Covers the outer `rings` cells all around the board.
+    pub fn rings(
+        &self,
+        gl: &mut GlGraphics,
+        args: &RenderArgs,
+        [columns, rows]: [i32; 2],
+        rings: i32,
+        color: [f32; 4],
+    ) {
+        if rings <= 0 {
+            return;
+        }
+        let cell_size = self.layout.cell_size;
+        let (width, height) = (f64::from(columns) * cell_size, f64::from(rows) * cell_size);
+        let band = f64::from(rings) * cell_size;
+        self.draw(gl, args, |c, gl| {
+            for rect in &[
+                [0.0, 0.0, width, band],
+                [0.0, height - band, width, band],
+                [0.0, band, band, height - 2.0 * band],
+                [width - band, band, band, height - 2.0 * band],
+            ] {
+                graphics::rectangle(color, *rect, c.transform, gl);
+            }
+        });
+    }
+
+    // The walls around the board, just outside its cells.
+    pub fn border(&self, gl: &mut GlGraphics, args: &RenderArgs, color: [f32; 4]) {
+        let [width, height] = self.layout.size;
+        self.draw(gl, args, |c, gl| {
+            for rect in &[
+                [-BORDER, -BORDER, width + 2.0 * BORDER, BORDER],
+                [-BORDER, height, width + 2.0 * BORDER, BORDER],
+                [-BORDER, 0.0, BORDER, height],
+                [width, 0.0, BORDER, height],
+            ] {
+                graphics::rectangle(color, *rect, c.transform, gl);
+            }
+        });
+    }
+
+    // A strip along the inside of the edge `side` of the arena left after
+    // `rings` have closed.
+    pub fn edge(
+        &self,
+        gl: &mut GlGraphics,
+        args: &RenderArgs,
+        [columns, rows]: [i32; 2],
+        rings: i32,
+        side: Direction,
+        color: [f32; 4],
+    ) {
+        let cell_size = self.layout.cell_size;
+        let inset = f64::from(rings) * cell_size;
+        let width = f64::from(columns) * cell_size - 2.0 * inset;
+        let height = f64::from(rows) * cell_size - 2.0 * inset;
+        let strip = EDGE_STRIP * cell_size;
+        let rect = match side {
+            Direction::Up => [inset, inset, width, strip],
+            Direction::Down => [inset, inset + height - strip, width, strip],
+            Direction::Left => [inset, inset, strip, height],
+            Direction::Right => [inset + width - strip, inset, strip, height],
+            // The warning only ever names a side.
+            _ => return,
+        };
+        self.draw(gl, args, |c, gl| {
+            graphics::rectangle(color, rect, c.transform, gl);
+        });
+    }
+
+    pub fn cell(
+        &self,
+        gl: &mut GlGraphics,
+        args: &RenderArgs,
+        part: Pos,
+        sprite: Sprite,
+        color: [f32; 4],
+    ) {
+        let rect = centered(
+            [f64::from(part.x), f64::from(part.y)],
+            1.0,
+            self.layout.cell_size,
+        );
+
+        self.draw(gl, args, |c, gl| match self.sprites {
+            Some(sprites) => sprites.draw(sprite, color, rect, 0.0, c, gl),
+            None => graphics::rectangle(color, rect, c.transform, gl),
+        });
+    }
+
+    pub fn mark(&self, gl: &mut GlGraphics, args: &RenderArgs, part: Pos, mark: Mark) {
+        let cell_size = self.layout.cell_size;
+        let pos = [f64::from(part.x), f64::from(part.y)];
+        self.draw(gl, args, |c, gl| match mark {
+            Mark::Dot => {
+                let rect = centered(pos, MARK_SIZE, cell_size);
+                graphics::ellipse(MARK_COLOR, rect, c.transform, gl);
+            }
+            Mark::Cross => {
+                let [x, y, size, _] = centered(pos, 1.0 - MARK_SIZE, cell_size);
+                let line = graphics::Line::new(MARK_COLOR, cell_size / 16.0);
+                line.draw([x, y, x + size, y + size], &c.draw_state, c.transform, gl);
+                line.draw([x + size, y, x, y + size], &c.draw_state, c.transform, gl);
+            }
+        });
+    }
+
+    pub fn portal(&self, gl: &mut GlGraphics, args: &RenderArgs, x: i32, y: i32, color: [f32; 4]) {
+        let rect = centered(
+            [f64::from(x), f64::from(y)],
+            PORTAL_SIZE,
+            self.layout.cell_size,
+        );
+        self.draw(gl, args, |c, gl| {
+            graphics::ellipse(color, rect, c.transform, gl)
+        });
+    }
+
+    // A diamond, unlike anything else on the board.
+    pub fn hazard(&self, gl: &mut GlGraphics, args: &RenderArgs, part: Pos, color: [f32; 4]) {
+        let cell_size = self.layout.cell_size;
+        let [x, y, size, _] = centered(
+            [f64::from(part.x), f64::from(part.y)],
+            HAZARD_SIZE,
+            cell_size,
+        );
+        let half = size / 2.0;
+        let corners = [
+            [x + half, y],
+            [x + size, y + half],
+            [x + half, y + size],
+            [x, y + half],
+        ];
+        self.draw(gl, args, |c, gl| {
+            graphics::polygon(color, &corners, c.transform, gl)
+        });
+    }
+
+    pub fn snake(
+        &self,
+        gl: &mut GlGraphics,
+        args: &RenderArgs,
+        snake: &Snake,
+        previous: &[Pos],
+        progress: f64,
+        style: SnakeStyle,
+    ) {
+        let positions: Vec<[f64; 2]> = snake
+            .segments()
+            .enumerate()
+            .map(|(i, part)| match previous.get(i) {
+                Some(&from) if part.distance(from) == 1 => [
+                    f64::from(from.x) + f64::from(part.x - from.x) * progress,
+                    f64::from(from.y) + f64::from(part.y - from.y) * progress,
+                ],
+                _ => [f64::from(part.x), f64::from(part.y)],
+            })
+            .collect();
+        let colors: Vec<[f32; 4]> = (0..positions.len())
+            .map(|i| style.segment(i, positions.len(), self.time))
+            .collect();
+
+        self.draw(gl, args, |c, gl| match self.sprites {
+            Some(sprites) => self.sprite_snake(sprites, &positions, snake.dir, &colors, c, gl),
+            None => self.shape_snake(&positions, snake.dir, &colors, c, gl),
+        });
+    }
+
+    fn sprite_snake(
+        &self,
+        sprites: &Sprites,
+        positions: &[[f64; 2]],
+        dir: Direction,
+        colors: &[[f32; 4]],
+        c: &Context,
+        gl: &mut GlGraphics,
+    ) {
+        let last = positions.len() - 1;
+        for (i, &pos) in positions.iter().enumerate().rev() {
+            let (sprite, rotation) = match i {
+                0 => (Sprite::Head, rotation(dir)),
+                i if i == last => (Sprite::Tail, 0.0),
+                _ => (Sprite::Body, 0.0),
+            };
+            let rect = centered(pos, 1.0, self.layout.cell_size);
+            sprites.draw(sprite, colors[i], rect, rotation, c, gl);
+        }
+    }
+
+    fn shape_snake(
+        &self,
+        positions: &[[f64; 2]],
+        dir: Direction,
+        colors: &[[f32; 4]],
+        c: &Context,
+        gl: &mut GlGraphics,
+    ) {
+        let cell_size = self.layout.cell_size;
+        let last = positions.len() - 1;
+
+        for (i, pair) in positions.windows(2).enumerate() {
+            let (a, b) = (pair[0], pair[1]);
+            if (a[0] - b[0]).abs() + (a[1] - b[1]).abs() > 1.0 {
+                continue;
+            }
+            let inset = (1.0 - BODY_SIZE) / 2.0;
+            let bridge = [
+                (a[0].min(b[0]) + inset) * cell_size,
+                (a[1].min(b[1]) + inset) * cell_size,
+                ((a[0] - b[0]).abs() + BODY_SIZE) * cell_size,
+                ((a[1] - b[1]).abs() + BODY_SIZE) * cell_size,
+            ];
+            graphics::rectangle(colors[i + 1], bridge, c.transform, gl);
+        }
+
+        for (i, &pos) in positions.iter().enumerate().rev() {
+            let size = match i {
+                0 => HEAD_SIZE,
+                i if i == last => TAIL_SIZE,
+                _ => BODY_SIZE,
+            };
+            let rect = centered(pos, size, cell_size);
+            Rectangle::new_round(colors[i], size * cell_size / 4.0).draw(
+                rect,
+                &c.draw_state,
+                c.transform,
+                gl,
+            );
+        }
+
+        let (dx, dy) = dir.offset();
+        let length = f64::from(dx * dx + dy * dy).sqrt();
+        let forward = [f64::from(dx) / length, f64::from(dy) / length];
+        let side = [-forward[1], forward[0]];
+        for &offset in [-0.22, 0.22].iter() {
+            let eye = [
+                positions[0][0] + forward[0] * 0.18 + side[0] * offset,
+                positions[0][1] + forward[1] * 0.18 + side[1] * offset,
+            ];
+            graphics::ellipse(
+                EYE_COLOR,
+                centered(eye, EYE_SIZE, cell_size),
+                c.transform,
+                gl,
+            );
+        }
+    }
+
+    fn draw<F>(&self, gl: &mut GlGraphics, args: &RenderArgs, f: F)
+    where
+        F: FnOnce(&Context, &mut GlGraphics),
+    {
+        let origin = self.layout.origin;
+        gl.draw(args.viewport(), |c, gl| {
+            f(&c.trans(origin[0], origin[1]), gl)
+        });
+    }
+}
+
+fn rotation(dir: Direction) -> f64 {
+    match dir {
+        Direction::Right => 0.0,
+        Direction::Down => 90.0,
+        Direction::Left => 180.0,
+        Direction::Up => 270.0,
+        Direction::DownRight => 45.0,
+        Direction::DownLeft => 135.0,
+        Direction::UpLeft => 225.0,
+        Direction::UpRight => 315.0,
+    }
+}
+
+fn centered(pos: [f64; 2], size: f64, cell_size: f64) -> [f64; 4] {
+    let inset = (1.0 - size) / 2.0;
+    [
+        (pos[0] + inset) * cell_size,
+        (pos[1] + inset) * cell_size,
+        size * cell_size,
+        size * cell_size,
+    ]
+}
@@ -0,0 +1,379 @@
+use crate::ai::Difficulty;
+use crate::board::Board;
+use crate::event::GameEvent;
+use crate::rules::{BoardMode, Rules, Spawn};
+use crate::snake::Direction;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "snake_2d-replay 7";
+
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub board: Board,
+    pub board_mode: BoardMode,
+    pub level: Option<String>,
+    pub ai_difficulty: Option<Difficulty>,
+    pub update_speed: u64,
+    pub max_speed: u64,
+    pub speed_up_every: u32,
+    pub food_count: usize,
+    pub lives: u32,
+    pub time_limit: Option<u64>,
+    #[serde(default)]
+    pub endless: bool,
+    #[serde(default)]
+    pub shrinking: bool,
+    #[serde(default)]
+    pub coop: bool,
+    #[serde(default)]
+    pub food_distance: u32,
+    #[serde(default = "tail_chase")]
+    pub tail_chase: bool,
+    #[serde(default = "corner_spawn")]
+    pub spawn: Spawn,
+    #[serde(default)]
+    pub diagonals: bool,
+    pub inputs: Vec<Vec<Direction>>,
+    // `Game::checksum` at the start of each tick, when the game kept them.
+    #[serde(default)]
+    pub checksums: Vec<u32>,
+    // The final score, kept with personal bests.
+    #[serde(default)]
+    pub score: Option<u32>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, rules: &Rules) -> Replay {
+        Replay {
+            seed,
+            board: rules.board,
+            board_mode: rules.board_mode,
+            level: rules.level.clone(),
+            ai_difficulty: if rules.ai_opponent {
+                Some(rules.ai_difficulty)
+            } else {
+                None
+            },
+            update_speed: rules.update_speed,
+            max_speed: rules.max_speed,
+            speed_up_every: rules.speed_up_every,
+            food_count: rules.food_count,
+            lives: rules.lives,
+            time_limit: rules.time_limit,
+            endless: rules.endless,
+            shrinking: rules.shrinking,
+            coop: rules.coop,
+            food_distance: rules.food_distance,
+            tail_chase: rules.tail_chase,
+            spawn: rules.spawn,
+            diagonals: rules.diagonals,
+            inputs: vec![Vec::new(); rules.players],
+            checksums: Vec::new(),
+            score: None,
+        }
+    }
+
+    pub fn last_direction(&self, player: usize) -> Option<Direction> {
+        self.inputs.get(player)?.last().copied()
+    }
+
+    // Records one tick: the new direction for players that turned and the
+    // previous one for the rest.
+    pub fn observe(&mut self, events: &[GameEvent]) {
+        let mut turns = vec![None; self.inputs.len()];
+        for event in events {
+            if let GameEvent::DirectionChanged { player, dir } = *event {
+                turns[player] = Some(dir);
+            }
+        }
+        for (inputs, turn) in self.inputs.iter_mut().zip(turns) {
+            if let Some(dir) = turn.or_else(|| inputs.last().copied()) {
+                inputs.push(dir);
+            }
+        }
+    }
+
+    pub fn direction(&self, player: usize, tick: usize) -> Option<Direction> {
+        self.inputs.get(player)?.get(tick).copied()
+    }
+
+    pub fn apply_to(&self, rules: &mut Rules) {
+        rules.seed = Some(self.seed);
+        rules.board = self.board;
+        rules.board_mode = self.board_mode;
+        rules.level = self.level.clone();
+        rules.players = self.inputs.len();
+        rules.ai_opponent = self.ai_difficulty.is_some();
+        if let Some(difficulty) = self.ai_difficulty {
+            rules.ai_difficulty = difficulty;
+        }
+        rules.update_speed = self.update_speed;
+        rules.max_speed = self.max_speed;
+        rules.speed_up_every = self.speed_up_every;
+        rules.food_count = self.food_count;
+        rules.lives = self.lives;
+        rules.time_limit = self.time_limit;
+        rules.endless = self.endless;
+        rules.shrinking = self.shrinking;
+        rules.coop = self.coop;
+        rules.food_distance = self.food_distance;
+        rules.tail_chase = self.tail_chase;
+        rules.spawn = self.spawn;
+        rules.diagonals = self.diagonals;
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.text())
+    }
+
+    // FNV-1a of the saved form, so the same run hashes the same everywhere.
+    pub fn hash(&self) -> u64 {
+        self.text()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    // Whether a game with these rules plays out like this replay, apart
+    // from the seed.
+    pub fn matches(&self, rules: &Rules) -> bool {
+        let other = Replay::new(self.seed, rules);
+        other.settings() == self.settings() && other.inputs.len() == self.inputs.len()
+    }
+
+    fn text(&self) -> String {
+        let mut contents = self.settings();
+        for inputs in &self.inputs {
+            contents.push_str(&format!("inputs {}\n", encode(inputs)));
+        }
+        if !self.checksums.is_empty() {
+            let checksums: Vec<String> = self
+                .checksums
+                .iter()
+                .map(|checksum| format!("{:08x}", checksum))
+                .collect();
+            contents.push_str(&format!("checksums {}\n", checksums.join(" ")));
+        }
+        if let Some(score) = self.score {
+            contents.push_str(&format!("score {}\n", score));
+        }
+        contents
+    }
+
+    fn settings(&self) -> String {
+        let mut contents = format!("{}\n", HEADER);
+        contents.push_str(&format!("seed {}\n", self.seed));
+        contents.push_str(&format!(
+            "grid {} {}\n",
+            self.board.columns, self.board.rows
+        ));
+        contents.push_str(&format!("mode {}\n", self.board_mode.name().to_lowercase()));
+        if let Some(ref level) = self.level {
+            contents.push_str(&format!("level {}\n", level));
+        }
+        if let Some(difficulty) = self.ai_difficulty {
+            contents.push_str(&format!("ai {}\n", difficulty.name().to_lowercase()));
+        }
+        contents.push_str(&format!(
+            "speed {} {} {}\n",
+            self.update_speed, self.max_speed, self.speed_up_every
+        ));
+        contents.push_str(&format!("food {}\n", self.food_count));
+        contents.push_str(&format!("lives {}\n", self.lives));
+        if let Some(limit) = self.time_limit {
+            contents.push_str(&format!("time {}\n", limit));
+        }
+        if self.endless {
+            contents.push_str("endless\n");
+        }
+        if self.shrinking {
+            contents.push_str("shrinking\n");
+        }
+        if self.coop {
+            contents.push_str("coop\n");
+        }
+        if self.food_distance > 0 {
+            contents.push_str(&format!("food_distance {}\n", self.food_distance));
+        }
+        if !self.tail_chase {
+            contents.push_str("no_tail_chase\n");
+        }
+        if self.spawn != Spawn::Corner {
+            contents.push_str(&format!("spawn {}\n", self.spawn.name()));
+        }
+        if self.diagonals {
+            contents.push_str("diagonals\n");
+        }
+        contents
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read replay {}: {}", path.display(), e))?;
+        let mut lines = contents.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(format!("{} is not a snake_2d replay", path.display()));
+        }
+
+        let mut replay = Replay::new(0, &Rules::default());
+        replay.inputs.clear();
+        replay.spawn = Spawn::Corner;
+        for line in lines {
+            let mut fields = line.split_whitespace();
+            let key = fields.next().unwrap_or("");
+            let values: Vec<&str> = fields.collect();
+            let invalid = || format!("Invalid replay line '{}'", line);
+            let number = |i: usize| {
+                values
+                    .get(i)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(invalid)
+            };
+
+            match key {
+                "seed" => replay.seed = number(0)?,
+                "grid" => {
+                    replay.board = Board::new(number(0)? as i32, number(1)? as i32);
+                }
+                "mode" => {
+                    replay.board_mode = match values.first() {
+                        Some(&"walls") => BoardMode::Walls,
+                        Some(&"wrap") => BoardMode::Wrap,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "level" => replay.level = Some(values.join(" ")),
+                "ai" => {
+                    replay.ai_difficulty = Some(match values.first() {
+                        Some(&"easy") => Difficulty::Easy,
+                        Some(&"normal") => Difficulty::Normal,
+                        Some(&"hard") => Difficulty::Hard,
+                        Some(&"adaptive") => Difficulty::Adaptive,
+                        _ => return Err(invalid()),
+                    })
+                }
+                "speed" => {
+                    replay.update_speed = number(0)?;
+                    replay.max_speed = number(1)?;
+                    replay.speed_up_every = number(2)? as u32;
+                }
+                "food" => replay.food_count = number(0)? as usize,
+                "lives" => replay.lives = number(0)? as u32,
+                "time" => replay.time_limit = Some(number(0)?),
+                "endless" => replay.endless = true,
+                "shrinking" => replay.shrinking = true,
+                "coop" => replay.coop = true,
+                "food_distance" => replay.food_distance = number(0)? as u32,
+                "no_tail_chase" => replay.tail_chase = false,
+                "spawn" => {
+                    replay.spawn = values
+                        .first()
+                        .and_then(|name| Spawn::parse(name))
+                        .ok_or_else(invalid)?
+                }
+                "diagonals" => replay.diagonals = true,
+                "score" => replay.score = Some(number(0)? as u32),
+                "inputs" => replay
+                    .inputs
+                    .push(decode(values.first().unwrap_or(&"")).ok_or_else(invalid)?),
+                "checksums" => {
+                    replay.checksums = values
+                        .iter()
+                        .map(|value| u32::from_str_radix(value, 16).map_err(|_| invalid()))
+                        .collect::<Result<_, _>>()?
+                }
+                "" => {}
+                _ => return Err(invalid()),
+            }
+        }
+
+        if replay.inputs.is_empty() {
+            return Err(format!("Replay {} has no inputs", path.display()));
+        }
+        Ok(replay)
+    }
+}
+
+// Replays from before the rule could be turned off allowed it.
+fn tail_chase() -> bool {
+    true
+}
+
+// Snakes started in the corners before they started in the middle.
+fn corner_spawn() -> Spawn {
+    Spawn::Corner
+}
+
+pub fn last_run_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("snake_2d").join("last.replay"))
+}
+
+// The highest scoring run in a mode, e.g. `best/time_attack.replay`.
+pub fn best_run_path(mode: &str) -> Option<PathBuf> {
+    let file = format!("{}.replay", mode.to_lowercase().replace(' ', "_"));
+    dirs::config_dir().map(|dir| dir.join("snake_2d").join("best").join(file))
+}
+
+fn encode(inputs: &[Direction]) -> String {
+    let mut encoded = String::new();
+    let mut i = 0;
+    while i < inputs.len() {
+        let run = inputs[i..].iter().take_while(|&&d| d == inputs[i]).count();
+        encoded.push(direction_char(inputs[i]));
+        encoded.push_str(&run.to_string());
+        i += run;
+    }
+    encoded
+}
+
+fn decode(encoded: &str) -> Option<Vec<Direction>> {
+    let mut inputs = Vec::new();
+    let mut chars = encoded.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let dir = char_direction(ch)?;
+        let mut run = String::new();
+        while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            run.push(digit);
+            chars.next();
+        }
+        let run: usize = run.parse().ok()?;
+        inputs.extend(std::iter::repeat_n(dir, run));
+    }
+    Some(inputs)
+}
+
+// Diagonals take the keys around WASD.
+fn direction_char(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+        Direction::UpLeft => 'Q',
+        Direction::UpRight => 'E',
+        Direction::DownLeft => 'Z',
+        Direction::DownRight => 'C',
+    }
+}
+
+fn char_direction(ch: char) -> Option<Direction> {
+    match ch {
+        'U' => Some(Direction::Up),
+        'D' => Some(Direction::Down),
+        'L' => Some(Direction::Left),
+        'R' => Some(Direction::Right),
+        'Q' => Some(Direction::UpLeft),
+        'E' => Some(Direction::UpRight),
+        'Z' => Some(Direction::DownLeft),
+        'C' => Some(Direction::DownRight),
+        _ => None,
+    }
+}
@@ -0,0 +1,53 @@
+use crate::game::{Game, Snapshot};
+use std::collections::VecDeque;
+
+// How many ticks back a game can be wound.
+pub const HISTORY_TICKS: usize = 200;
+// How far one rewind goes.
+pub const REWIND_TICKS: usize = 10;
+
+// The last `HISTORY_TICKS` states of a game, oldest first. `record` is
+// called before every step, so the newest snapshot is the state just
+// before the last tick.
+pub struct Rewind {
+    states: VecDeque<Snapshot>,
+}
+
+impl Rewind {
+    pub fn new() -> Rewind {
+        Rewind {
+            states: VecDeque::with_capacity(HISTORY_TICKS),
+        }
+    }
+
+    pub fn record(&mut self, game: &Game) {
+        if self.states.len() == HISTORY_TICKS {
+            self.states.pop_front();
+        }
+        self.states.push_back(game.snapshot());
+    }
+
+    // Puts `game` back `REWIND_TICKS` ticks, or as far as the history goes.
+    // Returns false when there is nothing to go back to.
+    pub fn rewind(&mut self, game: &mut Game) -> bool {
+        let keep = self.states.len().saturating_sub(REWIND_TICKS);
+        self.states.truncate(keep + 1);
+        match self.states.pop_back() {
+            Some(snapshot) => {
+                game.restore(&snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+}
+
+impl Default for Rewind {
+    fn default() -> Rewind {
+        Rewind::new()
+    }
+}
@@ -0,0 +1,243 @@
+use crate::ai::Difficulty;
+use crate::board::Board;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_FOOD: usize = 5;
+pub const TIME_ATTACK_SECONDS: u64 = 120;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    pub board: Board,
+    pub update_speed: u64,
+    pub max_speed: u64,
+    pub speed_up_every: u32,
+    pub board_mode: BoardMode,
+    pub seed: Option<u64>,
+    pub level: Option<String>,
+    pub players: usize,
+    pub ai_opponent: bool,
+    pub ai_difficulty: Difficulty,
+    pub food_count: usize,
+    pub lives: u32,
+    pub time_limit: Option<u64>,
+    // Grow a new wall every ENDLESS_WALL_EVERY foods.
+    pub endless: bool,
+    // Close the outer ring of the board every SHRINK_EVERY seconds.
+    pub shrinking: bool,
+    // Two snakes on one team: a shared score and lives, no crashing into
+    // each other, and a win at COOP_LENGTH segments between them.
+    pub coop: bool,
+    // New food lands at least this many moves from every head and never in
+    // the two cells straight ahead of one; 0 places it anywhere.
+    pub food_distance: u32,
+    // A head may move onto the cell its own tail leaves on the same tick.
+    pub tail_chase: bool,
+    pub spawn: Spawn,
+    // Experimental: snakes may also move diagonally, by holding two
+    // direction keys at once.
+    pub diagonals: bool,
+}
+
+impl Rules {
+    pub fn validated(mut self) -> Rules {
+        self.board = self.board.validated();
+        if self.ai_opponent || self.coop {
+            self.players = 2;
+        }
+        self.players = self.players.clamp(1, 2);
+        self.food_count = self.food_count.clamp(1, MAX_FOOD);
+        self.lives = self.lives.max(1);
+        self.update_speed = self.update_speed.max(1);
+        self.max_speed = self.max_speed.max(self.update_speed);
+        self
+    }
+
+    pub fn contact(&self) -> Contact {
+        if self.coop {
+            Contact::Stall
+        } else {
+            Contact::Crash
+        }
+    }
+
+    pub fn speed_preset(&self) -> SpeedPreset {
+        SpeedPreset::from_speed(self.update_speed)
+    }
+
+    pub fn set_speed_preset(&mut self, preset: SpeedPreset) {
+        self.update_speed = preset.speed();
+        self.max_speed = self.max_speed.max(self.update_speed);
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            board: Board::default(),
+            update_speed: 6,
+            max_speed: 20,
+            speed_up_every: 5,
+            board_mode: BoardMode::Walls,
+            seed: None,
+            level: None,
+            players: 1,
+            ai_opponent: false,
+            ai_difficulty: Difficulty::Normal,
+            food_count: 1,
+            lives: 3,
+            time_limit: None,
+            endless: false,
+            shrinking: false,
+            coop: false,
+            food_distance: 0,
+            tail_chase: true,
+            spawn: Spawn::Center,
+            diagonals: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardMode {
+    Walls,
+    Wrap,
+}
+
+// Where the snakes start a game.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Spawn {
+    // Near the middle, facing the most open way. Two snakes start at
+    // mirrored cells at least MIN_SPAWN_DISTANCE apart.
+    Center,
+    // The top left corner heading right and the bottom right one heading
+    // left, as in replays from before spawning could be chosen.
+    Corner,
+}
+
+impl Spawn {
+    pub fn name(self) -> &'static str {
+        match self {
+            Spawn::Center => "center",
+            Spawn::Corner => "corner",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Spawn> {
+        match name {
+            "center" => Some(Spawn::Center),
+            "corner" => Some(Spawn::Corner),
+            _ => None,
+        }
+    }
+}
+
+// What happens to a snake whose head runs into another snake.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Contact {
+    Crash,
+    // It waits where it is for a tick instead of moving.
+    Stall,
+}
+
+impl BoardMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardMode::Walls => "Walls",
+            BoardMode::Wrap => "Wrap",
+        }
+    }
+
+    pub fn toggled(self) -> BoardMode {
+        match self {
+            BoardMode::Walls => BoardMode::Wrap,
+            BoardMode::Wrap => BoardMode::Walls,
+        }
+    }
+}
+
+// Named starting speeds. Faster presets are worth more points per food; the
+// preset is worked out from `Rules::update_speed`, so a speed set by number
+// scores like the preset it is closest to.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedPreset {
+    Slow,
+    Normal,
+    Fast,
+    Insane,
+}
+
+pub const SPEED_PRESETS: [SpeedPreset; 4] = [
+    SpeedPreset::Slow,
+    SpeedPreset::Normal,
+    SpeedPreset::Fast,
+    SpeedPreset::Insane,
+];
+
+impl SpeedPreset {
+    pub fn name(self) -> &'static str {
+        match self {
+            SpeedPreset::Slow => "Slow",
+            SpeedPreset::Normal => "Normal",
+            SpeedPreset::Fast => "Fast",
+            SpeedPreset::Insane => "Insane",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            SpeedPreset::Slow => "speed.slow",
+            SpeedPreset::Normal => "speed.normal",
+            SpeedPreset::Fast => "speed.fast",
+            SpeedPreset::Insane => "speed.insane",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<SpeedPreset> {
+        SPEED_PRESETS
+            .iter()
+            .copied()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+
+    // Moves per second at the start of a game.
+    pub fn speed(self) -> u64 {
+        match self {
+            SpeedPreset::Slow => 4,
+            SpeedPreset::Normal => 6,
+            SpeedPreset::Fast => 10,
+            SpeedPreset::Insane => 15,
+        }
+    }
+
+    pub fn from_speed(speed: u64) -> SpeedPreset {
+        match speed {
+            0..=4 => SpeedPreset::Slow,
+            5..=7 => SpeedPreset::Normal,
+            8..=12 => SpeedPreset::Fast,
+            _ => SpeedPreset::Insane,
+        }
+    }
+
+    pub fn food_points(self) -> u32 {
+        match self {
+            SpeedPreset::Slow => 5,
+            SpeedPreset::Normal => 10,
+            SpeedPreset::Fast => 15,
+            SpeedPreset::Insane => 20,
+        }
+    }
+
+    pub fn next(self) -> SpeedPreset {
+        let index = SPEED_PRESETS.iter().position(|&p| p == self).unwrap_or(0);
+        SPEED_PRESETS[(index + 1) % SPEED_PRESETS.len()]
+    }
+
+    pub fn previous(self) -> SpeedPreset {
+        let index = SPEED_PRESETS.iter().position(|&p| p == self).unwrap_or(0);
+        SPEED_PRESETS[(index + SPEED_PRESETS.len() - 1) % SPEED_PRESETS.len()]
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+const COMBO_TICKS: usize = 30;
+const MAX_MULTIPLIER: u32 = 5;
+const POISON_PENALTY: u32 = 30;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Score {
+    pub points: u32,
+    pub streak: u32,
+    #[serde(default)]
+    pub last_eat: Option<usize>,
+}
+
+impl Score {
+    // `points` is what the food is worth before the combo multiplier.
+    // Returns the points gained.
+    pub fn food_eaten(&mut self, points: u32, tick: usize) -> u32 {
+        self.expire(tick);
+        self.streak += 1;
+        self.last_eat = Some(tick);
+        let gained = points * self.multiplier();
+        self.points += gained;
+        gained
+    }
+
+    // Takes away points for eating poison and ends the combo. Returns the
+    // points lost, which never take the score below zero.
+    pub fn poisoned(&mut self) -> u32 {
+        let lost = POISON_PENALTY.min(self.points);
+        self.points -= lost;
+        self.streak = 0;
+        self.last_eat = None;
+        lost
+    }
+
+    // Ends the combo once more than COMBO_TICKS have passed since the last eat.
+    pub fn expire(&mut self, tick: usize) {
+        if self
+            .last_eat
+            .is_none_or(|last| tick.saturating_sub(last) > COMBO_TICKS)
+        {
+            self.streak = 0;
+        }
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        self.streak.clamp(1, MAX_MULTIPLIER)
+    }
+
+    pub fn init() -> Score {
+        Score {
+            points: 0,
+            streak: 0,
+            last_eat: None,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use image::imageops;
+use image::RgbaImage;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Writes what was just drawn as a PNG to the `screenshots` folder in the
+// config directory.
+pub fn capture(size: [u32; 2]) -> Result<PathBuf, String> {
+    let image = read_frame(size).ok_or("Empty framebuffer")?;
+    let path = output_path("screenshots", "png")?;
+    image.save(&path).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+// Reads back the frame that was just drawn, before it is swapped to the
+// screen.
+pub fn read_frame(size: [u32; 2]) -> Option<RgbaImage> {
+    let [width, height] = size;
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut _,
+        );
+    }
+    let mut image = RgbaImage::from_raw(width, height, pixels)?;
+    // OpenGL rows start at the bottom of the window.
+    imageops::flip_vertical_in_place(&mut image);
+    Some(image)
+}
+
+// A new file in `folder` of the config directory, named after the current
+// time.
+pub fn output_path(folder: &str, extension: &str) -> Result<PathBuf, String> {
+    let dir = dirs::config_dir()
+        .map(|dir| dir.join("snake_2d").join(folder))
+        .ok_or("No config directory")?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    Ok(dir.join(format!("snake_2d-{}.{}", millis, extension)))
+}
@@ -0,0 +1,157 @@
+use crate::ai::Difficulty;
+use crate::bot::{BoardView, Controller, Process};
+use crate::game::{DeathCause, Game, TickResult};
+use crate::level::Level;
+use crate::rules::Rules;
+use crate::snake::Direction;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use std::fmt;
+
+// Stops games where the snake never dies, e.g. an AI circling in wrap mode.
+const MAX_TICKS: usize = 20_000;
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+#[derive(Clone)]
+pub enum Policy {
+    Ai(Difficulty),
+    Random,
+    // A bot program at this path, see `bot::Process`.
+    Bot(String),
+}
+
+#[derive(Default)]
+pub struct Stats {
+    pub games: usize,
+    pub total_length: usize,
+    pub total_score: u64,
+    pub total_ticks: usize,
+    pub deaths: Vec<(DeathCause, usize)>,
+    pub wins: usize,
+    pub timeouts: usize,
+    pub failures: usize,
+}
+
+impl Stats {
+    fn died(&mut self, cause: DeathCause) {
+        match self.deaths.iter_mut().find(|(c, _)| *c == cause) {
+            Some((_, count)) => *count += 1,
+            None => self.deaths.push((cause, 1)),
+        }
+    }
+
+    fn average(&self, total: f64) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            total / self.games as f64
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Games: {}", self.games)?;
+        writeln!(
+            f,
+            "Average length: {:.1}",
+            self.average(self.total_length as f64)
+        )?;
+        writeln!(
+            f,
+            "Average score: {:.1}",
+            self.average(self.total_score as f64)
+        )?;
+        writeln!(
+            f,
+            "Average ticks survived: {:.1}",
+            self.average(self.total_ticks as f64)
+        )?;
+        writeln!(f, "Board cleared: {}", self.wins)?;
+        writeln!(f, "Out of time or ticks: {}", self.timeouts)?;
+        if self.failures > 0 {
+            writeln!(f, "Failed: {}", self.failures)?;
+        }
+        write!(f, "Deaths:")?;
+        if self.deaths.is_empty() {
+            write!(f, " none")?;
+        }
+        for &(cause, count) in &self.deaths {
+            write!(f, "\n  {}: {}", cause.message(), count)?;
+        }
+        Ok(())
+    }
+}
+
+// Plays `games` single-snake games without a window. With a fixed seed in the
+// rules, game i uses seed + i so the whole run is reproducible. Fails if a
+// bot can't be started or stops answering.
+pub fn run(mut rules: Rules, level: Level, games: usize, policy: Policy) -> Result<Stats, String> {
+    let base_seed = rules.seed;
+    rules.players = 1;
+    rules.ai_opponent = false;
+
+    let mut bot = match policy {
+        Policy::Bot(ref path) => Some(Process::spawn(path)?),
+        _ => None,
+    };
+    let mut stats = Stats::default();
+    let mut game = Game::new(rules, level);
+    for i in 0..games {
+        game.rules.seed = base_seed.map(|seed| seed.wrapping_add(i as u64));
+        game.restart();
+        if let Policy::Ai(difficulty) = policy {
+            game.players[0].ai = Some(difficulty);
+        }
+        let mut rng = Pcg32::seed_from_u64(game.seed);
+
+        loop {
+            let input = match policy {
+                Policy::Ai(_) => None,
+                Policy::Random => Some(DIRECTIONS[rng.gen_range(0, DIRECTIONS.len())]),
+                Policy::Bot(_) => bot
+                    .as_mut()
+                    .map(|bot| bot.next_move(&BoardView::new(&game, 0))),
+            };
+            if let Some(e) = bot.as_mut().and_then(|bot| bot.error.take()) {
+                return Err(e);
+            }
+            match game.step(input) {
+                TickResult::Died(cause) => {
+                    stats.died(cause);
+                    break;
+                }
+                TickResult::LostLife(cause) => stats.died(cause),
+                TickResult::Won => {
+                    stats.wins += 1;
+                    break;
+                }
+                TickResult::TimeUp => {
+                    stats.timeouts += 1;
+                    break;
+                }
+                TickResult::Failed(_) => {
+                    stats.failures += 1;
+                    break;
+                }
+                TickResult::Moved | TickResult::Ate(_) => {}
+            }
+            if game.tick >= MAX_TICKS {
+                stats.timeouts += 1;
+                break;
+            }
+        }
+
+        let player = &game.players[0];
+        stats.games += 1;
+        stats.total_length += player.snake.len();
+        stats.total_score += u64::from(player.score.points);
+        stats.total_ticks += game.tick;
+    }
+    Ok(stats)
+}
@@ -0,0 +1,282 @@
+use crate::error::SnakeError;
+use crate::level::Level;
+use crate::pos::Pos;
+use crate::rules::{BoardMode, Rules};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "SavedSnake")]
+pub struct Snake {
+    // Head first. Only changed through the methods below so `occupied`
+    // stays in step with it.
+    body: VecDeque<Pos>,
+    pub dir: Direction,
+    #[serde(default)]
+    pub growth: usize,
+    // How many segments are on each cell, kept up to date as the snake
+    // moves so lookups don't walk the body. A count above 1 means overlap.
+    #[serde(skip)]
+    occupied: HashMap<Pos, u32>,
+    // The cell the tail left on the last move, if it moved.
+    #[serde(skip)]
+    vacated: Option<Pos>,
+}
+
+// The saved fields; the occupancy counts are rebuilt from the body.
+#[derive(Deserialize)]
+struct SavedSnake {
+    body: VecDeque<Pos>,
+    dir: Direction,
+    #[serde(default)]
+    growth: usize,
+}
+
+impl From<SavedSnake> for Snake {
+    fn from(saved: SavedSnake) -> Snake {
+        let mut snake = Snake::new(saved.body.into_iter().collect(), saved.dir);
+        snake.growth = saved.growth;
+        snake
+    }
+}
+
+impl Snake {
+    // Moves one cell ahead. Entering a portal puts the head on its twin, and
+    // the body follows through over the next ticks.
+    pub fn update_direction(&mut self, rules: &Rules, level: &Level) -> Result<(), SnakeError> {
+        let new_head = self.next_head(rules, level)?;
+        self.body.push_front(new_head);
+        self.occupy(new_head);
+        self.vacated = None;
+        if self.growth > 0 {
+            self.growth -= 1;
+        } else if let Some(tail) = self.body.pop_back() {
+            self.vacate(tail);
+            self.vacated = Some(tail);
+        }
+        Ok(())
+    }
+
+    // Where the head goes on the next move.
+    pub fn next_head(&self, rules: &Rules, level: &Level) -> Result<Pos, SnakeError> {
+        let mut new_head = self.head()? + self.dir;
+        if rules.board_mode == BoardMode::Wrap {
+            new_head = new_head.wrapped(rules.board);
+        }
+        if let Some((x, y)) = level.portal_exit(new_head.x, new_head.y) {
+            new_head = Pos { x, y };
+        }
+        Ok(new_head)
+    }
+
+    // True if any segment is on the cell.
+    pub fn contains(&self, pos: Pos) -> bool {
+        self.occupied.contains_key(&pos)
+    }
+
+    // Head to tail.
+    pub fn segments(&self) -> impl DoubleEndedIterator<Item = Pos> + ExactSizeIterator + '_ {
+        self.body.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    fn occupy(&mut self, part: Pos) {
+        *self.occupied.entry(part).or_insert(0) += 1;
+    }
+
+    fn vacate(&mut self, part: Pos) {
+        if let Some(count) = self.occupied.get_mut(&part) {
+            *count -= 1;
+            if *count == 0 {
+                self.occupied.remove(&part);
+            }
+        }
+    }
+
+    pub fn head(&self) -> Result<Pos, SnakeError> {
+        self.body.front().copied().ok_or(SnakeError::EmptySnake)
+    }
+
+    // The tail stays put on the next move, so the new segment always lands
+    // on a cell the snake has just occupied.
+    pub fn grow(&mut self) {
+        self.growth += 1;
+    }
+
+    pub fn shrink(&mut self, segments: usize) {
+        for _ in 0..segments {
+            if self.body.len() <= 2 {
+                break;
+            }
+            if let Some(tail) = self.body.pop_back() {
+                self.vacate(tail);
+            }
+        }
+    }
+
+    pub fn check_eat(&self, food: &Pos) -> bool {
+        self.head().is_ok_and(|head| head == *food)
+    }
+
+    // The head shares its cell with another segment.
+    pub fn collision(&self) -> bool {
+        self.head()
+            .is_ok_and(|head| self.occupied.get(&head).is_some_and(|&n| n > 1))
+    }
+
+    // The head moved onto the cell the tail left, which only counts as a
+    // collision without `Rules::tail_chase`.
+    pub fn chased_tail(&self) -> bool {
+        self.vacated.is_some() && self.head().ok() == self.vacated
+    }
+
+    // The head is off the board or on one of the `closed` outer rings.
+    pub fn out_of_bounds(&self, rules: &Rules, closed: i32) -> bool {
+        self.head()
+            .is_ok_and(|head| !rules.board.inside(head.x, head.y, closed))
+    }
+
+    pub fn new(body: Vec<Pos>, dir: Direction) -> Snake {
+        let mut snake = Snake {
+            body: VecDeque::with_capacity(body.len()),
+            dir,
+            growth: 0,
+            occupied: HashMap::new(),
+            vacated: None,
+        };
+        for part in body {
+            snake.body.push_back(part);
+            snake.occupy(part);
+        }
+        snake
+    }
+
+    pub fn init() -> Snake {
+        Snake::new(
+            vec![Pos { x: 0, y: 0 }, Pos { x: 0, y: 1 }],
+            Direction::Right,
+        )
+    }
+
+    pub fn init_player_two(rules: &Rules) -> Snake {
+        let x = rules.board.columns - 1;
+        let y = rules.board.rows - 1;
+        Snake::new(vec![Pos { x, y }, Pos { x, y: y - 1 }], Direction::Left)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Right,
+    Left,
+    Up,
+    Down,
+    // Only with `Rules::diagonals`.
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    // One step this way as (dx, dy), with y growing downwards.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Right => (1, 0),
+            Direction::Left => (-1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        let (dx, dy) = self.offset();
+        dx != 0 && dy != 0
+    }
+
+    // The diagonal between two perpendicular straight directions, as when
+    // both keys are held; None for any other pair.
+    pub fn combined(self, other: Direction) -> Option<Direction> {
+        if self.is_diagonal() || other.is_diagonal() {
+            return None;
+        }
+        let (x1, y1) = self.offset();
+        let (x2, y2) = other.offset();
+        match (x1 + x2, y1 + y2) {
+            (-1, -1) => Some(Direction::UpLeft),
+            (1, -1) => Some(Direction::UpRight),
+            (-1, 1) => Some(Direction::DownLeft),
+            (1, 1) => Some(Direction::DownRight),
+            _ => None,
+        }
+    }
+
+    // The way along (dx, dy) that is closest to it without reversing
+    // `heading`, falling back to the other axis; None for a zero vector.
+    pub fn towards(dx: f64, dy: f64, heading: Direction) -> Option<Direction> {
+        let horizontal = (
+            if dx < 0.0 {
+                Direction::Left
+            } else {
+                Direction::Right
+            },
+            dx,
+        );
+        let vertical = (
+            if dy < 0.0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            },
+            dy,
+        );
+        let choices = if dx.abs() >= dy.abs() {
+            [horizontal, vertical]
+        } else {
+            [vertical, horizontal]
+        };
+        choices
+            .iter()
+            .find(|&&(dir, amount)| amount != 0.0 && dir != heading.opposite())
+            .map(|&(dir, _)| dir)
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steering_towards_a_point_never_reverses_the_snake() {
+        let towards = Direction::towards;
+        assert_eq!(towards(3.0, 1.0, Direction::Up), Some(Direction::Right));
+        assert_eq!(towards(-1.0, 2.0, Direction::Left), Some(Direction::Down));
+        // Straight behind: the other axis is zero, so there is no turn.
+        assert_eq!(towards(-3.0, 0.0, Direction::Right), None);
+        assert_eq!(towards(-3.0, 1.0, Direction::Right), Some(Direction::Down));
+    }
+}
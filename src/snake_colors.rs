@@ -0,0 +1,116 @@
+use crate::theme::SnakeStyle;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// Named by their translation keys.
+pub const PALETTE: [(&str, [f32; 4]); 10] = [
+    ("color.red", [1.0, 0.0, 0.0, 1.0]),
+    ("color.dark_red", [0.6, 0.0, 0.0, 1.0]),
+    ("color.orange", [1.0, 0.55, 0.0, 1.0]),
+    ("color.yellow", [1.0, 0.9, 0.1, 1.0]),
+    ("color.green", [0.2, 0.8, 0.2, 1.0]),
+    ("color.cyan", [0.0, 0.85, 0.85, 1.0]),
+    ("color.blue", [0.2, 0.4, 1.0, 1.0]),
+    ("color.purple", [0.6, 0.3, 0.9, 1.0]),
+    ("color.pink", [1.0, 0.5, 0.75, 1.0]),
+    ("color.white", [1.0, 1.0, 1.0, 1.0]),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Part {
+    Body,
+    Head,
+}
+
+// A player's picks from Settings; None keeps the theme's color.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerColors {
+    pub body: Option<[f32; 4]>,
+    pub head: Option<[f32; 4]>,
+}
+
+// Snake colors chosen in Settings, kept in `snake_colors.ron` so they last
+// between sessions. They take precedence over the theme and `snake.toml`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnakeColors {
+    pub players: [PlayerColors; 2],
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl SnakeColors {
+    pub fn load() -> SnakeColors {
+        let path = dirs::config_dir().map(|dir| dir.join("snake_2d").join("snake_colors.ron"));
+        let mut colors: SnakeColors = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default();
+        colors.path = path;
+        colors
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        let contents =
+            ron::ser::to_string_pretty(self, PrettyConfig::default()).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    // `theme` with this player's picks laid over it.
+    pub fn style(&self, index: usize, theme: SnakeStyle) -> SnakeStyle {
+        let picks = self.players[index.min(1)];
+        SnakeStyle {
+            body: picks.body.unwrap_or(theme.body),
+            head: picks.head.unwrap_or(theme.head),
+            ..theme
+        }
+    }
+
+    pub fn get(&self, index: usize, part: Part) -> Option<[f32; 4]> {
+        let picks = &self.players[index.min(1)];
+        match part {
+            Part::Body => picks.body,
+            Part::Head => picks.head,
+        }
+    }
+
+    // Steps through the theme's color and then the palette.
+    pub fn cycle(&mut self, index: usize, part: Part, forward: bool) {
+        let picks = &mut self.players[index.min(1)];
+        let color = match part {
+            Part::Body => &mut picks.body,
+            Part::Head => &mut picks.head,
+        };
+        let count = PALETTE.len() + 1;
+        let current = color
+            .and_then(|c| PALETTE.iter().position(|&(_, p)| p == c))
+            .map_or(0, |i| i + 1);
+        let next = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        *color = next.checked_sub(1).map(|i| PALETTE[i].1);
+    }
+}
+
+pub fn color_key(color: Option<[f32; 4]>) -> &'static str {
+    match color {
+        None => "color.theme",
+        Some(c) => PALETTE
+            .iter()
+            .find(|&&(_, p)| p == c)
+            .map_or("color.custom", |&(name, _)| name),
+    }
+}
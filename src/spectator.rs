@@ -0,0 +1,53 @@
+use crate::config::KeyMap;
+use piston::input::Key;
+use snake_2d::Locale;
+
+const SPEEDS: [f64; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+const NORMAL_SPEED: usize = 1;
+
+// Watching the bot play the first snake. The game runs `speed()` times as
+// fast as normal until a direction key hands the snake back to the player,
+// which clears `watching` for the rest of that game.
+pub struct Spectator {
+    pub watching: bool,
+    speed: usize,
+}
+
+impl Spectator {
+    pub fn new() -> Spectator {
+        Spectator {
+            watching: true,
+            speed: NORMAL_SPEED,
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    // Handles the +/- keys, returning whether the key was one of them.
+    pub fn pressed(&mut self, key: Key) -> bool {
+        match key {
+            Key::Plus | Key::Equals | Key::NumPadPlus => {
+                self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+            }
+            Key::Minus | Key::NumPadMinus => self.speed = self.speed.saturating_sub(1),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn prompt(&self, keys: &KeyMap, locale: &Locale) -> String {
+        let key = |key| format!("{:?}", key);
+        locale.format(
+            "spectator.prompt",
+            &[
+                ("speed", &self.speed()),
+                ("up", &key(keys.up)),
+                ("down", &key(keys.down)),
+                ("left", &key(keys.left)),
+                ("right", &key(keys.right)),
+            ],
+        )
+    }
+}
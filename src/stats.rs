@@ -0,0 +1,67 @@
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use snake_2d::game::DeathCause;
+use snake_2d::Game;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+// Totals across every game played on this machine, kept in `stats.ron`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub games_played: u32,
+    pub food_eaten: u32,
+    pub playtime: f64,
+    pub best_length: BTreeMap<String, usize>,
+    pub deaths: Vec<(DeathCause, u32)>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Stats {
+    pub fn load() -> Stats {
+        let path = dirs::config_dir().map(|dir| dir.join("snake_2d").join("stats.ron"));
+        let mut stats: Stats = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default();
+        stats.path = path;
+        stats
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        let contents =
+            ron::ser::to_string_pretty(self, PrettyConfig::default()).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    pub fn ate(&mut self) {
+        self.food_eaten += 1;
+    }
+
+    // Adds a finished game played in `mode` by the given players.
+    pub fn record(&mut self, game: &Game, players: &[usize], mode: &str) {
+        self.games_played += 1;
+        self.playtime += game.elapsed;
+        for &i in players {
+            let player = &game.players[i];
+            let best = self.best_length.entry(mode.to_string()).or_insert(0);
+            *best = (*best).max(player.snake.len());
+            if let Some(cause) = player.death {
+                match self.deaths.iter_mut().find(|(c, _)| *c == cause) {
+                    Some((_, count)) => *count += 1,
+                    None => self.deaths.push((cause, 1)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Histogram buckets are this many milliseconds wide.
+const BUCKET_MS: f64 = 0.5;
+
+// Durations in milliseconds, counted in buckets so sessions can be merged
+// and still give percentiles to within a bucket.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Histogram {
+    buckets: BTreeMap<u32, u32>,
+}
+
+impl Histogram {
+    pub fn record(&mut self, duration: Duration) {
+        let bucket = (duration.as_secs_f64() * 1000.0 / BUCKET_MS) as u32;
+        *self.buckets.entry(bucket).or_insert(0) += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets.values().map(|&n| u64::from(n)).sum()
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        for (&bucket, &n) in &other.buckets {
+            *self.buckets.entry(bucket).or_insert(0) += n;
+        }
+    }
+
+    // The top of the bucket that `fraction` of the samples fall within.
+    pub fn percentile(&self, fraction: f64) -> f64 {
+        let target = (self.count() as f64 * fraction).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (&bucket, &n) in &self.buckets {
+            seen += u64::from(n);
+            if seen >= target {
+                return f64::from(bucket + 1) * BUCKET_MS;
+            }
+        }
+        0.0
+    }
+}
+
+// One run of the game, added to `telemetry.ron` as a line of its own when
+// the window closes.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    // Seconds since the Unix epoch.
+    pub started: u64,
+    pub seconds: f64,
+    // From pressing a turn to the tick that turned the snake.
+    pub latency: Histogram,
+    // Turns pressed while the input queue was already full.
+    pub dropped: u32,
+    // Time between frames drawn while playing.
+    pub frames: Histogram,
+}
+
+// Input latency, dropped inputs and frame times, measured on the clock so
+// they compare across tick rates. Only kept with `telemetry = true`; the
+// file never leaves this machine.
+pub struct Telemetry {
+    session: Session,
+    opened: Instant,
+    // When each turn still waiting in the snake's queue was pressed.
+    pressed: VecDeque<Instant>,
+    last_frame: Option<Instant>,
+    path: Option<PathBuf>,
+}
+
+impl Telemetry {
+    pub fn init(enabled: bool) -> Telemetry {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        Telemetry {
+            session: Session {
+                started,
+                ..Session::default()
+            },
+            opened: Instant::now(),
+            pressed: VecDeque::new(),
+            last_frame: None,
+            path: if enabled { path() } else { None },
+        }
+    }
+
+    pub fn turn_queued(&mut self) {
+        if self.path.is_some() {
+            self.pressed.push_back(Instant::now());
+        }
+    }
+
+    pub fn turn_dropped(&mut self) {
+        self.session.dropped += 1;
+    }
+
+    // The newest queued turn was taken back.
+    pub fn turn_retracted(&mut self) {
+        self.pressed.pop_back();
+    }
+
+    // The snake took the oldest queued turn.
+    pub fn turned(&mut self) {
+        if let Some(pressed) = self.pressed.pop_front() {
+            self.session.latency.record(pressed.elapsed());
+        }
+    }
+
+    // Queued turns were thrown away, by a new game or a crash.
+    pub fn clear_turns(&mut self) {
+        self.pressed.clear();
+    }
+
+    // Call on every frame; only frames drawn while playing are timed.
+    pub fn frame(&mut self, playing: bool) {
+        if self.path.is_none() {
+            return;
+        }
+        let now = Instant::now();
+        if let (true, Some(last)) = (playing, self.last_frame) {
+            self.session.frames.record(now - last);
+        }
+        self.last_frame = if playing { Some(now) } else { None };
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        self.session.seconds = self.opened.elapsed().as_secs_f64();
+        let line = ron::to_string(&self.session).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| e.to_string())
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("snake_2d").join("telemetry.ron"))
+}
+
+// Every session in the telemetry file added up, for --stats-report.
+#[derive(Default)]
+pub struct Report {
+    sessions: usize,
+    seconds: f64,
+    latency: Histogram,
+    dropped: u64,
+    frames: Histogram,
+}
+
+impl Report {
+    pub fn load() -> Result<Report, String> {
+        let path = path().ok_or("No config directory for the telemetry file")?;
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let mut report = Report::default();
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let session: Session = ron::from_str(line)
+                .map_err(|e| format!("{} line {}: {}", path.display(), number + 1, e))?;
+            report.sessions += 1;
+            report.seconds += session.seconds;
+            report.latency.merge(&session.latency);
+            report.dropped += u64::from(session.dropped);
+            report.frames.merge(&session.frames);
+        }
+        Ok(report)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Sessions: {}", self.sessions)?;
+        writeln!(f, "Time played: {:.0} s", self.seconds)?;
+        writeln!(f, "Turns: {}", self.latency.count())?;
+        writeln!(f, "Dropped turns: {}", self.dropped)?;
+        for (name, histogram) in &[
+            ("Input latency", &self.latency),
+            ("Frame time", &self.frames),
+        ] {
+            writeln!(
+                f,
+                "{} (ms): p50 {:.1}  p90 {:.1}  p99 {:.1}",
+                name,
+                histogram.percentile(0.5),
+                histogram.percentile(0.9),
+                histogram.percentile(0.99)
+            )?;
+        }
+        Ok(())
+    }
+}
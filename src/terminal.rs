@@ -0,0 +1,231 @@
+use crate::config::{Action, Config};
+use crate::snake_colors::SnakeColors;
+use crate::theme::Theme;
+use crate::user_assets::UserAssets;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use piston::input::Key;
+use snake_2d::level::{Level, HAZARD_COLOR};
+use snake_2d::{Game, Locale, TickResult};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Playing,
+    Paused,
+    GameOver,
+}
+
+pub fn run(config: &Config, game: &mut Game) -> io::Result<()> {
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = play(config, game, &mut out);
+    execute!(
+        out,
+        ResetColor,
+        cursor::Show,
+        terminal::LeaveAlternateScreen
+    )?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn play(config: &Config, game: &mut Game, out: &mut impl Write) -> io::Result<()> {
+    let theme = config.theme(&UserAssets::scan().all_themes());
+    let colors = SnakeColors::load();
+    let locale = config.locale();
+    let mut state = State::Playing;
+    let mut last_tick = Instant::now();
+    game.restart();
+
+    loop {
+        draw(out, game, &theme, &colors, &locale, state)?;
+
+        let interval = Duration::from_secs_f64(game.tick_interval());
+        if event::poll(interval.saturating_sub(last_tick.elapsed()))? {
+            if let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event::read()?
+            {
+                let ctrl_c =
+                    code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL);
+                if code == KeyCode::Esc || ctrl_c {
+                    return Ok(());
+                }
+                if let Some(key) = piston_key(code) {
+                    state = pressed(config, game, state, key);
+                    if state == State::Playing && game.tick == 0 {
+                        last_tick = Instant::now();
+                    }
+                }
+            }
+        }
+
+        if state != State::Playing {
+            last_tick = Instant::now();
+        } else if last_tick.elapsed() >= interval {
+            last_tick = Instant::now();
+            match game.step(None) {
+                TickResult::Died(_)
+                | TickResult::Won
+                | TickResult::TimeUp
+                | TickResult::Failed(_) => {
+                    state = State::GameOver;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn pressed(config: &Config, game: &mut Game, state: State, key: Key) -> State {
+    let action = config.keys.action(key);
+    match state {
+        State::Playing if action == Some(Action::Pause) => State::Paused,
+        State::Playing => {
+            if game.playback.is_none() {
+                if let Some(dir) = action.and_then(Action::direction) {
+                    game.players[0].steer(dir);
+                }
+                if let Some(player) = game.players.get_mut(1) {
+                    if let Some(dir) = config.keys_player_two.direction(key) {
+                        player.steer(dir);
+                    }
+                }
+            }
+            State::Playing
+        }
+        State::Paused if action == Some(Action::Pause) => State::Playing,
+        State::GameOver if action == Some(Action::Restart) => {
+            game.restart();
+            State::Playing
+        }
+        _ => state,
+    }
+}
+
+// Every board cell is two characters wide so it looks roughly square.
+fn draw(
+    out: &mut impl Write,
+    game: &Game,
+    theme: &Theme,
+    colors: &SnakeColors,
+    locale: &Locale,
+    state: State,
+) -> io::Result<()> {
+    let board = game.rules.board;
+    let (columns, rows) = (board.columns, board.rows);
+    let mut cells = vec![None; board.cells()];
+    let mut set = |x: i32, y: i32, cell: (&'static str, [f32; 4])| {
+        if board.contains(x, y) {
+            cells[(y * columns + x) as usize] = Some(cell);
+        }
+    };
+    for (x, y) in board.positions().filter(|&(x, y)| !game.in_arena(x, y)) {
+        set(x, y, ("::", theme.obstacle));
+    }
+    for obstacle in &game.level.obstacles {
+        set(obstacle.x, obstacle.y, ("##", theme.obstacle));
+    }
+    for (i, portal) in game.level.portals.iter().enumerate() {
+        for &(x, y) in &portal.ends {
+            set(x, y, ("<>", Level::portal_color(i)));
+        }
+    }
+    for hazard in game.hazards() {
+        set(hazard.x, hazard.y, ("/\\", HAZARD_COLOR));
+    }
+    for food in &game.foods {
+        set(
+            food.part.x,
+            food.part.y,
+            ("()", food.kind.color(theme.food)),
+        );
+    }
+    for (i, player) in game.players.iter().enumerate() {
+        let style = colors.style(i, theme.snake_style(i));
+        for (j, part) in player.snake.segments().enumerate().rev() {
+            let cell = match j {
+                0 => ("@@", style.head),
+                _ => ("[]", style.body),
+            };
+            set(part.x, part.y, cell);
+        }
+    }
+
+    let border = format!("+{}+", "-".repeat(columns as usize * 2));
+    queue!(out, cursor::MoveTo(0, 0), ResetColor, Print(&border))?;
+    for y in 0..rows {
+        queue!(out, cursor::MoveTo(0, y as u16 + 1), ResetColor, Print("|"))?;
+        queue!(out, SetBackgroundColor(color(theme.background)))?;
+        for x in 0..columns {
+            match cells[(y * columns + x) as usize] {
+                Some((text, fg)) => queue!(out, SetForegroundColor(color(fg)), Print(text))?,
+                None => queue!(out, Print("  "))?,
+            }
+        }
+        queue!(out, ResetColor, Print("|"))?;
+    }
+    queue!(out, cursor::MoveTo(0, rows as u16 + 1), Print(&border))?;
+
+    let mut status = String::new();
+    for (i, player) in game.players.iter().enumerate() {
+        let name = player.name(i, locale);
+        status.push_str(&locale.format(
+            "hud.player_score",
+            &[("name", &name), ("score", &player.score.points)],
+        ));
+        status.push_str("  ");
+        status.push_str(&locale.format("hud.lives", &[("lives", &player.lives)]));
+        status.push_str("  ");
+    }
+    status.push_str(&locale.format("hud.speed", &[("speed", &game.speed())]));
+    let message = match state {
+        State::Playing => "",
+        State::Paused => locale.get("terminal.paused"),
+        State::GameOver if game.won => locale.get("terminal.won"),
+        State::GameOver if game.time_up() => locale.get("terminal.time_up"),
+        State::GameOver => locale.get("terminal.game_over"),
+    };
+    queue!(
+        out,
+        cursor::MoveTo(0, rows as u16 + 2),
+        terminal::Clear(terminal::ClearType::CurrentLine),
+        Print(status),
+        cursor::MoveTo(0, rows as u16 + 3),
+        terminal::Clear(terminal::ClearType::CurrentLine),
+        Print(message)
+    )?;
+    out.flush()
+}
+
+fn color(c: [f32; 4]) -> Color {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::Rgb {
+        r: channel(c[0]),
+        g: channel(c[1]),
+        b: channel(c[2]),
+    }
+}
+
+// The key map is stored as piston keys, whose codes match ASCII for letters,
+// digits and space.
+fn piston_key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Enter => Some(Key::Return),
+        KeyCode::Backspace => Some(Key::Backspace),
+        KeyCode::Tab => Some(Key::Tab),
+        KeyCode::Char(ch) if ch.is_ascii() => Some(Key::from(ch.to_ascii_lowercase() as u32)),
+        _ => None,
+    }
+}
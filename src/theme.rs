@@ -0,0 +1,233 @@
+use crate::config::Colors;
+
+// Radians per second, and between neighbouring segments, of the pulse wave.
+const PULSE_SPEED: f64 = 5.0;
+const PULSE_SPACING: f64 = 0.7;
+
+// How a theme shades the body: `gradient` is how much darker the tail is
+// than the neck, `pulse` how far the brightness swings as a wave runs from
+// head to tail.
+#[derive(Clone, Copy)]
+pub struct Skin {
+    pub gradient: f32,
+    pub pulse: f32,
+}
+
+// The colors one player's snake is drawn in.
+#[derive(Clone, Copy)]
+pub struct SnakeStyle {
+    pub body: [f32; 4],
+    pub head: [f32; 4],
+    pub skin: Skin,
+}
+
+impl SnakeStyle {
+    pub fn faded(mut self, alpha: f32) -> SnakeStyle {
+        self.body[3] *= alpha;
+        self.head[3] *= alpha;
+        self
+    }
+
+    pub fn still(mut self) -> SnakeStyle {
+        self.skin.pulse = 0.0;
+        self
+    }
+
+    // The color of segment `index` of `count` at `time` seconds, the head
+    // being segment 0.
+    pub fn segment(&self, index: usize, count: usize, time: f64) -> [f32; 4] {
+        if index == 0 {
+            return self.head;
+        }
+        let along = index as f32 / (count - 1) as f32;
+        let wave = (time * PULSE_SPEED - index as f64 * PULSE_SPACING).sin() as f32;
+        let shade = (1.0 - self.skin.gradient * along) * (1.0 + self.skin.pulse * wave);
+        let [r, g, b, a] = self.body;
+        [
+            (r * shade).min(1.0),
+            (g * shade).min(1.0),
+            (b * shade).min(1.0),
+            a,
+        ]
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub name: &'static str,
+    pub background: [f32; 4],
+    pub snake: [f32; 4],
+    pub snake_player_two: [f32; 4],
+    pub head: [f32; 4],
+    pub head_player_two: [f32; 4],
+    pub food: [f32; 4],
+    pub obstacle: [f32; 4],
+    pub grid_line: [f32; 4],
+    pub text: [f32; 4],
+    pub skin: Skin,
+}
+
+// The last three keep snake, food and second player apart for the common
+// kinds of color blindness. High contrast and the flat retro palette keep
+// the body one solid color.
+pub const THEMES: [Theme; 7] = [
+    Theme {
+        name: "classic",
+        background: [0.0, 0.5, 0.2, 1.0],
+        snake: [1.0, 0.0, 0.0, 1.0],
+        snake_player_two: [0.2, 0.4, 1.0, 1.0],
+        head: [0.75, 0.0, 0.0, 1.0],
+        head_player_two: [0.1, 0.25, 0.75, 1.0],
+        food: [1.0, 0.0, 0.0, 1.0],
+        obstacle: [0.4, 0.4, 0.4, 1.0],
+        grid_line: [1.0, 1.0, 1.0, 0.08],
+        text: [1.0, 1.0, 1.0, 1.0],
+        skin: Skin {
+            gradient: 0.45,
+            pulse: 0.08,
+        },
+    },
+    Theme {
+        name: "dark",
+        background: [0.08, 0.08, 0.1, 1.0],
+        snake: [0.3, 0.8, 0.4, 1.0],
+        snake_player_two: [0.4, 0.6, 1.0, 1.0],
+        head: [0.55, 1.0, 0.6, 1.0],
+        head_player_two: [0.6, 0.75, 1.0, 1.0],
+        food: [1.0, 0.4, 0.3, 1.0],
+        obstacle: [0.3, 0.3, 0.35, 1.0],
+        grid_line: [1.0, 1.0, 1.0, 0.05],
+        text: [0.9, 0.9, 0.9, 1.0],
+        skin: Skin {
+            gradient: 0.5,
+            pulse: 0.1,
+        },
+    },
+    Theme {
+        name: "high-contrast",
+        background: [0.0, 0.0, 0.0, 1.0],
+        snake: [1.0, 1.0, 1.0, 1.0],
+        snake_player_two: [0.0, 1.0, 1.0, 1.0],
+        head: [1.0, 1.0, 0.0, 1.0],
+        head_player_two: [1.0, 1.0, 0.0, 1.0],
+        food: [1.0, 0.0, 1.0, 1.0],
+        obstacle: [0.5, 0.5, 0.5, 1.0],
+        grid_line: [1.0, 1.0, 1.0, 0.2],
+        text: [1.0, 1.0, 1.0, 1.0],
+        skin: Skin {
+            gradient: 0.0,
+            pulse: 0.0,
+        },
+    },
+    Theme {
+        name: "retro-green",
+        background: [0.61, 0.74, 0.06, 1.0],
+        snake: [0.06, 0.22, 0.06, 1.0],
+        snake_player_two: [0.19, 0.38, 0.19, 1.0],
+        head: [0.0, 0.1, 0.0, 1.0],
+        head_player_two: [0.0, 0.1, 0.0, 1.0],
+        food: [0.06, 0.22, 0.06, 1.0],
+        obstacle: [0.19, 0.38, 0.19, 1.0],
+        grid_line: [0.06, 0.22, 0.06, 0.15],
+        text: [0.06, 0.22, 0.06, 1.0],
+        skin: Skin {
+            gradient: 0.0,
+            pulse: 0.0,
+        },
+    },
+    Theme {
+        name: "deuteranopia",
+        background: [0.1, 0.12, 0.2, 1.0],
+        snake: [0.0, 0.45, 0.7, 1.0],
+        snake_player_two: [0.8, 0.8, 0.8, 1.0],
+        head: [0.34, 0.71, 0.91, 1.0],
+        head_player_two: [1.0, 1.0, 1.0, 1.0],
+        food: [0.9, 0.62, 0.0, 1.0],
+        obstacle: [0.4, 0.4, 0.45, 1.0],
+        grid_line: [1.0, 1.0, 1.0, 0.08],
+        text: [1.0, 1.0, 1.0, 1.0],
+        skin: Skin {
+            gradient: 0.35,
+            pulse: 0.06,
+        },
+    },
+    Theme {
+        name: "protanopia",
+        background: [0.12, 0.12, 0.12, 1.0],
+        snake: [0.34, 0.71, 0.91, 1.0],
+        snake_player_two: [0.8, 0.8, 0.8, 1.0],
+        head: [0.0, 0.45, 0.7, 1.0],
+        head_player_two: [1.0, 1.0, 1.0, 1.0],
+        food: [0.94, 0.89, 0.26, 1.0],
+        obstacle: [0.45, 0.45, 0.45, 1.0],
+        grid_line: [1.0, 1.0, 1.0, 0.08],
+        text: [1.0, 1.0, 1.0, 1.0],
+        skin: Skin {
+            gradient: 0.35,
+            pulse: 0.06,
+        },
+    },
+    Theme {
+        name: "tritanopia",
+        background: [0.1, 0.1, 0.1, 1.0],
+        snake: [0.0, 0.6, 0.6, 1.0],
+        snake_player_two: [0.8, 0.47, 0.65, 1.0],
+        head: [0.0, 0.8, 0.8, 1.0],
+        head_player_two: [0.95, 0.65, 0.8, 1.0],
+        food: [0.84, 0.37, 0.0, 1.0],
+        obstacle: [0.45, 0.45, 0.45, 1.0],
+        grid_line: [1.0, 1.0, 1.0, 0.08],
+        text: [1.0, 1.0, 1.0, 1.0],
+        skin: Skin {
+            gradient: 0.35,
+            pulse: 0.06,
+        },
+    },
+];
+
+impl Theme {
+    // `themes` is THEMES, or those and the user's own.
+    pub fn named(name: &str, themes: &[Theme]) -> Option<Theme> {
+        themes.iter().find(|t| t.name == name).copied()
+    }
+
+    pub fn next(&self, themes: &[Theme]) -> Theme {
+        let index = themes.iter().position(|t| t.name == self.name).unwrap_or(0);
+        themes[(index + 1) % themes.len()]
+    }
+
+    pub fn with_overrides(mut self, colors: &Colors) -> Theme {
+        let overrides = [
+            (&mut self.background, colors.background),
+            (&mut self.snake, colors.snake),
+            (&mut self.snake_player_two, colors.snake_player_two),
+            (&mut self.head, colors.head),
+            (&mut self.head_player_two, colors.head_player_two),
+            (&mut self.food, colors.food),
+            (&mut self.obstacle, colors.obstacle),
+            (&mut self.grid_line, colors.grid_line),
+            (&mut self.text, colors.text),
+        ];
+        for (color, custom) in overrides {
+            if let Some(custom) = custom {
+                *color = custom;
+            }
+        }
+        self
+    }
+
+    pub fn snake_style(&self, index: usize) -> SnakeStyle {
+        match index {
+            0 => SnakeStyle {
+                body: self.snake,
+                head: self.head,
+                skin: self.skin,
+            },
+            _ => SnakeStyle {
+                body: self.snake_player_two,
+                head: self.head_player_two,
+                skin: self.skin,
+            },
+        }
+    }
+}
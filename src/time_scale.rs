@@ -0,0 +1,35 @@
+use piston::input::Key;
+
+const SCALES: [f64; 6] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+const NORMAL_SCALE: usize = 3;
+
+// How fast game time runs against the clock, changed with +/- while
+// playing. Only the time between ticks is stretched, so a slowed-down game
+// scores the same as one at full pace.
+pub struct TimeScale {
+    index: usize,
+}
+
+impl TimeScale {
+    pub fn new() -> TimeScale {
+        TimeScale {
+            index: NORMAL_SCALE,
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        SCALES[self.index]
+    }
+
+    // Handles the +/- keys, returning whether the key was one of them.
+    pub fn pressed(&mut self, key: Key) -> bool {
+        match key {
+            Key::Plus | Key::Equals | Key::NumPadPlus => {
+                self.index = (self.index + 1).min(SCALES.len() - 1);
+            }
+            Key::Minus | Key::NumPadMinus => self.index = self.index.saturating_sub(1),
+            _ => return false,
+        }
+        true
+    }
+}
@@ -0,0 +1,105 @@
+use crate::config::KeyMap;
+use log::error;
+use snake_2d::food::{Behavior, Food, FoodKind};
+use snake_2d::{Direction, Locale, Pos, Rules};
+use std::fs;
+use std::path::PathBuf;
+
+// How far ahead of the head scripted food is put.
+const FOOD_DISTANCE: i32 = 4;
+// Extra segments to grow after the first meal.
+pub const GROW_SEGMENTS: usize = 2;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Step {
+    Turn,
+    Eat,
+    Grow,
+    Pause,
+    Resume,
+    Crash,
+    Restart,
+    Done,
+}
+
+// A guided single-player game that moves on to the next step as the player
+// does what the prompt asks.
+pub struct Tutorial {
+    pub step: Step,
+    pub target_length: usize,
+    // Put back once the tutorial is over.
+    pub rules: Rules,
+}
+
+impl Tutorial {
+    pub fn new(rules: Rules) -> Tutorial {
+        Tutorial {
+            step: Step::Turn,
+            target_length: 0,
+            rules,
+        }
+    }
+
+    pub fn prompt(&self, keys: &KeyMap, locale: &Locale) -> String {
+        let key = |key| format!("{:?}", key);
+        match self.step {
+            Step::Turn => locale.format(
+                "tutorial.turn",
+                &[
+                    ("up", &key(keys.up)),
+                    ("down", &key(keys.down)),
+                    ("left", &key(keys.left)),
+                    ("right", &key(keys.right)),
+                ],
+            ),
+            Step::Eat => locale.format("tutorial.eat", &[]),
+            Step::Grow => locale.format("tutorial.grow", &[("length", &self.target_length)]),
+            Step::Pause => locale.format("tutorial.pause", &[("key", &key(keys.pause))]),
+            Step::Resume => locale.format("tutorial.resume", &[("key", &key(keys.pause))]),
+            Step::Crash => locale.format("tutorial.crash", &[]),
+            Step::Restart => locale.format("tutorial.restart", &[("key", &key(keys.restart))]),
+            Step::Done => locale.format("tutorial.done", &[]),
+        }
+    }
+}
+
+// A still, ordinary pellet a few cells ahead of `head`, or in the middle of
+// the board when that would be off the edge.
+pub fn food_ahead(head: Pos, dir: Direction, rules: &Rules) -> Food {
+    let (dx, dy) = dir.offset();
+    let ahead = Pos::new(head.x + dx * FOOD_DISTANCE, head.y + dy * FOOD_DISTANCE);
+    let part = if ahead.on(rules.board) {
+        ahead
+    } else {
+        let (x, y) = rules.board.center();
+        Pos::new(x, y)
+    };
+    Food {
+        part,
+        kind: FoodKind::Normal,
+        behavior: Behavior::Still,
+        timer: 0,
+        lifetime: 0,
+    }
+}
+
+// The tutorial starts by itself until it has been finished once.
+pub fn seen() -> bool {
+    marker_path().is_none_or(|path| path.exists())
+}
+
+pub fn mark_seen() {
+    if let Some(path) = marker_path() {
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, ""));
+        if let Err(e) = written {
+            error!("Could not save tutorial progress: {}", e);
+        }
+    }
+}
+
+fn marker_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("snake_2d").join("tutorial_done"))
+}
@@ -0,0 +1,161 @@
+use crate::render;
+use graphics::Transformed;
+use log::error;
+use opengl_graphics::{GlGraphics, GlyphCache};
+use piston::input::RenderArgs;
+use snake_2d::Pos;
+
+pub const FONT_SIZE: u32 = 16;
+pub const LINE_HEIGHT: f64 = FONT_SIZE as f64 + 6.0;
+const POPUP_SECONDS: f64 = 0.5;
+const TOAST_SECONDS: f64 = 3.0;
+const TOAST_FADE_SECONDS: f64 = 0.5;
+
+pub struct Menu {
+    pub items: Vec<String>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(items: Vec<String>) -> Menu {
+        Menu { items, selected: 0 }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len() - 1);
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    pub fn render(
+        &self,
+        gl: &mut GlGraphics,
+        glyphs: &mut GlyphCache<'static>,
+        arg: &RenderArgs,
+        color: [f32; 4],
+        x: f64,
+        y: f64,
+    ) {
+        for (i, item) in self.items.iter().enumerate() {
+            let marker = if i == self.selected { "> " } else { "  " };
+            let line = format!("{}{}", marker, item);
+            text(gl, glyphs, arg, color, &line, x, y + i as f64 * LINE_HEIGHT);
+        }
+    }
+}
+
+// Text that rises from a board cell and fades out.
+pub struct Popup {
+    pub part: Pos,
+    pub text: String,
+    age: f64,
+}
+
+impl Popup {
+    pub fn new(part: Pos, text: String) -> Popup {
+        Popup {
+            part,
+            text,
+            age: 0.0,
+        }
+    }
+
+    // Returns false once the popup has faded out.
+    pub fn update(&mut self, dt: f64) -> bool {
+        self.age += dt;
+        self.age < POPUP_SECONDS
+    }
+
+    pub fn progress(&self) -> f64 {
+        (self.age / POPUP_SECONDS).min(1.0)
+    }
+}
+
+// A notification along the bottom of the window that fades out.
+pub struct Toast {
+    pub text: String,
+    age: f64,
+}
+
+impl Toast {
+    pub fn new(text: String) -> Toast {
+        Toast { text, age: 0.0 }
+    }
+
+    // Returns false once the toast has faded out.
+    pub fn update(&mut self, dt: f64) -> bool {
+        self.age += dt;
+        self.age < TOAST_SECONDS
+    }
+
+    pub fn alpha(&self) -> f32 {
+        ((TOAST_SECONDS - self.age) / TOAST_FADE_SECONDS).clamp(0.0, 1.0) as f32
+    }
+}
+
+pub fn clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).ceil() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// A line graph of `values` from left to right inside `rect`, scaled so the
+// largest value reaches the top, over a faint frame.
+pub fn graph(
+    gl: &mut GlGraphics,
+    arg: &RenderArgs,
+    rect: [f64; 4],
+    values: &[usize],
+    color: [f32; 4],
+) {
+    let [x, y, width, height] = rect;
+    let top = values.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let step = width / (values.len().max(2) - 1) as f64;
+    let point = |i: usize| {
+        [
+            x + i as f64 * step,
+            y + height * (1.0 - values[i] as f64 / top),
+        ]
+    };
+    let mut frame = color;
+    frame[3] *= 0.2;
+    gl.draw(arg.viewport(), |c, gl| {
+        graphics::rectangle(frame, rect, c.transform, gl);
+        let line = graphics::Line::new(color, 1.0);
+        for i in 1..values.len() {
+            let ([x0, y0], [x1, y1]) = (point(i - 1), point(i));
+            line.draw([x0, y0, x1, y1], &c.draw_state, c.transform, gl);
+        }
+    });
+}
+
+pub fn text(
+    gl: &mut GlGraphics,
+    glyphs: &mut GlyphCache<'static>,
+    arg: &RenderArgs,
+    color: [f32; 4],
+    text: &str,
+    x: f64,
+    y: f64,
+) {
+    let scale = render::scale_factor(arg);
+    let size = (f64::from(FONT_SIZE) * scale).round() as u32;
+
+    gl.draw(arg.viewport(), |c, gl| {
+        let transform = c.transform.trans(x, y).zoom(1.0 / scale);
+        // A string the glyph cache can't draw is left out rather than
+        // taking the game down with it.
+        if let Err(e) = graphics::text(color, size, text, glyphs, transform, gl) {
+            error!("Could not render text '{}': {}", text, e);
+        }
+    });
+}
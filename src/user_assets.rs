@@ -0,0 +1,204 @@
+use crate::audio::{Sound, SOUNDS};
+use crate::config::Colors;
+use crate::theme::{Theme, THEMES};
+use serde::Deserialize;
+use snake_2d::level::Level;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// A theme file: colors named as under [colors] in snake.toml, laid over a
+// built-in theme.
+#[derive(Deserialize)]
+struct ThemeFile {
+    // The built-in theme it starts from, classic by default.
+    base: Option<String>,
+    gradient: Option<f32>,
+    pulse: Option<f32>,
+    #[serde(flatten)]
+    colors: Colors,
+}
+
+// Sounds from a folder under `sounds`, each replacing the built-in one of
+// the same name; the rest stay as they are.
+pub struct SoundPack {
+    pub name: String,
+    pub effects: Vec<(Sound, Arc<[u8]>)>,
+    pub music: Option<Arc<[u8]>>,
+}
+
+// Levels, themes and sound packs players add without building the game,
+// read once at startup from the `snake_2d` data directory
+// (`~/.local/share/snake_2d/` on Linux):
+//
+//   levels/<name>.txt           a layout like the built-in levels
+//   themes/<name>.toml          see `ThemeFile`
+//   sounds/<pack>/<sound>.wav   eat, power_up, death, menu or music
+//
+// A file that doesn't check out is left out, and why is kept in `errors`.
+#[derive(Default)]
+pub struct UserAssets {
+    pub levels: Vec<PathBuf>,
+    pub themes: Vec<Theme>,
+    pub sound_packs: Vec<SoundPack>,
+    pub errors: Vec<String>,
+}
+
+impl UserAssets {
+    pub fn scan() -> UserAssets {
+        let mut assets = UserAssets::default();
+        let dir = match dir() {
+            Some(dir) => dir,
+            None => return assets,
+        };
+        for path in files(&dir.join("levels"), &mut assets.errors) {
+            match check_level(&path) {
+                Ok(()) => assets.levels.push(path),
+                Err(e) => assets.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        for path in files(&dir.join("themes"), &mut assets.errors) {
+            match load_theme(&path, &assets.themes) {
+                Ok(theme) => assets.themes.push(theme),
+                Err(e) => assets.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        for path in files(&dir.join("sounds"), &mut assets.errors) {
+            match load_sound_pack(&path) {
+                Ok(pack) => assets.sound_packs.push(pack),
+                Err(e) => assets.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        assets
+    }
+
+    // The built-in themes followed by the user's.
+    pub fn all_themes(&self) -> Vec<Theme> {
+        THEMES.iter().chain(&self.themes).copied().collect()
+    }
+
+    pub fn sound_pack(&self, name: &str) -> Option<&SoundPack> {
+        self.sound_packs.iter().find(|pack| pack.name == name)
+    }
+}
+
+pub fn dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("snake_2d"))
+}
+
+// The name a level file, theme or sound pack is listed under.
+pub fn name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// Entries of `dir` in name order. A missing folder just has nothing in it.
+fn files(dir: &Path, errors: &mut Vec<String>) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(entry) => paths.push(entry.path()),
+            Err(e) => errors.push(format!("{}: {}", dir.display(), e)),
+        }
+    }
+    paths.sort();
+    paths
+}
+
+fn check_level(path: &Path) -> Result<(), String> {
+    if path.extension().is_none_or(|ext| ext != "txt") {
+        return Err(String::from("Expected a .txt level file"));
+    }
+    let layout = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Level::check(&layout)
+}
+
+fn load_theme(path: &Path, loaded: &[Theme]) -> Result<Theme, String> {
+    if path.extension().is_none_or(|ext| ext != "toml") {
+        return Err(String::from("Expected a .toml theme file"));
+    }
+    let name = name(path);
+    if THEMES.iter().chain(loaded).any(|theme| theme.name == name) {
+        return Err(format!("There already is a theme called {}", name));
+    }
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: ThemeFile = toml::from_str(&contents).map_err(|e| e.to_string())?;
+    let base = file.base.as_deref().unwrap_or(THEMES[0].name);
+    let mut theme = Theme::named(base, &THEMES)
+        .ok_or_else(|| format!("Unknown base theme '{}'", base))?
+        .with_overrides(&file.colors);
+    let channels = [
+        theme.background,
+        theme.snake,
+        theme.snake_player_two,
+        theme.head,
+        theme.head_player_two,
+        theme.food,
+        theme.obstacle,
+        theme.grid_line,
+        theme.text,
+    ];
+    if channels.iter().flatten().any(|c| !(0.0..=1.0).contains(c)) {
+        return Err(String::from("Colors have to be between 0 and 1"));
+    }
+    if let Some(gradient) = file.gradient {
+        theme.skin.gradient = gradient.clamp(0.0, 1.0);
+    }
+    if let Some(pulse) = file.pulse {
+        theme.skin.pulse = pulse.clamp(0.0, 1.0);
+    }
+    // Loaded once and kept for the whole run, like the built-in names.
+    theme.name = Box::leak(name.into_boxed_str());
+    Ok(theme)
+}
+
+fn load_sound_pack(dir: &Path) -> Result<SoundPack, String> {
+    if !dir.is_dir() {
+        return Err(String::from("Expected a folder of .wav files"));
+    }
+    let mut pack = SoundPack {
+        name: name(dir),
+        effects: Vec::new(),
+        music: None,
+    };
+    let mut errors = Vec::new();
+    for path in files(dir, &mut errors) {
+        let sound = name(&path);
+        let file = path.file_name().unwrap_or_default().to_string_lossy();
+        let bytes: Arc<[u8]> = fs::read(&path)
+            .map_err(|e| format!("{}: {}", file, e))?
+            .into();
+        if path.extension().is_none_or(|ext| ext != "wav") || !is_wav(&bytes) {
+            return Err(format!("{} is not a WAV file", file));
+        }
+        match SOUNDS.iter().find(|s| s.name() == sound) {
+            Some(&effect) => pack.effects.push((effect, bytes)),
+            None if sound == "music" => pack.music = Some(bytes),
+            None => {
+                let names: Vec<&str> = SOUNDS.iter().map(|s| s.name()).collect();
+                return Err(format!(
+                    "Unknown sound {}, expected {} or music",
+                    sound,
+                    names.join(", ")
+                ));
+            }
+        }
+    }
+    if let Some(e) = errors.into_iter().next() {
+        return Err(e);
+    }
+    if pack.effects.is_empty() && pack.music.is_none() {
+        return Err(String::from("No sounds in it"));
+    }
+    Ok(pack)
+}
+
+// A RIFF container holding WAVE data.
+fn is_wav(bytes: &[u8]) -> bool {
+    bytes.len() > 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE"
+}
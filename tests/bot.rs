@@ -0,0 +1,76 @@
+mod common;
+
+use common::{game, put_food, rules};
+use snake_2d::bot::{BoardView, Controller};
+use snake_2d::level::Level;
+use snake_2d::simulate::{self, Policy};
+use snake_2d::{DeathCause, Direction, Pos, Snake};
+
+#[test]
+fn the_view_is_written_as_lines_ending_with_end() {
+    let mut game = game(rules(6, 4, 1));
+    game.foods.clear();
+    put_food(&mut game, 3, 0);
+    let body = vec![Pos::new(1, 0), Pos::new(0, 0)];
+    game.players[0].snake = Snake::new(body, Direction::Right);
+
+    let mut out = Vec::new();
+    BoardView::new(&game, 0).write_to(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "board 6 4 walls\n\
+         tick 0\n\
+         you 0\n\
+         snake 0 alive right 1,0 0,0\n\
+         food 3 0\n\
+         end\n"
+    );
+}
+
+// Heads for the first food, going round its own neck if it has to.
+struct Greedy;
+
+impl Controller for Greedy {
+    fn next_move(&mut self, view: &BoardView) -> Direction {
+        let snake = &view.snakes[view.you];
+        let (head, food) = (snake.body[0], view.food[0]);
+        let (dx, dy) = (f64::from(food.x - head.x), f64::from(food.y - head.y));
+        Direction::towards(dx, dy, snake.dir).unwrap_or(snake.dir)
+    }
+}
+
+#[test]
+fn a_controller_can_steer_a_snake_to_food() {
+    let mut game = game(rules(10, 10, 4));
+    game.foods.clear();
+    put_food(&mut game, 5, 7);
+    let mut bot = Greedy;
+    for _ in 0..20 {
+        let dir = bot.next_move(&BoardView::new(&game, 0));
+        game.step(Some(dir));
+    }
+    assert!(game.foods_eaten >= 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn a_bot_process_plays_headless_games() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join(format!("snake_bot_{}.sh", std::process::id()));
+    std::fs::write(
+        &path,
+        "#!/bin/sh\nwhile read line; do [ \"$line\" = end ] && echo down; done\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let policy = Policy::Bot(path.to_string_lossy().into_owned());
+    let stats = simulate::run(rules(8, 8, 2), Level::empty(), 2, policy).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(stats.games, 2);
+    assert_eq!(stats.deaths, vec![(DeathCause::Wall, 2)]);
+
+    let missing = Policy::Bot(String::from("/nonexistent/snake_bot"));
+    assert!(simulate::run(rules(8, 8, 2), Level::empty(), 1, missing).is_err());
+}
@@ -0,0 +1,97 @@
+// The driver shared by the simulation tests: plays input scripts against the
+// core and draws the board as text to compare with expected snapshots.
+#![allow(dead_code)]
+
+use snake_2d::food::{Behavior, Food, FoodKind};
+use snake_2d::level::Level;
+use snake_2d::{Board, Direction, Game, Pos, Rules, TickResult};
+
+// One life, so the first death ends the game.
+pub fn rules(columns: i32, rows: i32, seed: u64) -> Rules {
+    Rules {
+        board: Board::new(columns, rows),
+        seed: Some(seed),
+        lives: 1,
+        ..Rules::default()
+    }
+}
+
+pub fn game(rules: Rules) -> Game {
+    let mut game = Game::new(rules, Level::empty());
+    game.restart();
+    game
+}
+
+pub fn is_over(result: TickResult) -> bool {
+    matches!(
+        result,
+        TickResult::Died(_) | TickResult::Won | TickResult::TimeUp | TickResult::Failed(_)
+    )
+}
+
+// One character per tick: U, D, L or R turns the first snake and anything
+// else keeps it going. Stops when the game ends and returns every tick's
+// result.
+pub fn play(game: &mut Game, script: &str) -> Vec<TickResult> {
+    let mut results = Vec::new();
+    for c in script.chars() {
+        let input = match c {
+            'U' => Some(Direction::Up),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        };
+        let result = game.step(input);
+        results.push(result);
+        if is_over(result) {
+            break;
+        }
+    }
+    results
+}
+
+// The board a row per line: H for a head, o for the rest of a snake, * for
+// food, # for an obstacle and . for an empty cell.
+pub fn snapshot(game: &Game) -> String {
+    let board = game.rules.board;
+    let mut text = String::new();
+    for y in 0..board.rows {
+        for x in 0..board.columns {
+            let pos = Pos::new(x, y);
+            let snake = game
+                .players
+                .iter()
+                .find(|player| player.snake.contains(pos))
+                .map(|player| &player.snake);
+            text.push(match snake {
+                Some(snake) if snake.head() == Ok(pos) => 'H',
+                Some(_) => 'o',
+                None if game.foods.iter().any(|food| food.part == pos) => '*',
+                None if game.level.contains(x, y) => '#',
+                None => '.',
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+pub fn put_food(game: &mut Game, x: i32, y: i32) {
+    game.foods = vec![Food {
+        part: Pos::new(x, y),
+        kind: FoodKind::Normal,
+        behavior: Behavior::Still,
+        timer: 0,
+        lifetime: 0,
+    }];
+}
+
+// Turns every pellet into a plain one that stays put, so each meal grows
+// the snake by exactly one segment.
+pub fn plain_food(game: &mut Game) {
+    for food in &mut game.foods {
+        food.kind = FoodKind::Normal;
+        food.behavior = Behavior::Still;
+    }
+}
@@ -0,0 +1,46 @@
+use snake_2d::i18n::{self, Locale, LOCALES};
+
+// The `{name}` placeholders in a text, in order.
+fn placeholders(text: &str) -> Vec<&str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|part| part.split('}').next())
+        .collect()
+}
+
+#[test]
+fn every_locale_has_the_english_keys_and_placeholders() {
+    let english = i18n::table("en");
+    assert!(!english.is_empty());
+    for &(language, _) in LOCALES.iter() {
+        let table = i18n::table(language);
+        for (key, text) in &english {
+            let translated = table
+                .get(key)
+                .unwrap_or_else(|| panic!("{} has no {}", language, key));
+            let mut expected = placeholders(text);
+            let mut found = placeholders(translated);
+            expected.sort_unstable();
+            found.sort_unstable();
+            assert_eq!(expected, found, "{} {}", language, key);
+        }
+        for key in table.keys() {
+            assert!(english.contains_key(key), "{} has unused {}", language, key);
+        }
+    }
+}
+
+#[test]
+fn lookups_fall_back_from_region_to_language_to_english_to_key() {
+    let locale = Locale::new(&[String::from("de_AT.UTF-8")]);
+    assert_eq!(locale.language, "de");
+    assert_eq!(locale.get("menu.settings"), "Einstellungen");
+
+    let locale = Locale::new(&[String::from("fr-FR"), String::from("ru")]);
+    assert_eq!(locale.language, "ru");
+
+    let locale = Locale::new(&[String::from("fr")]);
+    assert_eq!(locale.language, "en");
+    assert_eq!(locale.format("hud.score", &[("score", &42)]), "Score: 42");
+    assert_eq!(locale.get("no.such.key"), "no.such.key");
+}
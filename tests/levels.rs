@@ -0,0 +1,24 @@
+use snake_2d::level::{Level, BUILTIN_LEVELS};
+
+#[test]
+fn built_in_levels_pass_the_checks_user_levels_get() {
+    for &(name, layout) in BUILTIN_LEVELS.iter() {
+        assert_eq!(Level::check(layout), Ok(()), "{}", name);
+    }
+}
+
+#[test]
+fn malformed_layouts_say_what_is_wrong() {
+    for &(layout, error) in &[
+        ("\n\n", "The layout is empty"),
+        ("..#\n.?.\n", "Line 2, column 2: unexpected '?'"),
+        ("1..\n.1.\n..1\n", "Portal 1 has 3 ends instead of 2"),
+        ("aA.\n...\n..A\n", "Hazard A has more than one start"),
+        (
+            "a.a\n...\n",
+            "The cells of hazard a don't form a single path",
+        ),
+    ] {
+        assert_eq!(Level::check(layout), Err(error.to_string()), "{:?}", layout);
+    }
+}
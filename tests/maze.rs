@@ -0,0 +1,55 @@
+use snake_2d::level::Level;
+use snake_2d::{maze, Board, Game, Rules, TickResult};
+use std::collections::HashSet;
+
+fn walls(level: &Level) -> HashSet<(i32, i32)> {
+    level.obstacles.iter().map(|o| (o.x, o.y)).collect()
+}
+
+#[test]
+fn mazes_are_perfect_and_the_same_for_the_same_seed() {
+    for &board in &[Board::new(20, 20), Board::new(40, 30), Board::new(16, 9)] {
+        for seed in 0..20 {
+            let level = maze::generate(board, seed).unwrap();
+            let walls = walls(&level);
+            // Two-wide rooms three cells apart, with one two-cell passage
+            // fewer than rooms when they form a tree.
+            let rooms = ((board.columns + 1) / 3 * ((board.rows + 1) / 3)) as usize;
+            let open = (board.columns * board.rows) as usize - walls.len();
+            assert_eq!(
+                open,
+                rooms * 4 + (rooms - 1) * 2,
+                "{:?} seed {}",
+                board,
+                seed
+            );
+            for cell in &[(0, 0), (0, 1), (1, 0), (2, 0)] {
+                assert!(!walls.contains(cell), "{:?} seed {}", board, seed);
+            }
+            assert_eq!(walls, self::walls(&maze::generate(board, seed).unwrap()));
+        }
+    }
+    assert!(maze::generate(Board::new(4, 20), 1).is_err());
+}
+
+#[test]
+fn the_snake_starts_heading_down_the_first_corridor() {
+    let rules = Rules {
+        seed: Some(3),
+        level: Some(maze::spec(3)),
+        ..Rules::default()
+    };
+    let level = Level::load(&maze::spec(3), rules.board).unwrap();
+    let walls = walls(&level);
+    let mut game = Game::new(rules, level);
+    assert!(game
+        .foods
+        .iter()
+        .all(|f| !walls.contains(&(f.part.x, f.part.y))));
+    for _ in 0..3 {
+        assert!(matches!(
+            game.step(None),
+            TickResult::Moved | TickResult::Ate(_)
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+// Invariants checked over many seeded games, with random input scripts or
+// the AI steering.
+mod common;
+
+use common::{game, is_over, plain_food, rules};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use snake_2d::ai::Difficulty;
+use snake_2d::{BoardMode, DeathCause, Direction, Pos, TickResult};
+use std::collections::HashSet;
+
+const GAMES: u64 = 50;
+const MAX_TICKS: usize = 2_000;
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+// A turn on roughly one tick in three.
+fn random_input(rng: &mut Pcg32) -> Option<Direction> {
+    match rng.gen_range(0, 3) {
+        0 => Some(DIRECTIONS[rng.gen_range(0, DIRECTIONS.len())]),
+        _ => None,
+    }
+}
+
+#[test]
+fn the_snake_is_two_segments_plus_one_per_food_eaten() {
+    for seed in 0..GAMES {
+        let mut game = game(rules(10, 10, seed));
+        game.players[0].ai = Some(Difficulty::Hard);
+        plain_food(&mut game);
+        for _ in 0..MAX_TICKS {
+            let result = game.step(None);
+            plain_food(&mut game);
+            let snake = &game.players[0].snake;
+            assert_eq!(
+                snake.len() + snake.growth,
+                2 + game.foods_eaten as usize,
+                "seed {} tick {}",
+                seed,
+                game.tick
+            );
+            if is_over(result) {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn food_never_spawns_on_a_snake() {
+    for seed in 0..GAMES {
+        let mut game = game(rules(8, 8, seed));
+        game.players[0].ai = Some(Difficulty::Normal);
+        for _ in 0..MAX_TICKS {
+            let before: HashSet<Pos> = game.foods.iter().map(|food| food.part).collect();
+            let result = game.step(None);
+            // Food goes down before the snakes move, so the head may have
+            // moved onto a new pellet since; the rest of the body can't.
+            let snake = &game.players[0].snake;
+            for food in game
+                .foods
+                .iter()
+                .filter(|food| !before.contains(&food.part))
+            {
+                assert!(
+                    !snake.segments().skip(1).any(|part| part == food.part),
+                    "seed {} tick {}: food at {:?}",
+                    seed,
+                    game.tick,
+                    food.part
+                );
+            }
+            if is_over(result) {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn running_into_itself_ends_the_game_on_the_same_tick() {
+    for seed in 0..GAMES {
+        let mut rules = rules(10, 10, seed);
+        rules.board_mode = BoardMode::Wrap;
+        let mut game = game(rules);
+        let mut rng = Pcg32::seed_from_u64(seed);
+        for _ in 0..MAX_TICKS {
+            // Ghost pellets would let the snake pass through itself.
+            plain_food(&mut game);
+            let result = game.step(random_input(&mut rng));
+            let snake = &game.players[0].snake;
+            let head = snake.head().unwrap();
+            let overlaps = snake.segments().skip(1).any(|part| part == head);
+            assert_eq!(
+                overlaps,
+                result == TickResult::Died(DeathCause::SelfCollision),
+                "seed {} tick {}",
+                seed,
+                game.tick
+            );
+            if is_over(result) {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{game, play, put_food, rules, snapshot};
+use snake_2d::metrics::RunMetrics;
+use snake_2d::{BoardMode, DeathCause, Direction, Pos, Rules, Snake, Spawn, TickResult};
+
+#[test]
+fn a_scripted_game_matches_its_board_snapshots() {
+    let mut game = game(Rules {
+        spawn: Spawn::Corner,
+        ..rules(6, 4, 1)
+    });
+    put_food(&mut game, 3, 0);
+    assert_eq!(
+        snapshot(&game),
+        "H..*..\n\
+         o.....\n\
+         ......\n\
+         ......\n"
+    );
+
+    play(&mut game, "...");
+    assert_eq!(
+        snapshot(&game),
+        "..oH..\n\
+         ......\n\
+         ......\n\
+         ......\n"
+    );
+
+    let results = play(&mut game, ".DD");
+    assert!(matches!(results[0], TickResult::Ate(_)));
+    assert_eq!(
+        snapshot(&game),
+        "....o.\n\
+         .**.o.\n\
+         ....H.\n\
+         ......\n"
+    );
+}
+
+#[test]
+fn turning_into_the_body_ends_the_game_on_that_tick() {
+    let mut game = game(rules(8, 5, 2));
+    game.foods.clear();
+    let body = (0..5).rev().map(|x| Pos::new(x, 1)).collect();
+    game.players[0].snake = Snake::new(body, Direction::Right);
+
+    let results = play(&mut game, "DLU..");
+    assert_eq!(
+        results,
+        vec![
+            TickResult::Moved,
+            TickResult::Moved,
+            TickResult::Died(DeathCause::SelfCollision),
+        ]
+    );
+}
+
+#[test]
+fn run_metrics_keep_a_bounded_length_history() {
+    let mut rules = rules(10, 10, 3);
+    rules.board_mode = BoardMode::Wrap;
+    let mut game = game(rules);
+    game.foods.clear();
+    let mut metrics = RunMetrics::new(0);
+    for _ in 0..1000 {
+        game.step(None);
+        metrics.observe(&game);
+    }
+    let lengths = metrics.lengths();
+    assert!(lengths.len() > 120 && lengths.len() <= 240);
+    assert_eq!(lengths[0], 2);
+    assert_eq!(metrics.max_combo, 0);
+    assert_eq!(metrics.average_speed(), game.speed() as f64);
+}
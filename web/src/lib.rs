@@ -0,0 +1,220 @@
+use snake_2d::level::{Level, HAZARD_COLOR};
+use snake_2d::{Board, Direction, Game, Locale, Pos, Rules, TickResult};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+const BACKGROUND: [f32; 4] = [0.0, 0.5, 0.2, 1.0];
+const SNAKE: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const SNAKE_PLAYER_TWO: [f32; 4] = [0.2, 0.4, 1.0, 1.0];
+const HEAD: [f32; 4] = [0.75, 0.0, 0.0, 1.0];
+const FOOD: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const OBSTACLE: [f32; 4] = [0.4, 0.4, 0.4, 1.0];
+const OVERLAY: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const TEXT: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Playing,
+    Paused,
+    GameOver,
+}
+
+// The game on a 2D canvas. The page drives it: update() and render() from
+// requestAnimationFrame, key() from keydown events.
+#[wasm_bindgen]
+pub struct WebGame {
+    game: Game,
+    context: CanvasRenderingContext2d,
+    size: [f64; 2],
+    cell_size: f64,
+    accumulator: f64,
+    state: State,
+    locale: Locale,
+}
+
+#[wasm_bindgen]
+impl WebGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, columns: i32, rows: i32) -> Result<WebGame, JsValue> {
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("Canvas has no 2d context"))?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        let rules = Rules {
+            board: Board::new(columns, rows),
+            ..Rules::default()
+        }
+        .validated();
+        let size = [f64::from(canvas.width()), f64::from(canvas.height())];
+        let cell_size = (size[0] / f64::from(rules.board.columns))
+            .min(size[1] / f64::from(rules.board.rows))
+            .floor();
+
+        let mut game = Game::new(rules, Level::empty());
+        game.restart();
+        Ok(WebGame {
+            game,
+            context,
+            size,
+            cell_size,
+            accumulator: 0.0,
+            state: State::Playing,
+            locale: Locale::default(),
+        })
+    }
+
+    // Takes a language tag such as navigator.language; English until then.
+    pub fn set_language(&mut self, language: &str) {
+        self.locale = Locale::new(&[String::from(language)]);
+    }
+
+    // Advances the game by `dt` seconds.
+    pub fn update(&mut self, dt: f64) {
+        if self.state != State::Playing {
+            return;
+        }
+
+        self.accumulator += dt;
+        while self.state == State::Playing && self.accumulator >= self.game.tick_interval() {
+            self.accumulator -= self.game.tick_interval();
+            if let TickResult::Died(_)
+            | TickResult::Won
+            | TickResult::TimeUp
+            | TickResult::Failed(_) = self.game.step(None)
+            {
+                self.state = State::GameOver;
+            }
+        }
+    }
+
+    // Takes a KeyboardEvent.key value and returns whether the game used it,
+    // so the page can prevent scrolling on arrow keys.
+    pub fn key(&mut self, key: &str) -> bool {
+        let dir = match key {
+            "ArrowUp" | "w" | "W" => Some(Direction::Up),
+            "ArrowDown" | "s" | "S" => Some(Direction::Down),
+            "ArrowLeft" | "a" | "A" => Some(Direction::Left),
+            "ArrowRight" | "d" | "D" => Some(Direction::Right),
+            _ => None,
+        };
+
+        match (self.state, key, dir) {
+            (State::Playing, _, Some(dir)) => self.game.players[0].steer(dir),
+            (State::Playing, "p", _) | (State::Playing, "P", _) => self.state = State::Paused,
+            (State::Paused, "p", _) | (State::Paused, "P", _) => self.state = State::Playing,
+            (State::GameOver, " ", _) => {
+                self.game.restart();
+                self.accumulator = 0.0;
+                self.state = State::Playing;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render(&self) {
+        let ctx = &self.context;
+        self.fill(OVERLAY, [0.0, 0.0, self.size[0], self.size[1]]);
+        let Board { columns, rows } = self.game.rules.board;
+        self.fill(
+            BACKGROUND,
+            [
+                0.0,
+                0.0,
+                f64::from(columns) * self.cell_size,
+                f64::from(rows) * self.cell_size,
+            ],
+        );
+
+        for obstacle in &self.game.level.obstacles {
+            self.cell(
+                Pos {
+                    x: obstacle.x,
+                    y: obstacle.y,
+                },
+                OBSTACLE,
+            );
+        }
+        for (i, portal) in self.game.level.portals.iter().enumerate() {
+            for &(x, y) in &portal.ends {
+                self.cell(Pos { x, y }, Level::portal_color(i));
+            }
+        }
+        for hazard in self.game.hazards() {
+            self.cell(hazard, HAZARD_COLOR);
+        }
+        for food in &self.game.foods {
+            let mut color = food.kind.color(FOOD);
+            color[3] *= (0.25 + 0.75 * food.remaining()) as f32;
+            self.cell(food.part, color);
+        }
+        for (i, player) in self.game.players.iter().enumerate() {
+            let color = if i == 0 { SNAKE } else { SNAKE_PLAYER_TWO };
+            for (j, part) in player.snake.segments().enumerate().rev() {
+                self.cell(part, if j == 0 { HEAD } else { color });
+            }
+        }
+
+        let player = &self.game.players[0];
+        let locale = &self.locale;
+        ctx.set_fill_style_str(&css(TEXT));
+        ctx.set_font("16px sans-serif");
+        let hud = [
+            locale.format("hud.score", &[("score", &player.score.points)]),
+            locale.format("hud.lives", &[("lives", &player.lives)]),
+            locale.format("hud.speed", &[("speed", &self.game.speed())]),
+        ]
+        .join("  ");
+        let _ = ctx.fill_text(&hud, 5.0, 20.0);
+
+        let message = match self.state {
+            State::Playing => return,
+            State::Paused => locale.get("web.paused"),
+            State::GameOver if self.game.won => locale.get("web.won"),
+            State::GameOver => locale.get("web.game_over"),
+        };
+        self.fill(OVERLAY, [0.0, 0.0, self.size[0], self.size[1]]);
+        ctx.set_fill_style_str(&css(TEXT));
+        let _ = ctx.fill_text(message, 20.0, self.size[1] / 2.0);
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game.players[0].score.points
+    }
+
+    pub fn game_over(&self) -> bool {
+        self.state == State::GameOver
+    }
+}
+
+impl WebGame {
+    fn cell(&self, part: Pos, color: [f32; 4]) {
+        let size = self.cell_size;
+        self.fill(
+            color,
+            [
+                f64::from(part.x) * size,
+                f64::from(part.y) * size,
+                size,
+                size,
+            ],
+        );
+    }
+
+    fn fill(&self, color: [f32; 4], rect: [f64; 4]) {
+        self.context.set_fill_style_str(&css(color));
+        self.context.fill_rect(rect[0], rect[1], rect[2], rect[3]);
+    }
+}
+
+fn css(color: [f32; 4]) -> String {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "rgba({}, {}, {}, {})",
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        color[3]
+    )
+}